@@ -5,11 +5,29 @@ pub(crate) fn get_refactor_args(m: &ArgMatches, deps: &[String]) -> RefactorArgs
     RefactorArgs {
         file: m.value_of("file").unwrap().to_string(),
         refactoring: m.value_of("refactoring").unwrap().to_string(),
-        selection: SelectionType::Range(m.value_of("selection").unwrap().to_string()),
+        selection: get_selection(m.value_of("selection").unwrap(), m.value_of("columns") == Some("utf16")),
         unsafe_: m.is_present("unsafe"),
         deps: deps.to_vec(),
         add_comment: false,
-        with_changes: vec![]
+        with_changes: vec![],
+        options: m.values_of("option").map(|values| values.map(get_option).collect()).unwrap_or_default()
+    }
+}
+/// `<name>=<value>`, or `<name>` which is the same as `<name>=true`
+fn get_option(option: &str) -> (String, String) {
+    match option.find('=') {
+        Some(i) => (option[..i].to_string(), option[i + 1..].to_string()),
+        None => (option.to_string(), "true".to_string())
+    }
+}
+/// `<line>:<column>-<line>:<column>` or `<byte start>:<byte end>`
+fn get_selection(selection: &str, utf16: bool) -> SelectionType {
+    if !selection.contains('-') {
+        SelectionType::Range(selection.to_string())
+    } else if utf16 {
+        SelectionType::LineColumnUtf16(selection.to_string())
+    } else {
+        SelectionType::LineColumn(selection.to_string())
     }
 }
 pub(crate) fn get_candidate_args(m: &ArgMatches, deps: &[String]) -> CandidateArgs {
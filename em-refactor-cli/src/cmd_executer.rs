@@ -1,6 +1,7 @@
 use itertools::Itertools;
-use em_refactor_lib_types::{RefactorOutputs, RefactorOutputs2};
+use em_refactor_lib_types::{FileStringReplacement, RefactorOutputs, RefactorOutputs2, RustcInvocation};
 use std::process::Command;
+use em_refactor_lib_types::defs::ENV_CAPTURE_RUSTC_INVOCATION;
 use super::{InvocationError, InvocationResult};
 
 static DRIVER_NAME: &str = "em-refactor-driver";
@@ -66,14 +67,17 @@ pub(crate) fn get_metadata() -> InvocationResult<Metadata> {
     Ok(metadata)
 }
 
-pub(crate) fn run_refactoring_cmd(target_dir: Option<&str>, env_args: (String, String)) -> InvocationResult<RefactorOutputs2> {
-    
+fn driver_path() -> std::path::PathBuf {
     let mut path = std::env::current_exe()
         .expect("current executable path invalid")
         .with_file_name(DRIVER_NAME);
     if cfg!(windows) {
         path.set_extension("exe");
     }
+    path
+}
+
+fn run_cargo_check(target_dir: Option<&str>, env_args: (String, String)) -> InvocationResult<String> {
     let mut args = vec!["check".to_owned(), "-j".to_owned(), "1".to_owned(), "--quiet".to_owned(), "--all-targets".to_owned()];
 
     if let Some(arg) = target_dir {
@@ -82,21 +86,61 @@ pub(crate) fn run_refactoring_cmd(target_dir: Option<&str>, env_args: (String, S
 
     let output = Command::new("cargo")
         .args(&args)
-        .env("RUSTC_WRAPPER", path)
+        .env("RUSTC_WRAPPER", driver_path())
         .env(env_args.0, env_args.1)
         .stdout(std::process::Stdio::piped())
         // .stderr(std::process::Stdio::piped())
         .output()?;
     
     if output.status.success() {
-        let s = std::str::from_utf8(output.stdout.as_slice()).unwrap();
-        
-        Ok(combine_output(s))
+        Ok(std::str::from_utf8(output.stdout.as_slice()).unwrap().to_string())
     } else {
         Err(InvocationError::new(std::str::from_utf8(output.stderr.as_slice()).unwrap().to_string()))
     }
 }
 
+pub(crate) fn run_refactoring_cmd(target_dir: Option<&str>, env_args: (String, String)) -> InvocationResult<RefactorOutputs2> {
+    let s = run_cargo_check(target_dir, env_args)?;
+
+    Ok(combine_output(&s))
+}
+
+///
+/// Runs cargo check once and records how cargo invoked rustc for each of the local crates
+///
+pub(crate) fn capture_rustc_invocations(target_dir: Option<&str>) -> InvocationResult<Vec<RustcInvocation>> {
+    let s = run_cargo_check(target_dir, (ENV_CAPTURE_RUSTC_INVOCATION.to_owned(), "1".to_owned()))?;
+
+    Ok(s.lines()
+        .filter_map(|line| serde_json::from_str::<RustcInvocation>(&line).ok())
+        .collect::<Vec<_>>())
+}
+
+///
+/// Runs the driver directly (without cargo) for each of the captured invocations
+///
+pub(crate) fn run_invocations_cmd(invocations: &[RustcInvocation], env_args: (String, String)) -> InvocationResult<RefactorOutputs2> {
+    let mut s = String::new();
+    for invocation in invocations {
+        let mut cmd = Command::new(driver_path());
+        cmd.args(invocation.args.iter().skip(1))
+            .current_dir(&invocation.cwd)
+            .envs(invocation.env.iter().map(|(k, v)| (k, v)))
+            .env(&env_args.0, &env_args.1)
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
+        let output = cmd.output()?;
+
+        if !output.status.success() {
+            return Err(InvocationError::from_output(&cmd, &output));
+        }
+        s.push_str(std::str::from_utf8(output.stdout.as_slice()).unwrap());
+        s.push('\n');
+    }
+
+    Ok(combine_output(&s))
+}
+
 fn combine_output(s: &str) -> RefactorOutputs2 {
 
     let outputs = s
@@ -107,23 +151,31 @@ fn combine_output(s: &str) -> RefactorOutputs2 {
         .collect::<Vec<_>>();
 
     let mut output2 = RefactorOutputs2::empty();
-    let mut replacements = vec![];
+    // Composite refactorings output one RefactorOutput per step, and the changes of each step must be applied in order
+    let mut steps: Vec<Vec<FileStringReplacement>> = vec![];
     let mut some_has_no_non_errors = false;
 
     for o in outputs {
         output2.candidates.extend(o.candidates.into_iter().flat_map(|c| c.candidates));
-        replacements.extend(o.refactorings.iter().flat_map(|c| c.replacements.clone()));
+        for (i, refactoring) in o.refactorings.iter().enumerate() {
+            if steps.len() <= i {
+                steps.push(vec![]);
+            }
+            steps[i].extend(refactoring.replacements.clone());
+        }
         let errors = o.refactorings.into_iter().flat_map(|c| c.errors).collect::<Vec<_>>();
         some_has_no_non_errors = some_has_no_non_errors || !errors.iter().any(|p| !p.is_error);
         output2.errors.extend(errors);
     }
     output2.candidates = output2.candidates.into_iter().unique().sorted().collect::<Vec<_>>();
-    let changes = replacements.into_iter()
-        .unique()
-        .sorted_by_key(|p| -(p.byte_start as i32))
-        .collect::<Vec<_>>();
-    if !changes.is_empty() {
-        output2.changes.push(changes);    
+    for replacements in steps {
+        let changes = replacements.into_iter()
+            .unique()
+            .sorted_by_key(|p| -(p.byte_start as i32))
+            .collect::<Vec<_>>();
+        if !changes.is_empty() {
+            output2.changes.push(changes);
+        }
     }
     if some_has_no_non_errors {
         output2.errors = output2.errors.into_iter().filter(|e| e.is_error).unique().sorted().collect::<Vec<_>>();
@@ -0,0 +1,112 @@
+use em_refactor_lib_types::{*, defs::*};
+use std::io::{BufRead, Write};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use super::*;
+
+///
+/// Long running mode which reads one DaemonRequest per line from stdin and writes one RefactorOutputs2 per line to stdout.
+///
+/// Cargo is only used to find out how rustc is invoked for each of the local crates. These invocations are cached
+/// and the driver is then invoked directly for each request. The cache is invalidated when a source file
+/// or a manifest in the workspace changes.
+///
+pub(crate) struct Daemon<'a> {
+    target_dir: Option<&'a str>,
+    metadata: Metadata,
+    invocations: Vec<RustcInvocation>,
+    fingerprint: Option<Vec<(PathBuf, SystemTime)>>
+}
+
+impl<'a> Daemon<'a> {
+    pub(crate) fn new(metadata: Metadata, target_dir: Option<&'a str>) -> Self {
+        Self {
+            target_dir,
+            metadata,
+            invocations: vec![],
+            fingerprint: None
+        }
+    }
+
+    pub(crate) fn run(&mut self) -> InvocationResult<()> {
+        let stdin = std::io::stdin();
+        let stdout = std::io::stdout();
+
+        for line in stdin.lock().lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let output = match serde_json::from_str::<DaemonRequest>(&line) {
+                Ok(DaemonRequest::Shutdown) => break,
+                Ok(request) => self.handle_request(&request).unwrap_or_else(|e| internal_error(e, request_refactoring(&request))),
+                Err(e) => internal_error(InvocationError::new(e.to_string()), "")
+            };
+            let mut out = stdout.lock();
+            writeln!(out, "{}", serialize(&output)?)?;
+            out.flush()?;
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: &DaemonRequest) -> InvocationResult<RefactorOutputs2> {
+        self.refresh()?;
+        let deps = &self.metadata.dependency_names;
+
+        let env_args = match request {
+            DaemonRequest::Candidates { refactoring } => {
+                (ENV_CANDIDATE_ARGS.to_owned(), serialize(&CandidateArgs {
+                    refactoring: refactoring.to_string(),
+                    deps: deps.to_vec()
+                })?)
+            },
+            DaemonRequest::Refactor { refactoring, file, selection, unsafe_, options } => {
+                (ENV_REFACTORING_ARGS.to_owned(), serialize(&RefactorArgs {
+                    refactoring: refactoring.to_string(),
+                    selection: selection.clone(),
+                    file: file.to_string(),
+                    unsafe_: *unsafe_,
+                    deps: deps.to_vec(),
+                    add_comment: false,
+                    with_changes: vec![],
+                    options: options.clone()
+                })?)
+            },
+            DaemonRequest::Shutdown => unreachable!()
+        };
+
+        run_invocations_cmd(&self.invocations, env_args)
+    }
+
+    ///
+    /// Captures the rustc invocations again if this is the first request, or if any file has changed since they were captured
+    ///
+    fn refresh(&mut self) -> InvocationResult<()> {
+        let fingerprint = get_fingerprint(Path::new("."), self.target_dir);
+        if self.fingerprint.as_ref() == Some(&fingerprint) {
+            return Ok(());
+        }
+        self.metadata = get_metadata()?;
+        clean_local_targets(&self.metadata, self.target_dir)?;
+        self.invocations = capture_rustc_invocations(self.target_dir)?;
+        self.fingerprint = Some(fingerprint);
+        Ok(())
+    }
+}
+
+fn request_refactoring(request: &DaemonRequest) -> &str {
+    match request {
+        DaemonRequest::Candidates { refactoring } | DaemonRequest::Refactor { refactoring, .. } => refactoring,
+        DaemonRequest::Shutdown => ""
+    }
+}
+
+fn internal_error(err: InvocationError, refactoring: &str) -> RefactorOutputs2 {
+    RefactorOutputs2::from_error(RefactoringError {
+        is_error: true,
+        kind: RefactorErrorType::Internal,
+        message: err.message,
+        codes: vec![],
+        at_refactoring: refactoring.to_string()
+    })
+}
@@ -14,7 +14,7 @@ extern crate rustc_typeck;
 
 use std::process::exit;
 use em_refactor_lib::candidates::query_candidates::list_candidates_and_print_result;
-use em_refactor_lib::refactoring_invocation::{get_candidate_args, get_compiler_args, get_refactor_args, pass_to_rustc, run_refactoring_and_output_result, should_pass_to_rustc};
+use em_refactor_lib::refactoring_invocation::{get_candidate_args, get_compiler_args, get_refactor_args, get_rustc_invocation, pass_to_rustc, run_refactoring_and_output_result, should_pass_to_rustc};
 
 ///
 /// 1. Run rustc with refactoring callbacks
@@ -29,6 +29,12 @@ fn run_rustc() -> Result<(), i32> {
         return Ok(());
     }
 
+    if let Some(invocation) = get_rustc_invocation(&std_env_args) {
+        print!("{}", serde_json::to_string(&invocation).unwrap());
+        pass_to_rustc(&rustc_args);
+        return Ok(());
+    }
+
     if let Some(args) = get_candidate_args() {
         list_candidates_and_print_result(&args, &rustc_args);
         return Ok(());
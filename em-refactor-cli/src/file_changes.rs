@@ -0,0 +1,233 @@
+use em_refactor_lib_types::RefactorOutputs2;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use super::{InvocationError, InvocationResult};
+
+const DIFF_CONTEXT: usize = 3;
+
+pub(crate) struct FileChange {
+    pub file_name: String,
+    pub old_content: String,
+    pub new_content: String
+}
+
+///
+/// Applies the changes of each step (in order) to the content of the files on disk
+///
+pub(crate) fn get_file_changes(output: &RefactorOutputs2) -> InvocationResult<Vec<FileChange>> {
+    let mut files = BTreeMap::new();
+
+    for changes in &output.changes {
+        // The changes of a step are sorted by descending byte_start
+        for change in changes {
+            if !files.contains_key(&change.file_name) {
+                // A file which does not exist is created by inserting its content
+                let content = match std::fs::read_to_string(&change.file_name) {
+                    Err(_) if !Path::new(&change.file_name).exists() && change.byte_end == 0 => String::new(),
+                    content => content.map_err(|e| InvocationError::new(format!("{}: {}", change.file_name, e)))?
+                };
+                files.insert(change.file_name.to_string(), (content.to_string(), content));
+            }
+            let (_, content) = files.get_mut(&change.file_name).unwrap();
+            content.replace_range(change.byte_start as usize..change.byte_end as usize, &change.replacement);
+        }
+    }
+
+    Ok(files.into_iter().map(|(file_name, (old_content, new_content))| FileChange {
+        file_name,
+        old_content,
+        new_content
+    }).collect())
+}
+
+///
+/// Writes the changed files, but only if none of them have changed since `fingerprint` was taken.
+/// The new content is first written to a temporary file next to each file, which then replaces the file.
+///
+pub(crate) fn write_file_changes(changes: &[FileChange], fingerprint: &[(PathBuf, SystemTime)]) -> InvocationResult<()> {
+    for change in changes {
+        let path = Path::new(".").join(&change.file_name);
+        let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let unchanged = if modified.is_none() && change.old_content.is_empty() {
+            !fingerprint.iter().any(|(p, _)| p == &path)
+        } else {
+            fingerprint.iter().any(|(p, t)| p == &path && Some(*t) == modified)
+                && std::fs::read_to_string(&path).ok().as_ref() == Some(&change.old_content)
+        };
+        if !unchanged {
+            return Err(InvocationError::new(format!("{} has changed since the refactoring was computed, no changes were written", change.file_name)));
+        }
+    }
+
+    let mut tmp_files = vec![];
+    for change in changes {
+        let path = Path::new(".").join(&change.file_name);
+        let tmp_path = path.with_file_name(format!(".{}.em-refactor.tmp", path.file_name().unwrap().to_string_lossy()));
+        if let Err(e) = std::fs::create_dir_all(path.parent().unwrap()).and_then(|_| std::fs::write(&tmp_path, &change.new_content)) {
+            for (tmp_path, _) in &tmp_files {
+                let _ = std::fs::remove_file(tmp_path);
+            }
+            return Err(e.into());
+        }
+        tmp_files.push((tmp_path, path));
+    }
+    for (tmp_path, path) in tmp_files {
+        std::fs::rename(tmp_path, path)?;
+    }
+    Ok(())
+}
+
+///
+/// The modification times of all .rs files and manifests in the workspace, excluding the target directories
+///
+pub(crate) fn get_fingerprint(dir: &Path, target_dir: Option<&str>) -> Vec<(PathBuf, SystemTime)> {
+    let mut ret = vec![];
+    let target_dir = target_dir.and_then(|d| Path::new(d).canonicalize().ok());
+    collect_fingerprint(dir, &target_dir, &mut ret);
+    ret.sort();
+    ret
+}
+
+fn collect_fingerprint(dir: &Path, target_dir: &Option<PathBuf>, ret: &mut Vec<(PathBuf, SystemTime)>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
+        if path.is_dir() {
+            if name == "target" || name.starts_with('.') || path.canonicalize().ok() == *target_dir {
+                continue;
+            }
+            collect_fingerprint(&path, target_dir, ret);
+        } else if name.ends_with(".rs") || name == "Cargo.toml" || name == "Cargo.lock" {
+            if let Ok(modified) = entry.metadata().and_then(|m| m.modified()) {
+                ret.push((path, modified));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DiffOp {
+    Equal,
+    Delete,
+    Insert
+}
+
+///
+/// Formats the changes as a unified diff (as produced by `diff -u` / `git diff`)
+///
+pub(crate) fn unified_diff(changes: &[FileChange]) -> String {
+    let mut ret = String::new();
+    for change in changes.iter().filter(|c| c.old_content != c.new_content) {
+        ret.push_str(&format!("--- a/{}\n+++ b/{}\n", change.file_name, change.file_name));
+        ret.push_str(&diff_hunks(&split_lines(&change.old_content), &split_lines(&change.new_content)));
+    }
+    ret
+}
+
+/// Splits the lines, keeping the line endings so that a missing newline at the end of the file is a change
+fn split_lines(s: &str) -> Vec<&str> {
+    let mut lines = vec![];
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if c == '\n' {
+            lines.push(&s[start..=i]);
+            start = i + 1;
+        }
+    }
+    if start < s.len() {
+        lines.push(&s[start..]);
+    }
+    lines
+}
+
+///
+/// Longest common subsequence of the lines, after the common prefix and suffix is removed.
+/// Returns the operations in order, together with the index of the line in `old` (Equal, Delete) or `new` (Insert)
+///
+fn diff_lines(old: &[&str], new: &[&str]) -> Vec<(DiffOp, usize)> {
+    let prefix = old.iter().zip(new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+
+    let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] { lcs[i + 1][j + 1] + 1 } else { lcs[i + 1][j].max(lcs[i][j + 1]) };
+        }
+    }
+
+    let mut ops = (0..prefix).map(|i| (DiffOp::Equal, i)).collect::<Vec<_>>();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            ops.push((DiffOp::Equal, prefix + i));
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            ops.push((DiffOp::Delete, prefix + i));
+            i += 1;
+        } else {
+            ops.push((DiffOp::Insert, prefix + j));
+            j += 1;
+        }
+    }
+    ops.extend((old.len() - suffix..old.len()).map(|i| (DiffOp::Equal, i)));
+    ops
+}
+
+fn diff_hunks(old: &[&str], new: &[&str]) -> String {
+    let ops = diff_lines(old, new);
+
+    // The line number in old and new before each op
+    let mut positions = vec![];
+    let (mut old_pos, mut new_pos) = (0, 0);
+    for (op, _) in &ops {
+        positions.push((old_pos, new_pos));
+        match op {
+            DiffOp::Equal => { old_pos += 1; new_pos += 1; },
+            DiffOp::Delete => old_pos += 1,
+            DiffOp::Insert => new_pos += 1
+        }
+    }
+
+    let changed = (0..ops.len()).filter(|i| ops[*i].0 != DiffOp::Equal).collect::<Vec<_>>();
+    let mut ret = String::new();
+    let mut k = 0;
+    while k < changed.len() {
+        let start = changed[k].saturating_sub(DIFF_CONTEXT);
+        let mut last = changed[k];
+        while k + 1 < changed.len() && changed[k + 1] - last <= 2 * DIFF_CONTEXT {
+            k += 1;
+            last = changed[k];
+        }
+        let end = (last + 1 + DIFF_CONTEXT).min(ops.len());
+        k += 1;
+
+        let old_count = ops[start..end].iter().filter(|(op, _)| *op != DiffOp::Insert).count();
+        let new_count = ops[start..end].iter().filter(|(op, _)| *op != DiffOp::Delete).count();
+        let (old_start, new_start) = positions[start];
+        ret.push_str(&format!("@@ -{},{} +{},{} @@\n",
+            if old_count == 0 { old_start } else { old_start + 1 }, old_count,
+            if new_count == 0 { new_start } else { new_start + 1 }, new_count));
+
+        for (op, i) in &ops[start..end] {
+            let (prefix, line) = match op {
+                DiffOp::Equal => (' ', old[*i]),
+                DiffOp::Delete => ('-', old[*i]),
+                DiffOp::Insert => ('+', new[*i])
+            };
+            ret.push(prefix);
+            ret.push_str(line);
+            if !line.ends_with('\n') {
+                ret.push_str("\n\\ No newline at end of file\n");
+            }
+        }
+    }
+    ret
+}
@@ -1,12 +1,16 @@
 use arg_mappings::*;
 use clap::{Arg, App, AppSettings, SubCommand};
 use cmd_executer::*;
+use daemon::*;
+use file_changes::*;
 use invocation_error::*;
 use itertools::Itertools;
 use em_refactor_lib_types::{*, defs::*};
 
 mod arg_mappings;
 mod cmd_executer;
+mod daemon;
+mod file_changes;
 mod invocation_error;
 
 fn app<'a, 'b>() -> App<'a, 'b> {
@@ -23,6 +27,15 @@ fn app<'a, 'b>() -> App<'a, 'b> {
     .arg(Arg::with_name("single-file")
         .long("single-file")
         .help("Output the changed file instead of the diff's. Asserts that only a single file was changed."))
+    .arg(Arg::with_name("output")
+        .long("output")
+        .help("Output the result as json, or the changes as a unified diff")
+        .takes_value(true)
+        .possible_values(&["json", "diff"])
+        .default_value("json"))
+    .arg(Arg::with_name("apply")
+        .long("apply")
+        .help("Write the changes to the files. Nothing is written if a file was modified while the refactoring was computed."))
      .subcommand(
          SubCommand::with_name("refactor")
             .arg(Arg::with_name("refactoring")
@@ -34,12 +47,24 @@ fn app<'a, 'b>() -> App<'a, 'b> {
                 .required(true)
                 .takes_value(true))
             .arg(Arg::with_name("selection")
-                .help("Selection on the format <byte start>:<byte end>. E.g.: 10:20")
+                .help("Selection on the format <byte start>:<byte end> or <line>:<column>-<line>:<column> (1-based). E.g.: 10:20 or 12:5-18:20")
                 .required(true)
                 .takes_value(true))
+            .arg(Arg::with_name("columns")
+                .long("columns")
+                .help("How the columns of a <line>:<column> selection are counted. utf16 is used by e.g. LSP")
+                .takes_value(true)
+                .possible_values(&["utf8", "utf16"])
+                .default_value("utf8"))
             .arg(Arg::with_name("unsafe")
                 .long("unsafe")
-                .help("Skips the recompile check")))
+                .help("Skips the recompile check"))
+            .arg(Arg::with_name("option")
+                .long("option")
+                .help("Option of the refactoring on the format <name>=<value>, e.g. --option name=foo. May be repeated")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)))
     .subcommand(
         SubCommand::with_name("candidates")
             .arg(Arg::with_name("refactoring")
@@ -49,6 +74,9 @@ fn app<'a, 'b>() -> App<'a, 'b> {
             .arg(Arg::with_name("target-dir")
                 .long("target-dir")
                 .takes_value(true)))
+    .subcommand(
+        SubCommand::with_name("daemon")
+            .about("Reads requests from stdin (one json object per line) and writes the results to stdout"))
 }
 
 
@@ -86,6 +114,17 @@ fn process() -> InvocationResult<()> {
     }
     let metadata = get_metadata()?;
     let single_file = matches.is_present("single-file");
+    let diff = matches.value_of("output") == Some("diff");
+    // Taken before the byte offsets are computed, so that changes made to the files in the meantime are detected
+    let fingerprint = if matches.is_present("apply") {
+        Some(get_fingerprint(std::path::Path::new("."), target_dir))
+    } else {
+        None
+    };
+
+    if let ("daemon", Some(_)) = matches.subcommand() {
+        return Daemon::new(metadata, target_dir).run();
+    }
 
     let output = match matches.subcommand() {
         ("candidates", Some(candidate_matches)) => {
@@ -99,7 +138,17 @@ fn process() -> InvocationResult<()> {
         (subcommand, _) => panic!("Unexpected subcommand: {:?}", subcommand)
     };
 
-    print_result(output, single_file)?;
+    // The changes are computed once, since applying them rewrites the files they are computed from
+    let changes = if let Some(fingerprint) = fingerprint {
+        assert_no_errors(&output)?;
+        let changes = get_file_changes(&output)?;
+        write_file_changes(&changes, &fingerprint)?;
+        Some(changes)
+    } else {
+        None
+    };
+
+    print_result(output, changes, single_file, diff)?;
     Ok(())
 }
 
@@ -114,74 +163,38 @@ fn run_crate(metadata: &Metadata, target_dir: Option<&str>, env_args: (String, S
     run_refactoring_cmd(target_dir, env_args)
 }
 
-fn run_refactoring(metadata: &Metadata, mut refactor_args: RefactorArgs, target_dir: Option<&str>) -> InvocationResult<RefactorOutputs2> {
-    match refactor_args.refactoring.as_ref() {
-        defs::EXTRACT_METHOD => {
-            refactor_args.add_comment = true;
-
-            let mut combined = RefactorOutputs2::empty();
-            for (refactoring, comment) in defs::extract_method_def() {
-
-                if comment.len() > 0 {
-                    refactor_args.selection = SelectionType::Comment(comment.to_string());
-                }
-
-                refactor_args.refactoring = refactoring.to_string();
-                refactor_args.with_changes = combined.changes.clone();
-                
-                let env_args = (ENV_REFACTORING_ARGS.to_owned(), serde_json::to_string(&refactor_args).unwrap());
-                let out = run_crate(metadata, target_dir, env_args)?;
-                combined.changes.extend(out.changes);
-                combined.errors.extend(out.errors);
-
-                if !combined.errors.is_empty() {
-                    break;
-                }
-            }
-            Ok(combined)
-        },
-        _ => {
+fn run_refactoring(metadata: &Metadata, refactor_args: RefactorArgs, target_dir: Option<&str>) -> InvocationResult<RefactorOutputs2> {
+    // Composite refactorings (e.g. extract-method) are run as a pipeline by the driver, so a single cargo invocation is enough
+    let env_args = (ENV_REFACTORING_ARGS.to_owned(), serialize(&refactor_args)?);
+    run_crate(metadata, target_dir, env_args)
+}
 
-            let env_args = (ENV_REFACTORING_ARGS.to_owned(), serde_json::to_string(&refactor_args).unwrap());
-            run_crate(metadata, target_dir, env_args)
-        }
+fn assert_no_errors(output: &RefactorOutputs2) -> InvocationResult<()> {
+    if output.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(
+            InvocationError::new(output.errors.iter().map(|e| format!("{:?}\n{}\n", e.kind, e.message)).join("\n"))
+        )
     }
 }
 
-fn print_result(output: RefactorOutputs2, single_file: bool) -> InvocationResult<()> {
-    if single_file {
-        if output.errors.is_empty() {
-            print!("{}", get_file_content_with_changes(output).unwrap());
-        } else {
-            return Err(
-                InvocationError::new(output.errors.iter().map(|e| format!("{:?}\n{}\n", e.kind, e.message)).join("\n"))
-            );
+fn print_result(output: RefactorOutputs2, changes: Option<Vec<FileChange>>, single_file: bool, diff: bool) -> InvocationResult<()> {
+    if single_file || diff {
+        assert_no_errors(&output)?;
+        let changes = match changes {
+            Some(changes) => changes,
+            None => get_file_changes(&output)?
+        };
+        if !single_file {
+            print!("{}", unified_diff(&changes));
+        } else if changes.len() > 1 {
+            return Err(InvocationError::new("--single-file was used, but more than one file was changed".to_owned()));
+        } else if let Some(change) = changes.first() {
+            print!("{}", change.new_content);
         }
     } else {
         print!("{}", serde_json::to_string(&output).unwrap());
     }
     Ok(())
 }
-
-
-fn get_file_content_with_changes(refactor_output: RefactorOutputs2) -> Option<String> {
-    use std::fs::File;
-    use std::io::prelude::*;
-
-    let mut content = String::new();
-    let file_name = refactor_output.changes.first()?.first()?.file_name.to_string();
-    let mut file = File::open(&file_name).unwrap();
-    file.read_to_string(&mut content).unwrap();
-
-    for changes in refactor_output.changes {
-        
-        for change in &changes {
-            assert_eq!(file_name, change.file_name);
-            let s1 = &content[..(change.byte_start) as usize];
-            let s2 = &content[(change.byte_end) as usize..];
-            content = format!("{}{}{}", s1, change.replacement, s2);
-        }
-    }
-    
-    Some(content)
-}
@@ -113,6 +113,50 @@ mod cli_tests {
             .stdout(expected);
     }
 
+    fn extract_block_unicode(selection: &str, columns: &str) {
+        let expected = serde_json::to_string(
+            &RefactorOutputs2::from_change(FileStringReplacement {
+                byte_end: 48,
+                byte_start: 16,
+                char_end: 31,
+                char_start: 4,
+                file_name: "src/main.rs".to_owned(),
+                line_end: 1,
+                line_start: 1,
+                replacement: "let s = \n{let s = \"Hællø, wørld! 𝄞\";s};".to_owned(),
+        })).unwrap();
+
+        cargo_em_refactor()
+            .arg(WORKSPACE_ARG_UNICODE)
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("refactor")
+            .arg("extract-block")
+            .arg("src/main.rs")
+            .arg(selection)
+            .arg(format!("--columns={}", columns))
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+
+    #[test]
+    fn output_json_unicode_bytes() {
+        extract_block_unicode("16:48", "utf8");
+    }
+
+    #[test]
+    fn output_json_unicode_line_column() {
+        extract_block_unicode("2:5-2:37", "utf8");
+    }
+
+    #[test]
+    fn output_json_unicode_line_column_utf16() {
+        extract_block_unicode("2:5-2:32", "utf16");
+    }
+
     #[test]
     #[ignore]
     fn output_json_extract_method() {
@@ -246,6 +290,68 @@ mod cli_tests {
             .stdout(expected);
     }
 
+    #[test]
+    fn daemon_answers_each_request() {
+        use std::io::Write;
+        use std::process::Stdio;
+
+        let requests = vec![
+            DaemonRequest::Candidates {
+                refactoring: "extract-method".to_owned()
+            },
+            DaemonRequest::Refactor {
+                refactoring: "extract-block".to_owned(),
+                file: "src/main.rs".to_owned(),
+                selection: SelectionType::Range("16:40".to_owned()),
+                unsafe_: false,
+                options: vec![]
+            },
+            DaemonRequest::Shutdown
+        ];
+        let expected = vec![
+            serde_json::to_string(&RefactorOutputs2::from_candidates(vec![
+                CandidatePosition::new("src/main.rs", 16, 40, Some(1)),
+                CandidatePosition::new("src/main.rs", 16, 63, Some(2)),
+                CandidatePosition::new("src/main.rs", 45, 63, Some(1)),
+                CandidatePosition::new("src/main.rs", 100, 101, Some(1)),
+                CandidatePosition::new("src/main.rs", 124, 126, Some(1)),
+            ])).unwrap(),
+            serde_json::to_string(&RefactorOutputs2::from_change(FileStringReplacement {
+                byte_end: 40,
+                byte_start: 16,
+                char_end: 28,
+                char_start: 4,
+                file_name: "src/main.rs".to_owned(),
+                line_end: 1,
+                line_start: 1,
+                replacement: "let s = \n{let s = \"Hello, world!\";s};".to_owned(),
+            })).unwrap()
+        ];
+
+        let mut child = cargo_em_refactor()
+            .arg(WORKSPACE_ARG)
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("daemon")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        {
+            let stdin = child.stdin.as_mut().unwrap();
+            for request in &requests {
+                writeln!(stdin, "{}", serde_json::to_string(request).unwrap()).unwrap();
+            }
+        }
+        let output = child.wait_with_output().unwrap();
+        let actual = std::str::from_utf8(&output.stdout).unwrap().lines().map(|s| s.to_owned()).collect::<Vec<_>>();
+
+        assert!(output.status.success());
+        assert_eq!(actual, expected);
+    }
+
     #[test]
     fn query_candidates_on_invalid_crate() {
         cargo_em_refactor()
@@ -326,6 +432,190 @@ fn test1() {2;}"#;
             .stdout(expected);
     }
 
+    #[test]
+    fn output_diff() {
+        let expected =
+r#"--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,5 +1,6 @@
+ fn main() {
+-    let s = "Hello, world!";
++    let s = 
++{let s = "Hello, world!";s};
+     println!("{}", s);
+ }
+ 
+"#;
+
+        cargo_em_refactor()
+            .arg(WORKSPACE_ARG)
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--output=diff")
+            .arg("refactor")
+            .arg("extract-block")
+            .arg("src/main.rs")
+            .arg("16:40")
+            .assert()
+            .success()
+            .stdout(expected);
+    }
+
+    #[test]
+    fn apply_writes_changes() {
+        // Created inside em-refactor-examples/crates to use the same toolchain
+        let workspace = tempfile::Builder::new().prefix("apply").tempdir_in("../em-refactor-examples/crates").unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\nname = \"apply\"\nversion = \"0.1.0\"\nedition = \"2018\"\n").unwrap();
+        std::fs::write(workspace.path().join("src/main.rs"), "struct S { f: i32 }\nfn main() { let s = S { f: 0 }; let _f = s.f; }\n").unwrap();
+
+        cargo_em_refactor()
+            .arg(format!("--workspace-root={}", workspace.path().to_str().unwrap()))
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--apply")
+            .arg("refactor")
+            .arg("box-field")
+            .arg("src/main.rs")
+            .arg("11:12")
+            .assert()
+            .success();
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs")).unwrap(),
+            "struct S { f: Box<i32> }\nfn main() { let s = S { f: Box::new(0) }; let _f = (*s.f); }\n");
+    }
+
+    #[test]
+    fn apply_output_diff() {
+        let workspace = tempfile::Builder::new().prefix("apply").tempdir_in("../em-refactor-examples/crates").unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\nname = \"apply\"\nversion = \"0.1.0\"\nedition = \"2018\"\n").unwrap();
+        std::fs::write(workspace.path().join("src/main.rs"), "struct S { f: i32 }\nfn main() { let s = S { f: 0 }; let _f = s.f; }\n").unwrap();
+
+        let expected =
+r#"--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,2 +1,2 @@
+-struct S { f: i32 }
+-fn main() { let s = S { f: 0 }; let _f = s.f; }
++struct S { f: Box<i32> }
++fn main() { let s = S { f: Box::new(0) }; let _f = (*s.f); }
+"#;
+
+        cargo_em_refactor()
+            .arg(format!("--workspace-root={}", workspace.path().to_str().unwrap()))
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--output=diff")
+            .arg("--apply")
+            .arg("refactor")
+            .arg("box-field")
+            .arg("src/main.rs")
+            .arg("11:12")
+            .assert()
+            .success()
+            .stdout(expected);
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs")).unwrap(),
+            "struct S { f: Box<i32> }\nfn main() { let s = S { f: Box::new(0) }; let _f = (*s.f); }\n");
+    }
+
+    #[test]
+    fn rename_in_all_targets() {
+        let workspace = tempfile::Builder::new().prefix("rename").tempdir_in("../em-refactor-examples/crates").unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::create_dir(workspace.path().join("tests")).unwrap();
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nedition = \"2018\"\n").unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "pub fn area(w: u32) -> u32 { w * w }\n").unwrap();
+        std::fs::write(workspace.path().join("src/main.rs"), "use shapes::area;\nfn main() { let _ = area(2); }\n").unwrap();
+        std::fs::write(workspace.path().join("tests/area.rs"), "#[test]\nfn area() { assert_eq!(shapes::area(1), 1); }\n").unwrap();
+
+        cargo_em_refactor()
+            .arg(format!("--workspace-root={}", workspace.path().to_str().unwrap()))
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--apply")
+            .arg("refactor")
+            .arg("rename")
+            .arg("src/lib.rs")
+            .arg("7:11")
+            .arg("--option=name=square")
+            .assert()
+            .success();
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/lib.rs")).unwrap(),
+            "pub fn square(w: u32) -> u32 { w * w }\n");
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs")).unwrap(),
+            "use shapes::square;\nfn main() { let _ = square(2); }\n");
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("tests/area.rs")).unwrap(),
+            "#[test]\nfn area() { assert_eq!(shapes::square(1), 1); }\n");
+    }
+
+    #[test]
+    fn move_item_to_new_module() {
+        let workspace = tempfile::Builder::new().prefix("move_item").tempdir_in("../em-refactor-examples/crates").unwrap();
+        std::fs::create_dir(workspace.path().join("src")).unwrap();
+        std::fs::write(workspace.path().join("Cargo.toml"), "[package]\nname = \"shapes\"\nversion = \"0.1.0\"\nedition = \"2018\"\n").unwrap();
+        std::fs::write(workspace.path().join("src/lib.rs"), "pub fn area(w: u32) -> u32 { w * w }\n\npub fn perimeter(w: u32) -> u32 { 4 * w }\n").unwrap();
+        std::fs::write(workspace.path().join("src/main.rs"), "fn main() { let _ = shapes::area(2) + shapes::perimeter(2); }\n").unwrap();
+
+        cargo_em_refactor()
+            .arg(format!("--workspace-root={}", workspace.path().to_str().unwrap()))
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--apply")
+            .arg("refactor")
+            .arg("move-item")
+            .arg("src/lib.rs")
+            .arg("0:36")
+            .arg("--option=to=crate::square")
+            .assert()
+            .success();
+
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/lib.rs")).unwrap(),
+            "pub mod square;\n\npub fn perimeter(w: u32) -> u32 { 4 * w }\n");
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/square.rs")).unwrap(),
+            "pub fn area(w: u32) -> u32 { w * w }\n");
+        assert_eq!(
+            std::fs::read_to_string(workspace.path().join("src/main.rs")).unwrap(),
+            "fn main() { let _ = shapes::square::area(2) + shapes::perimeter(2); }\n");
+    }
+
+    #[test]
+    fn apply_nothing_on_error() {
+        cargo_em_refactor()
+            .arg(WORKSPACE_ARG2)
+            .arg(format!(
+                "--target-dir={}",
+                create_tmp_dir().path().to_str().unwrap()
+            ))
+            .arg("--apply")
+            .arg("refactor")
+            .arg("extract-block")
+            .arg("src/main.rs")
+            .arg("16:42")
+            .assert()
+            .failure()
+            .stderr(predicate::str::starts_with("RustCError2"));
+    }
+
     #[test]
     fn should_display_version() {
         cargo_em_refactor()
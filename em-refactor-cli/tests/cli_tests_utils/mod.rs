@@ -3,6 +3,7 @@ use std::process::Command;
 use tempfile::TempDir;
 
 pub const WORKSPACE_ARG: &str = "--workspace-root=../em-refactor-examples/crates/hello_world";
+pub const WORKSPACE_ARG_UNICODE: &str = "--workspace-root=../em-refactor-examples/crates/hello_unicode";
 pub const WORKSPACE_ARG2: &str = "--workspace-root=../em-refactor-examples/crates/hello_world2";
 pub const WORKSPACE_ARG_MULTI_ROOT: &str = "--workspace-root=../em-refactor-examples/crates/multi_root";
 pub const WORKSPACE_ARG_MULTI_ROOT_OVERLAP: &str = "--workspace-root=../em-refactor-examples/crates/multi_root_overlap";
@@ -21,6 +21,10 @@ mod box_named_field {
         run_test("attribute_macro_debug");
     }
     #[test]
+    fn enum_struct_variant() {
+        run_test("enum_struct_variant");
+    }
+    #[test]
     fn expression_macro() {
         run_test("expression_macro");
     }
@@ -105,10 +109,26 @@ mod box_named_field {
         run_test("struct_pattern_field_binding_cond");
     }
     #[test]
+    fn struct_pattern_field_by_reference() {
+        run_test("struct_pattern_field_by_reference");
+    }
+    #[test]
+    fn struct_pattern_field_let() {
+        run_test("struct_pattern_field_let");
+    }
+    #[test]
+    fn struct_pattern_field_param() {
+        run_test("struct_pattern_field_param");
+    }
+    #[test]
     fn struct_pattern_field_used() {
         run_test("struct_pattern_field_used");
     }
     #[test]
+    fn struct_pattern_field_used_in_guard() {
+        run_test("struct_pattern_field_used_in_guard");
+    }
+    #[test]
     #[ignore]
     fn trait_copy_cannot_contain_drop() {
         run_test("trait_copy_cannot_contain_drop");
@@ -9,6 +9,10 @@ fn run_test(name: &str) {
 mod box_tuple_field {
     use super::*;
 
+    #[test]
+    fn enum_tuple_variant() {
+        run_test("enum_tuple_variant");
+    }
     #[test]
     fn field_access_2() {
         run_test("field_access_2");
@@ -30,6 +34,10 @@ mod box_tuple_field {
         run_test("struct_pattern_tuple_id_pat");
     }
     #[test]
+    fn struct_pattern_tuple_if_let() {
+        run_test("struct_pattern_tuple_if_let");
+    }
+    #[test]
     fn visibility_tuple_pub_1() {
         run_test("visibility_tuple_pub_1");
     }
@@ -0,0 +1,43 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("change_signature", name).unwrap();
+}
+
+mod change_signature {
+    use super::*;
+    #[test]
+    fn add_param_between() {
+        run_test("add_param_between");
+    }
+    #[test]
+    fn method_add_param() {
+        run_test("method_add_param");
+    }
+    #[test]
+    fn param_is_used() {
+        run_test("param_is_used");
+    }
+    #[test]
+    fn remove_param() {
+        run_test("remove_param");
+    }
+    #[test]
+    fn removed_arg_side_effects() {
+        run_test("removed_arg_side_effects");
+    }
+    #[test]
+    fn reorder_params() {
+        run_test("reorder_params");
+    }
+    #[test]
+    fn reordered_side_effects() {
+        run_test("reordered_side_effects");
+    }
+    #[test]
+    fn used_as_value() {
+        run_test("used_as_value");
+    }
+}
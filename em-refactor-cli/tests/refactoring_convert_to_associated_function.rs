@@ -0,0 +1,31 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("convert_to_associated_function", name).unwrap();
+}
+
+mod convert_to_associated_function {
+    use super::*;
+    #[test]
+    fn boxed_self() {
+        run_test("boxed_self");
+    }
+    #[test]
+    fn mut_self_in_module() {
+        run_test("mut_self_in_module");
+    }
+    #[test]
+    fn name_is_used() {
+        run_test("name_is_used");
+    }
+    #[test]
+    fn no_self() {
+        run_test("no_self");
+    }
+    #[test]
+    fn ref_self() {
+        run_test("ref_self");
+    }
+}
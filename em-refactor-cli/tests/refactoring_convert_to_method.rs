@@ -0,0 +1,31 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("convert_to_method", name).unwrap();
+}
+
+mod convert_to_method {
+    use super::*;
+    #[test]
+    fn free_function() {
+        run_test("free_function");
+    }
+    #[test]
+    fn mut_param() {
+        run_test("mut_param");
+    }
+    #[test]
+    fn not_self_param() {
+        run_test("not_self_param");
+    }
+    #[test]
+    fn ref_param() {
+        run_test("ref_param");
+    }
+    #[test]
+    fn value_param() {
+        run_test("value_param");
+    }
+}
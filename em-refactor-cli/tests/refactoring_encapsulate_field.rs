@@ -0,0 +1,35 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("encapsulate_field", name).unwrap();
+}
+
+mod encapsulate_field {
+    use super::*;
+    #[test]
+    fn field_is_private() {
+        run_test("field_is_private");
+    }
+    #[test]
+    fn field_moved_in_module() {
+        run_test("field_moved_in_module");
+    }
+    #[test]
+    fn field_moved_outside_module() {
+        run_test("field_moved_outside_module");
+    }
+    #[test]
+    fn field_used_in_other_module() {
+        run_test("field_used_in_other_module");
+    }
+    #[test]
+    fn generated_setter() {
+        run_test("generated_setter");
+    }
+    #[test]
+    fn method_exists() {
+        run_test("method_exists");
+    }
+}
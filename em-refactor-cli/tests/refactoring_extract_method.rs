@@ -9,8 +9,84 @@ fn run_test(name: &str) {
 mod extract_method {
     use super::*;
     
+    #[test]
+    fn async_fn() {
+        run_test("async_fn");
+    }
+    #[test]
+    fn async_method() {
+        run_test("async_method");
+    }
+    #[test]
+    fn async_return() {
+        run_test("async_return");
+    }
+    #[test]
+    fn async_try() {
+        run_test("async_try");
+    }
+    #[test]
+    fn async_try_break() {
+        run_test("async_try_break");
+    }
+    #[test]
+    fn closure() {
+        run_test("closure");
+    }
     #[test]
     fn example_1() {
         run_test("example_1");
     }
+    #[test]
+    fn expression_argument() {
+        run_test("expression_argument");
+    }
+    #[test]
+    fn expression_condition() {
+        run_test("expression_condition");
+    }
+    #[test]
+    fn expression_initializer() {
+        run_test("expression_initializer");
+    }
+    #[test]
+    fn generics() {
+        run_test("generics");
+    }
+    #[test]
+    fn labeled_break() {
+        run_test("labeled_break");
+    }
+    #[test]
+    fn loop_value() {
+        run_test("loop_value");
+    }
+    #[test]
+    fn method_mut_self() {
+        run_test("method_mut_self");
+    }
+    #[test]
+    fn method_mut_self_read() {
+        run_test("method_mut_self_read");
+    }
+    #[test]
+    fn method_ref_self() {
+        run_test("method_ref_self");
+    }
+    #[test]
+    fn method_self() {
+        run_test("method_self");
+    }
+    #[test]
+    fn nested_fn() {
+        run_test("nested_fn");
+    }
+    #[test]
+    fn nested_fn_in_method() {
+        run_test("nested_fn_in_method");
+    }
+    #[test]
+    fn try_operator() {
+        run_test("try_operator");
+    }
 }
\ No newline at end of file
@@ -0,0 +1,16 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("extract_variable", name).unwrap();
+}
+
+mod extract_variable {
+    use super::*;
+    
+    #[test]
+    fn replace_all() {
+        run_test("replace_all");
+    }
+}
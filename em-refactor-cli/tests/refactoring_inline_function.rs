@@ -0,0 +1,24 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("inline_function", name).unwrap();
+}
+
+mod inline_function {
+    use super::*;
+    
+    #[test]
+    fn all_calls() {
+        run_test("all_calls");
+    }
+    #[test]
+    fn method_call() {
+        run_test("method_call");
+    }
+    #[test]
+    fn return_in_body() {
+        run_test("return_in_body");
+    }
+}
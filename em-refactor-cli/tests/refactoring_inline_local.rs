@@ -0,0 +1,16 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("inline_local", name).unwrap();
+}
+
+mod inline_local {
+    use super::*;
+    
+    #[test]
+    fn println_args() {
+        run_test("println_args");
+    }
+}
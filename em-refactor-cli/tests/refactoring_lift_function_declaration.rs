@@ -37,4 +37,12 @@ mod lift_function_declaration {
     fn parent_is_module() {
         run_test("parent_is_module");
     }
+    #[test]
+    fn parent_is_nested_fn() {
+        run_test("parent_is_nested_fn");
+    }
+    #[test]
+    fn self_param_is_not_receiver() {
+        run_test("self_param_is_not_receiver");
+    }
 }
\ No newline at end of file
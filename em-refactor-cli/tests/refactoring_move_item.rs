@@ -0,0 +1,39 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("move_item", name).unwrap();
+}
+
+mod move_item {
+    use super::*;
+    #[test]
+    fn already_in_module() {
+        run_test("already_in_module");
+    }
+    #[test]
+    fn fn_to_module() {
+        run_test("fn_to_module");
+    }
+    #[test]
+    fn impl_to_nested_module() {
+        run_test("impl_to_nested_module");
+    }
+    #[test]
+    fn module_does_not_exist() {
+        run_test("module_does_not_exist");
+    }
+    #[test]
+    fn name_is_used() {
+        run_test("name_is_used");
+    }
+    #[test]
+    fn struct_between_modules() {
+        run_test("struct_between_modules");
+    }
+    #[test]
+    fn trait_item() {
+        run_test("trait_item");
+    }
+}
@@ -0,0 +1,47 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("rename", name).unwrap();
+}
+
+mod rename {
+    use super::*;
+    #[test]
+    fn function_in_module() {
+        run_test("function_in_module");
+    }
+    #[test]
+    fn invalid_name() {
+        run_test("invalid_name");
+    }
+    #[test]
+    fn local_name_used() {
+        run_test("local_name_used");
+    }
+    #[test]
+    fn local_variable() {
+        run_test("local_variable");
+    }
+    #[test]
+    fn method() {
+        run_test("method");
+    }
+    #[test]
+    fn name_collision() {
+        run_test("name_collision");
+    }
+    #[test]
+    fn named_field() {
+        run_test("named_field");
+    }
+    #[test]
+    fn shadowed_by_local() {
+        run_test("shadowed_by_local");
+    }
+    #[test]
+    fn struct_and_variant() {
+        run_test("struct_and_variant");
+    }
+}
@@ -10,12 +10,10 @@ mod split_conflicting_match_arms {
     use super::*;
     
     #[test]
-    #[ignore]
     fn if_let_expr() {
         run_test("if_let_expr");
     }
     #[test]
-    #[ignore]
     fn match_expr() {
         run_test("match_expr");
     }
@@ -0,0 +1,39 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("unbox_field", name).unwrap();
+}
+
+mod unbox_field {
+    use super::*;
+    #[test]
+    fn assignment_expression() {
+        run_test("assignment_expression");
+    }
+    #[test]
+    fn box_value_used() {
+        run_test("box_value_used");
+    }
+    #[test]
+    fn expression_macro() {
+        run_test("expression_macro");
+    }
+    #[test]
+    fn field_access_tuple() {
+        run_test("field_access_tuple");
+    }
+    #[test]
+    fn recursive_struct() {
+        run_test("recursive_struct");
+    }
+    #[test]
+    fn struct_pattern_field_binding() {
+        run_test("struct_pattern_field_binding");
+    }
+    #[test]
+    fn struct_pattern_tuple() {
+        run_test("struct_pattern_tuple");
+    }
+}
@@ -0,0 +1,35 @@
+use crate::test_case::run_testcase;
+
+mod test_case;
+
+fn run_test(name: &str) {
+    run_testcase("wrap_field", name).unwrap();
+}
+
+mod wrap_field {
+    use super::*;
+    #[test]
+    fn cell_assigned() {
+        run_test("cell_assigned");
+    }
+    #[test]
+    fn cell_not_copy() {
+        run_test("cell_not_copy");
+    }
+    #[test]
+    fn option_tuple_field() {
+        run_test("option_tuple_field");
+    }
+    #[test]
+    fn rc_mutated() {
+        run_test("rc_mutated");
+    }
+    #[test]
+    fn rc_read() {
+        run_test("rc_read");
+    }
+    #[test]
+    fn ref_cell_mutated() {
+        run_test("ref_cell_mutated");
+    }
+}
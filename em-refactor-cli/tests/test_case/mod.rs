@@ -15,6 +15,7 @@ struct TestCase {
     file: String,
     refactoring: String,
     selection: String,
+    options: Vec<String>,
     expected: Expected,
 }
 /**
@@ -35,6 +36,7 @@ impl TestCase {
             file: v["file"].as_str().unwrap().to_string(),
             refactoring: args.0,
             selection: args.1,
+            options: TestCase::json_to_options(&v),
             expected: TestCase::map_expected(&v).unwrap(),
         })
     }
@@ -47,6 +49,12 @@ impl TestCase {
             stdout_file: expected["stdout_file"].as_str().map(|s| s.to_string()),
         })
     }
+    /// `"options": ["<name>=<value>", ...]` in args
+    fn json_to_options(v: &Value) -> Vec<String> {
+        v["args"]["options"].as_array()
+            .map(|options| options.iter().filter_map(|o| o.as_str()).map(|o| o.to_string()).collect())
+            .unwrap_or_default()
+    }
     fn json_str_to_param_vec(v: &Value) -> (String, String) {
         let args = &v["args"];
         (args["refactoring"].as_str().unwrap().to_string(), args["selection"].as_str().unwrap().to_string())
@@ -115,6 +123,7 @@ fn run_tool_and_assert(test: TestCase, folder: &str) -> std::io::Result<()> {
         .arg(&test.refactoring)
         .arg("src/main.rs")
         .arg(&test.selection)
+        .args(test.options.iter().map(|o| format!("--option={}", o)))
         .assert();
 
     if let Some(code) = test.expected.code {
@@ -0,0 +1,15 @@
+enum Shape {
+    Square { width: f64 },
+    Rect { width: f64, height: f64 }
+}
+fn area(s: Shape) -> f64 {
+    match s {
+        Shape::Square { width } => width * width,
+        Shape::Rect { width, height } => width * height
+    }
+}
+fn main() {
+    let width = 2.0;
+    area(Shape::Rect { width, height: 3.0 });
+    area(Shape::Square { width: 1.0 });
+}
\ No newline at end of file
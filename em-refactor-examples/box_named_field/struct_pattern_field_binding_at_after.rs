@@ -0,0 +1,7 @@
+struct S { field: Box<i32> }
+fn main() {
+    match (S {field: Box::new(0)}) {
+        S {ref field} if matches!(**field, 0) => match **field { a @ 0 => {}, _ => unreachable!() },
+        _ => {}
+    }
+}
@@ -0,0 +1,10 @@
+struct S { field: Option<String> }
+fn len(s: &S) -> usize {
+    match s {
+        S { field: Some(name) } => name.len(),
+        S { field: None } => 0
+    }
+}
+fn main() {
+    println!("{}", len(&S { field: None }));
+}
\ No newline at end of file
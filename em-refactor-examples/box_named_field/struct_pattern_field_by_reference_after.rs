@@ -0,0 +1,11 @@
+struct S { field: Box<Option<String>> }
+fn len(s: &S) -> usize {
+    match s {
+        S { field } if matches!(**field, Some(_)) => match &**field { Some(name) => name.len(), _ => unreachable!() },
+        S { field } if matches!(**field, None) => 0,
+        _ => unreachable!()
+    }
+}
+fn main() {
+    println!("{}", len(&S { field: Box::new(None) }));
+}
\ No newline at end of file
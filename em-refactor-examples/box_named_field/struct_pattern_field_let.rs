@@ -0,0 +1,6 @@
+struct S { field: (i32, String) }
+fn main() {
+    let s = S { field: (1, String::new()) };
+    let S { field: (n, name) } = s;
+    println!("{} {}", n, name);
+}
\ No newline at end of file
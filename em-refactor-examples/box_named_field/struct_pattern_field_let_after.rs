@@ -0,0 +1,7 @@
+struct S { field: Box<(i32, String)> }
+fn main() {
+    let s = S { field: Box::new((1, String::new())) };
+    let S { field } = s;
+    let (n, name) = *field;
+    println!("{} {}", n, name);
+}
\ No newline at end of file
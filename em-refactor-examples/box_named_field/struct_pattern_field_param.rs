@@ -0,0 +1,7 @@
+struct Point { pos: (i32, i32) }
+fn sum(Point { pos: (x, y) }: Point) -> i32 {
+    x + y
+}
+fn main() {
+    println!("{}", sum(Point { pos: (1, 2) }));
+}
\ No newline at end of file
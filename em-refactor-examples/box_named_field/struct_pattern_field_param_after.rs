@@ -0,0 +1,8 @@
+struct Point { pos: Box<(i32, i32)> }
+fn sum(Point { ref pos }: Point) -> i32 {
+    let (x, y) = **pos;
+    x + y
+}
+fn main() {
+    println!("{}", sum(Point { pos: Box::new((1, 2)) }));
+}
\ No newline at end of file
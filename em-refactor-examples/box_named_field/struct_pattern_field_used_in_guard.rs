@@ -0,0 +1,7 @@
+struct S { field: Option<i32> }
+fn main() {
+    match (S { field: Some(1) }) {
+        S { field: Some(n) } if n > 0 => {},
+        _ => {}
+    }
+}
\ No newline at end of file
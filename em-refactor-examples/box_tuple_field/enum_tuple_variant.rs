@@ -0,0 +1,20 @@
+enum E {
+    A([u8; 1024], i32),
+    B(i32)
+}
+fn main() {
+    let e = E::A([0; 1024], 1);
+    let _ = E::B(2);
+    match e {
+        E::A(data, n) => {
+            let _: u8 = data[0] + n as u8;
+        },
+        E::B(n) => {}
+    }
+    if let E::A(d, ..) = E::A([1; 1024], 3) {
+        let _: [u8; 1024] = d;
+    }
+    if let E::A(.., n) = E::A([2; 1024], 4) {
+        let _: i32 = n;
+    }
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+struct S ( Box<i32> );
+fn main() {
+    let s1 = S(Box::new(0));
+    match s1 {
+        S(ref field_0) if matches!(**field_0, 0) => match **field_0 { a @ 0 => {}, _ => unreachable!() },
+        S(_) => {},
+    }
+}
\ No newline at end of file
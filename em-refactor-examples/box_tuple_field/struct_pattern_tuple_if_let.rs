@@ -0,0 +1,7 @@
+struct S(Option<i32>, i32);
+fn main() {
+    let s = S(Some(1), 2);
+    if let S(Some(a), b) = s {
+        println!("{}", a + b);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,7 @@
+struct S(Box<Option<i32>>, i32);
+fn main() {
+    let s = S(Box::new(Some(1)), 2);
+    if let S(ref field_0, b) = s { if let Some(a) = **field_0 {
+        println!("{}", a + b);
+    } }
+}
\ No newline at end of file
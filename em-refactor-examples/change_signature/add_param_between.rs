@@ -0,0 +1,8 @@
+fn describe(name: &str, age: u32) -> String {
+    format!("{} ({})", name, age)
+}
+
+fn main() {
+    let s = describe("Ann", 30);
+    println!("{}", s);
+}
@@ -0,0 +1,8 @@
+fn describe(name: &str, title: &str, age: u32) -> String {
+    format!("{} ({})", name, age)
+}
+
+fn main() {
+    let s = describe("Ann", "Dr", 30);
+    println!("{}", s);
+}
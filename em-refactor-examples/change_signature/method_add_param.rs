@@ -0,0 +1,16 @@
+struct Counter {
+    count: u32
+}
+
+impl Counter {
+    fn increment(&mut self) {
+        self.count += 1;
+    }
+}
+
+fn main() {
+    let mut c = Counter { count: 0 };
+    c.increment();
+    Counter::increment(&mut c);
+    assert_eq!(c.count, 2);
+}
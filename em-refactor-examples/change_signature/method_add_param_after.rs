@@ -0,0 +1,16 @@
+struct Counter {
+    count: u32
+}
+
+impl Counter {
+    fn increment(&mut self, by: u32) {
+        self.count += 1;
+    }
+}
+
+fn main() {
+    let mut c = Counter { count: 0 };
+    c.increment(1);
+    Counter::increment(&mut c, 1);
+    assert_eq!(c.count, 2);
+}
@@ -0,0 +1,7 @@
+fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+fn main() {
+    let _ = area(2, 3);
+}
@@ -0,0 +1,10 @@
+mod shapes {
+    pub fn area(width: u32, height: u32) -> u32 {
+        width * height
+    }
+}
+
+fn main() {
+    let a = shapes::area(2, 3);
+    assert_eq!(a, 6);
+}
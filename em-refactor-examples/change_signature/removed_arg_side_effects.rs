@@ -0,0 +1,12 @@
+fn area(width: u32, height: u32, _unit: &str) -> u32 {
+    width * height
+}
+
+fn unit() -> &'static str {
+    println!("computing the unit");
+    "cm"
+}
+
+fn main() {
+    let _ = area(2, 3, unit());
+}
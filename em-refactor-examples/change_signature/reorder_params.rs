@@ -0,0 +1,9 @@
+fn volume(width: u32, height: u32, depth: u32) -> u32 {
+    width * height * depth
+}
+
+fn main() {
+    let w = 2;
+    let v = volume(w, 3, volume(1, 1, 4));
+    assert_eq!(v, 24);
+}
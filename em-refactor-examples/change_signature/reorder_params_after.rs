@@ -0,0 +1,9 @@
+fn volume(depth: u32, width: u32, height: u32) -> u32 {
+    width * height * depth
+}
+
+fn main() {
+    let w = 2;
+    let v = volume(volume(4, 1, 1), w, 3);
+    assert_eq!(v, 24);
+}
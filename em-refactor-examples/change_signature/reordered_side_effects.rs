@@ -0,0 +1,9 @@
+fn pair(a: u32, b: u32) -> (u32, u32) {
+    (a, b)
+}
+
+fn main() {
+    let mut i = 0;
+    let mut next = || { i += 1; i };
+    let _ = pair(next(), next());
+}
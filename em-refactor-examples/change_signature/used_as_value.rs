@@ -0,0 +1,8 @@
+fn double(x: u32, _y: u32) -> u32 {
+    x * 2
+}
+
+fn main() {
+    let f = double;
+    let _ = f(1, 2);
+}
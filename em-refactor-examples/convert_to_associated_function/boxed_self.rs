@@ -0,0 +1,14 @@
+struct Node {
+    value: i32
+}
+
+impl Node {
+    fn into_value(self: Box<Self>) -> i32 {
+        self.value
+    }
+}
+
+fn main() {
+    let node = Box::new(Node { value: 1 });
+    assert_eq!(node.into_value(), 1);
+}
@@ -0,0 +1,14 @@
+struct Node {
+    value: i32
+}
+
+impl Node {
+    fn into_value(node: Box<Self>) -> i32 {
+        node.value
+    }
+}
+
+fn main() {
+    let node = Box::new(Node { value: 1 });
+    assert_eq!(Node::into_value(node), 1);
+}
@@ -0,0 +1,19 @@
+mod counter {
+    pub struct Counter {
+        pub count: u32
+    }
+
+    impl Counter {
+        pub fn add(&mut self, count: u32) {
+            self.count += count;
+        }
+    }
+}
+
+fn main() {
+    let mut c = counter::Counter { count: 0 };
+    c.add(2);
+    let r = &mut c;
+    r.add(1);
+    assert_eq!(c.count, 3);
+}
@@ -0,0 +1,19 @@
+mod counter {
+    pub struct Counter {
+        pub count: u32
+    }
+
+    impl Counter {
+        pub fn add(counter: &mut Self, count: u32) {
+            counter.count += count;
+        }
+    }
+}
+
+fn main() {
+    let mut c = counter::Counter { count: 0 };
+    counter::Counter::add(&mut c, 2);
+    let r = &mut c;
+    counter::Counter::add(r, 1);
+    assert_eq!(c.count, 3);
+}
@@ -0,0 +1,13 @@
+struct Point {
+    x: i32
+}
+
+impl Point {
+    fn scaled(&self, factor: i32) -> Point {
+        Point { x: self.x * factor }
+    }
+}
+
+fn main() {
+    assert_eq!(Point { x: 1 }.scaled(2).x, 2);
+}
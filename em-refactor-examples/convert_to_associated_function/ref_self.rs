@@ -0,0 +1,21 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+
+impl Point {
+    fn norm(&self) -> i32 {
+        self.x * self.x + self.y * self.y
+    }
+    fn is_longer(&self, other: &Point) -> bool {
+        self.norm() > other.norm()
+    }
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let boxed = Box::new(Point { x: 3, y: 4 });
+    assert_eq!(p.norm(), 5);
+    assert_eq!(boxed.norm(), 25);
+    assert!(boxed.is_longer(&p));
+}
@@ -0,0 +1,21 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+
+impl Point {
+    fn norm(point: &Self) -> i32 {
+        point.x * point.x + point.y * point.y
+    }
+    fn is_longer(&self, other: &Point) -> bool {
+        Self::norm(self) > Self::norm(other)
+    }
+}
+
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let boxed = Box::new(Point { x: 3, y: 4 });
+    assert_eq!(Point::norm(&p), 5);
+    assert_eq!(Point::norm(&*boxed), 25);
+    assert!(boxed.is_longer(&p));
+}
@@ -0,0 +1,11 @@
+struct Point {
+    x: i32
+}
+
+fn get_x(p: &Point) -> i32 {
+    p.x
+}
+
+fn main() {
+    assert_eq!(get_x(&Point { x: 1 }), 1);
+}
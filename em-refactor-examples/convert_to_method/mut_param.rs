@@ -0,0 +1,17 @@
+struct Counter {
+    count: u32
+}
+
+impl Counter {
+    fn add(c: &mut Counter, n: u32) {
+        c.count += n;
+    }
+}
+
+fn main() {
+    let mut c = Counter { count: 0 };
+    Counter::add(&mut c, 2);
+    let r = &mut c;
+    Counter::add(r, 1);
+    assert_eq!(c.count, 3);
+}
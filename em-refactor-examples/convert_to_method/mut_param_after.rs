@@ -0,0 +1,17 @@
+struct Counter {
+    count: u32
+}
+
+impl Counter {
+    fn add(&mut self, n: u32) {
+        self.count += n;
+    }
+}
+
+fn main() {
+    let mut c = Counter { count: 0 };
+    c.add(2);
+    let r = &mut c;
+    r.add(1);
+    assert_eq!(c.count, 3);
+}
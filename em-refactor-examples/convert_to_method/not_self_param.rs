@@ -0,0 +1,13 @@
+struct Point {
+    x: i32
+}
+
+impl Point {
+    fn new(x: i32) -> Self {
+        Point { x }
+    }
+}
+
+fn main() {
+    assert_eq!(Point::new(1).x, 1);
+}
@@ -0,0 +1,19 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+
+impl Point {
+    fn norm(p: &Self) -> i32 {
+        p.x * p.x + p.y * p.y
+    }
+    fn is_longer(&self, other: &Point) -> bool {
+        Self::norm(self) > Point::norm(other)
+    }
+}
+
+fn main() {
+    let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    assert_eq!(Point::norm(&points[0]), 5);
+    assert!(points[1].is_longer(&points[0]));
+}
@@ -0,0 +1,19 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+
+impl Point {
+    fn norm(&self) -> i32 {
+        self.x * self.x + self.y * self.y
+    }
+    fn is_longer(&self, other: &Point) -> bool {
+        self.norm() > other.norm()
+    }
+}
+
+fn main() {
+    let points = vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }];
+    assert_eq!(points[0].norm(), 5);
+    assert!(points[1].is_longer(&points[0]));
+}
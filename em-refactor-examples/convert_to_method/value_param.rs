@@ -0,0 +1,16 @@
+struct Meters(f64);
+
+struct Distance {
+    meters: Meters
+}
+
+impl Meters {
+    fn into_distance(meters: Self) -> Distance {
+        Distance { meters }
+    }
+}
+
+fn main() {
+    let d = Meters::into_distance(Meters(1.0 + 2.0));
+    assert_eq!((d.meters).0, 3.0);
+}
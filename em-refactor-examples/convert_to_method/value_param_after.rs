@@ -0,0 +1,16 @@
+struct Meters(f64);
+
+struct Distance {
+    meters: Meters
+}
+
+impl Meters {
+    fn into_distance(self) -> Distance {
+        Distance { meters: self }
+    }
+}
+
+fn main() {
+    let d = Meters(1.0 + 2.0).into_distance();
+    assert_eq!((d.meters).0, 3.0);
+}
@@ -0,0 +1,4 @@
+fn main() {
+    let s = "Hællø, wørld! 𝄞";
+    println!("{}", s);
+}
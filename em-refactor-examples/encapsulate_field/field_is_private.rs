@@ -0,0 +1,5 @@
+struct S { name: String }
+fn main() {
+    let s = S { name: String::new() };
+    assert!(s.name.is_empty());
+}
@@ -0,0 +1,14 @@
+mod m {
+    pub struct S { pub name: String }
+    pub fn new() -> S {
+        S { name: String::new() }
+    }
+    pub fn into_name(s: S) -> String {
+        s.name
+    }
+}
+fn main() {
+    let s = m::new();
+    assert!(s.name.is_empty());
+    assert!(m::into_name(s).is_empty());
+}
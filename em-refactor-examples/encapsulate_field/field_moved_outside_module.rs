@@ -0,0 +1,11 @@
+mod m {
+    pub struct S { pub name: String }
+    pub fn new() -> S {
+        S { name: String::new() }
+    }
+}
+fn main() {
+    let s = m::new();
+    let name = s.name;
+    assert!(name.is_empty());
+}
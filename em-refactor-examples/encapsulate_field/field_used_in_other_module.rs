@@ -0,0 +1,18 @@
+mod shapes {
+    pub struct Rect { pub width: u32, pub height: u32 }
+    impl Rect {
+        pub fn new(width: u32, height: u32) -> Self {
+            Rect { width, height }
+        }
+        pub fn area(&self) -> u32 {
+            self.width * self.height
+        }
+    }
+}
+fn main() {
+    let mut r = shapes::Rect::new(1, 2);
+    r.width = 3;
+    r.width += 1;
+    assert_eq!(r.width, 4);
+    assert_eq!(r.area(), 8);
+}
@@ -0,0 +1,30 @@
+mod shapes {
+    pub struct Rect { width: u32, pub height: u32 }
+
+    impl Rect {
+        pub fn width(&self) -> &u32 {
+            &self.width
+        }
+        pub fn width_mut(&mut self) -> &mut u32 {
+            &mut self.width
+        }
+        pub fn set_width(&mut self, width: u32) {
+            self.width = width;
+        }
+    }
+    impl Rect {
+        pub fn new(width: u32, height: u32) -> Self {
+            Rect { width, height }
+        }
+        pub fn area(&self) -> u32 {
+            (*self.width()) * self.height
+        }
+    }
+}
+fn main() {
+    let mut r = shapes::Rect::new(1, 2);
+    r.set_width(3);
+    (*r.width_mut()) += 1;
+    assert_eq!((*r.width()), 4);
+    assert_eq!(r.area(), 8);
+}
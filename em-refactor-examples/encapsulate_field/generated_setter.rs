@@ -0,0 +1,5 @@
+pub struct Counter { pub count: usize }
+fn main() {
+    let c = Counter { count: 0 };
+    println!("{}", c.count);
+}
@@ -0,0 +1,14 @@
+pub struct Counter { count: usize }
+
+impl Counter {
+    pub fn count(&self) -> &usize {
+        &self.count
+    }
+    pub fn set_count(&mut self, count: usize) {
+        self.count = count;
+    }
+}
+fn main() {
+    let c = Counter { count: 0 };
+    println!("{}", (*c.count()));
+}
@@ -0,0 +1,10 @@
+pub struct S { pub name: String }
+impl S {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+fn main() {
+    let s = S { name: String::new() };
+    assert!(s.name().is_empty());
+}
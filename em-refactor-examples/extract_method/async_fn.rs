@@ -0,0 +1,17 @@
+async fn fetch(id: u32) -> u32 {
+    id * 10
+}
+
+async fn sum(ids: &[u32]) -> u32 {
+    let mut total = 0;
+    for id in ids {
+        let value = fetch(*id).await;
+        total += value;
+    }
+    total
+}
+
+fn main() {
+    let future = sum(&[1, 2, 3]);
+    let _ = future;
+}
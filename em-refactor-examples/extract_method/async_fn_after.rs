@@ -0,0 +1,19 @@
+async fn fetch(id: u32) -> u32 {
+    id * 10
+}
+
+async fn sum(ids: &[u32]) -> u32 {
+    let mut total = 0;
+    for id in ids {
+        ({
+add_value})(id, &mut total).await;
+    }
+    total
+}
+
+fn main() {
+    let future = sum(&[1, 2, 3]);
+    let _ = future;
+}
+async fn add_value(id: &u32, total: &mut u32) {let value = fetch(*id).await;
+        (*total) += value;}
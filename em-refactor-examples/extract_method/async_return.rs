@@ -0,0 +1,17 @@
+async fn parse(text: &str) -> u32 {
+    text.len() as u32
+}
+
+async fn first_even(texts: &[&str]) -> u32 {
+    for text in texts {
+        let value = parse(text).await;
+        if value % 2 == 0 {
+            return value;
+        }
+    }
+    0
+}
+
+fn main() {
+    let _ = first_even(&["1"]);
+}
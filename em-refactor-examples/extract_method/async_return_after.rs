@@ -0,0 +1,25 @@
+async fn parse(text: &str) -> u32 {
+    text.len() as u32
+}
+
+async fn first_even(texts: &[&str]) -> u32 {
+    for text in texts {
+        match ({
+foo})(text).await {
+ReturnFoo::Expr(e) => e,
+ReturnFoo::Return(e) => return e}
+    }
+    0
+}
+
+fn main() {
+    let _ = first_even(&["1"]);
+}
+enum ReturnFoo {
+Expr(()),
+Return(u32)
+}
+async fn foo(text: &&str) -> crate::ReturnFoo {let value = parse(text).await;
+        ReturnFoo::Expr(if value % 2 == 0 {
+            return ReturnFoo::Return(value);
+        })}
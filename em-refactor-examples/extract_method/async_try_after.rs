@@ -0,0 +1,39 @@
+struct Client {
+    base: u32,
+}
+
+impl Client {
+    async fn get(&self, id: u32) -> u32 {
+        self.base + id
+    }
+
+    async fn total(&self, ids: &[u32]) -> u32 {
+        let mut total = 0;
+        for id in ids {
+            let value = self.get(*id).await * 2;
+            total += value;
+        }
+        total
+    }
+}
+
+async fn parse(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.parse()
+}
+
+async fn parse_all(texts: &[&str]) -> Result<u32, std::num::ParseIntError> {
+    let mut total = 0;
+    for text in texts {
+        ({
+add_parsed})(text, &mut total).await?;
+    }
+    Ok(total)
+}
+
+fn main() {
+    let client = Client { base: 1 };
+    let _ = client.total(&[1, 2, 3]);
+    let _ = parse_all(&["1"]);
+}
+async fn add_parsed(text: &&str, total: &mut u32) -> std::result::Result<(), std::num::ParseIntError> {let value = parse(text).await?;
+        (*total) += value;Ok::<_, std::num::ParseIntError>(())}
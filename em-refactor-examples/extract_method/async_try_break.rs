@@ -0,0 +1,19 @@
+async fn parse(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.parse()
+}
+
+async fn sum_until_zero(texts: &[&str]) -> Result<u32, std::num::ParseIntError> {
+    let mut total = 0;
+    for text in texts {
+        let value = parse(text).await?;
+        if value == 0 {
+            break;
+        }
+        total += value;
+    }
+    Ok(total)
+}
+
+fn main() {
+    let _ = sum_until_zero(&["1"]);
+}
\ No newline at end of file
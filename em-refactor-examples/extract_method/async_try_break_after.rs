@@ -0,0 +1,27 @@
+async fn parse(text: &str) -> Result<u32, std::num::ParseIntError> {
+    text.parse()
+}
+
+async fn sum_until_zero(texts: &[&str]) -> Result<u32, std::num::ParseIntError> {
+    let mut total = 0;
+    for text in texts {
+        match ({
+add_value})(text, &mut total).await? {
+ReturnFoo::Break() => break,
+ReturnFoo::Expr(e) => e};
+    }
+    Ok(total)
+}
+
+fn main() {
+    let _ = sum_until_zero(&["1"]);
+}
+enum ReturnFoo {
+Break(),
+Expr(())
+}
+async fn add_value(text: &&str, total: &mut u32) -> std::result::Result<crate::ReturnFoo, std::num::ParseIntError> {let value = parse(text).await?;
+        if value == 0 {
+            return Ok::<_, std::num::ParseIntError>(ReturnFoo::Break());
+        }
+        (*total) += value;Ok::<_, std::num::ParseIntError>(ReturnFoo::Expr(()))}
\ No newline at end of file
@@ -0,0 +1,12 @@
+fn main() {
+    let offset = 3;
+    let values: Vec<i32> = vec![1, 2, 3]
+        .into_iter()
+        .map(|x| {
+            let doubled = x * 2;
+            let shifted = doubled + offset;
+            shifted
+        })
+        .collect();
+    println!("{:?}", values);
+}
@@ -0,0 +1,15 @@
+fn main() {
+    let offset = 3;
+    let values: Vec<i32> = vec![1, 2, 3]
+        .into_iter()
+        .map(|x| {
+            let shifted = 
+({
+foo})(offset, x);
+            shifted
+        })
+        .collect();
+    println!("{:?}", values);
+}
+fn foo(offset: i32, x: i32) -> i32 {let doubled = x * 2;
+            let shifted = doubled + offset;shifted}
@@ -0,0 +1,12 @@
+fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+fn main() {
+    let width = 3;
+    let height = 4;
+    let total = area(width + 1, height) + 2;
+    if width * height > 10 && total > 0 {
+        println!("{}", total);
+    }
+}
@@ -0,0 +1,14 @@
+fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+fn main() {
+    let width = 3;
+    let height = 4;
+    let total = area(width + 1, height) + 2;
+    if ({
+foo})(height, width) && total > 0 {
+        println!("{}", total);
+    }
+}
+fn foo(height: u32, width: u32) -> bool {width * height > 10}
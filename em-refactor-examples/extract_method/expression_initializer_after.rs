@@ -0,0 +1,14 @@
+fn area(width: u32, height: u32) -> u32 {
+    width * height
+}
+
+fn main() {
+    let width = 3;
+    let height = 4;
+    let total = ({
+foo})(height, width);
+    if width * height > 10 && total > 0 {
+        println!("{}", total);
+    }
+}
+fn foo(height: u32, width: u32) -> u32 {area(width + 1, height) + 2}
@@ -0,0 +1,19 @@
+use std::fmt::Display;
+
+fn describe<'a, T: Display, U>(items: &'a [T], separator: &str, _unused: U) -> String
+where
+    T: Clone,
+{
+    let out = 
+({
+describe_items})(items, separator);
+    out
+}
+
+fn main() {
+    println!("{}", describe(&[1, 2], ", ", ()));
+}
+fn describe_items<'a, T: Display>(items: &'a [T], separator: &str) -> std::string::String where T: Clone {let mut out = String::new();
+    for item in items {
+        out.push_str(&format!("{}{}", item.clone(), separator));
+    }out}
@@ -0,0 +1,16 @@
+fn main() {
+    let grid = vec![vec![1, 2], vec![3, 4]];
+    let mut found = None;
+    'rows: for row in &grid {
+        for &cell in row {
+            if cell == 0 {
+                continue 'rows;
+            }
+            if cell > 2 {
+                found = Some(cell);
+                break 'rows;
+            }
+        }
+    }
+    println!("{:?}", found);
+}
@@ -0,0 +1,26 @@
+fn main() {
+    let grid = vec![vec![1, 2], vec![3, 4]];
+    let mut found = None;
+    'rows: for row in &grid {
+        for &cell in row {
+            match ({
+foo})(cell, &mut found) {
+ReturnFoo::BreakRows() => break 'rows,
+ReturnFoo::ContinueRows() => continue 'rows,
+ReturnFoo::Expr(e) => e}
+        }
+    }
+    println!("{:?}", found);
+}
+enum ReturnFoo {
+BreakRows(),
+ContinueRows(),
+Expr(())
+}
+fn foo(cell: i32, found: &mut std::option::Option<i32>) -> crate::ReturnFoo {if cell == 0 {
+                return ReturnFoo::ContinueRows();
+            }
+            ReturnFoo::Expr(if cell > 2 {
+                (*found) = Some(cell);
+                return ReturnFoo::BreakRows();
+            })}
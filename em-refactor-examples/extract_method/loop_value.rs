@@ -0,0 +1,15 @@
+fn main() {
+    let mut i = 0;
+    let first = 'search: loop {
+        loop {
+            i += 1;
+            if i % 7 == 0 {
+                break 'search i * 2;
+            }
+            if i % 3 == 0 {
+                break;
+            }
+        }
+    };
+    println!("{}", first);
+}
@@ -0,0 +1,25 @@
+fn main() {
+    let mut i = 0;
+    let first = 'search: loop {
+        loop {
+            match ({
+foo})(&mut i) {
+ReturnFoo::BreakSearch(e) => break 'search e,
+ReturnFoo::Break() => break,
+ReturnFoo::Expr(e) => e}
+        }
+    };
+    println!("{}", first);
+}
+enum ReturnFoo {
+BreakSearch(i32),
+Break(),
+Expr(())
+}
+fn foo(i: &mut i32) -> crate::ReturnFoo {(*i) += 1;
+            if (*i) % 7 == 0 {
+                return ReturnFoo::BreakSearch((*i) * 2);
+            }
+            ReturnFoo::Expr(if (*i) % 3 == 0 {
+                return ReturnFoo::Break();
+            })}
@@ -0,0 +1,17 @@
+struct Counter {
+    count: u32,
+    step: u32,
+}
+
+impl Counter {
+    fn tick(&mut self, times: u32) -> u32 {
+        let next = self.count + self.step * times;
+        self.count = next;
+        self.count
+    }
+}
+
+fn main() {
+    let mut c = Counter { count: 0, step: 2 };
+    println!("{}", c.tick(3));
+}
\ No newline at end of file
@@ -0,0 +1,19 @@
+struct Counter {
+    count: u32,
+    step: u32,
+}
+
+impl Counter {
+    fn tick(&mut self, times: u32) -> u32 {
+        let next = 
+self.next_count(times);
+        self.count = next;
+        self.count
+    }
+    fn next_count(&self, times: u32) -> u32 {self.count + self.step * times}
+}
+
+fn main() {
+    let mut c = Counter { count: 0, step: 2 };
+    println!("{}", c.tick(3));
+}
\ No newline at end of file
@@ -0,0 +1,28 @@
+struct Counter {
+    count: u32,
+    step: u32,
+}
+
+impl Counter {
+    fn tick(&mut self, times: u32) -> u32 {
+        for _ in 0..times {
+            self.count += self.step;
+        }
+        self.count
+    }
+
+    fn describe(&self) -> String {
+        let prefix = "count";
+        let text = 
+
+self.describe_counter(prefix);        text
+    }
+    fn describe_counter(&self, prefix: &str) -> std::string::String {let text = format!("{}: {} (+{})", prefix, self.count, self.step);
+text}
+}
+
+fn main() {
+    let mut c = Counter { count: 0, step: 2 };
+    c.tick(3);
+    println!("{}", c.describe());
+}
@@ -0,0 +1,17 @@
+struct Counter {
+    count: u32,
+    step: u32,
+}
+
+impl Counter {
+    fn into_total(self) -> u32 {
+        let total = self.count + self.step;
+        drop(self);
+        total
+    }
+}
+
+fn main() {
+    let c = Counter { count: 1, step: 2 };
+    println!("{}", c.into_total());
+}
@@ -0,0 +1,10 @@
+fn main() {
+    struct Point { x: i32 }
+    fn norm(p: &Point) -> i32 {
+        ({
+foo})(p)
+    }
+    fn foo(p: &Point) -> i32 {let x = p.x;
+        x * x}
+    println!("{}", norm(&Point { x: 2 }));
+}
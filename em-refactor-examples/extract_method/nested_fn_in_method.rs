@@ -0,0 +1,11 @@
+struct S { v: i32 }
+impl S {
+    fn run(&self) -> i32 {
+        fn helper(a: i32) -> i32 {
+            let b = a + 1;
+            b * 2
+        }
+        helper(self.v)
+    }
+}
+fn main() { println!("{}", S { v: 1 }.run()); }
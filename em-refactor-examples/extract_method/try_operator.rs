@@ -0,0 +1,8 @@
+fn main() {
+    print!("{}", parse("1").unwrap());
+}
+fn parse(s: &str) -> Result<i32, std::num::ParseIntError> {
+    let i = s.parse::<i32>()?;
+    let j = i + 1;
+    Ok(j)
+}
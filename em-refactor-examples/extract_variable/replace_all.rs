@@ -0,0 +1,10 @@
+struct Rect {
+    width: u32,
+    height: u32
+}
+
+fn main() {
+    let rect = Rect { width: 3, height: 4 };
+    let double = rect.width * rect.height * 2;
+    println!("{} {}", double, rect.width * rect.height);
+}
\ No newline at end of file
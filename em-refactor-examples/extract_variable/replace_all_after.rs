@@ -0,0 +1,11 @@
+struct Rect {
+    width: u32,
+    height: u32
+}
+
+fn main() {
+    let rect = Rect { width: 3, height: 4 };
+    let area: u32 = rect.width * rect.height;
+    let double = area * 2;
+    println!("{} {}", double, area);
+}
\ No newline at end of file
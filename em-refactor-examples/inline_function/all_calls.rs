@@ -0,0 +1,10 @@
+fn main() {
+    let a = 2;
+    let b = square_plus(a, 1) * square_plus(3, a);
+    println!("{}", b);
+}
+
+fn square_plus(x: i32, y: i32) -> i32 {
+    let x2 = x * x;
+    x2 + y
+}
\ No newline at end of file
@@ -0,0 +1,13 @@
+fn main() {
+    let a = 2;
+    let b = ({
+        let y: i32 = 1;
+        let x2 = a * a;
+        x2 + y
+    }) * ({
+        let x: i32 = 3;
+        let x2 = x * x;
+        x2 + a
+    });
+    println!("{}", b);
+}
@@ -0,0 +1,17 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+impl Point {
+    fn manhattan(&self, other: &Point) -> i32 {
+        let dx = (self.x - other.x).abs();
+        let dy = (self.y - other.y).abs();
+        dx + dy
+    }
+}
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let q = Point { x: 4, y: 0 };
+    let d = p.manhattan(&q);
+    println!("{}", d);
+}
\ No newline at end of file
@@ -0,0 +1,17 @@
+struct Point {
+    x: i32,
+    y: i32
+}
+impl Point {
+}
+fn main() {
+    let p = Point { x: 1, y: 2 };
+    let q = Point { x: 4, y: 0 };
+    let d = {
+        let (self_, other): (_, &Point) = (&p, &q);
+        let dx = (self_.x - other.x).abs();
+        let dy = (self_.y - other.y).abs();
+        dx + dy
+    };
+    println!("{}", d);
+}
\ No newline at end of file
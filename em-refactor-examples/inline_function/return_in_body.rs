@@ -0,0 +1,11 @@
+fn main() {
+    let a = 2;
+    println!("{}", clamp(a));
+}
+
+fn clamp(x: i32) -> i32 {
+    if x > 1 {
+        return 1;
+    }
+    x
+}
\ No newline at end of file
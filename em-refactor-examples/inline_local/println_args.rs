@@ -0,0 +1,6 @@
+fn main() {
+    let width = 3;
+    let height = 4;
+    let area = width * height;
+    println!("{} {}", area, area / 2);
+}
\ No newline at end of file
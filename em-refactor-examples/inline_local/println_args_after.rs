@@ -0,0 +1,5 @@
+fn main() {
+    let width = 3;
+    let height = 4;
+    println!("{} {}", width * height, (width * height) / 2);
+}
\ No newline at end of file
@@ -0,0 +1,9 @@
+fn main() {
+    struct Point { x: i32 }
+    fn norm(p: &Point) -> i32 {
+        
+        square(p)
+    }
+    fn square(p: &Point) -> i32 { p.x * p.x }
+    norm(&Point { x: 1 });
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+fn main() {
+    struct Point { x: i32 }
+    fn norm(p: &Point) -> i32 {
+        fn square(p: &Point) -> i32 { p.x * p.x }
+        square(p)
+    }
+    norm(&Point { x: 1 });
+}
\ No newline at end of file
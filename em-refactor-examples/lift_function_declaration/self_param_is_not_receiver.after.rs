@@ -0,0 +1,10 @@
+fn main() { }
+
+struct S;
+impl S {
+    fn foo(&self) {
+        
+        Self::bar(self);
+    }
+    fn bar(self_: &S) { }
+}
\ No newline at end of file
@@ -0,0 +1,9 @@
+fn main() { }
+
+struct S;
+impl S {
+    fn foo(&self) {
+        fn bar(self_: &S) { }
+        bar(self);
+    }
+}
\ No newline at end of file
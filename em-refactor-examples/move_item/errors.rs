@@ -0,0 +1,17 @@
+mod util {
+    pub fn double(x: i32) -> i32 {
+        x * 2
+    }
+}
+
+trait Describe {
+    fn describe(&self) -> String;
+}
+
+fn double(x: i32) -> i32 {
+    x + x
+}
+
+fn main() {
+    println!("{} {}", double(1), util::double(2));
+}
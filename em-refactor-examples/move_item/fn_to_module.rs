@@ -0,0 +1,22 @@
+mod geometry {
+    pub struct Rect {
+        pub width: f64,
+        pub height: f64
+    }
+}
+
+use geometry::Rect;
+
+fn scale(x: f64) -> f64 {
+    x * 2.0
+}
+
+/// The area of the scaled rectangle
+fn scaled_area(rect: &Rect) -> f64 {
+    scale(rect.width) * scale(rect.height)
+}
+
+fn main() {
+    let rect = Rect { width: 1.5, height: 2.0 };
+    println!("{}", scaled_area(&rect));
+}
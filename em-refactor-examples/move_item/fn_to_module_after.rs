@@ -0,0 +1,23 @@
+mod geometry {
+    pub struct Rect {
+        pub width: f64,
+        pub height: f64
+    }
+
+    /// The area of the scaled rectangle
+    pub(crate) fn scaled_area(rect: &Rect) -> f64 {
+        crate::scale(rect.width) * crate::scale(rect.height)
+    }
+}
+
+use geometry::Rect;
+use geometry::scaled_area;
+
+fn scale(x: f64) -> f64 {
+    x * 2.0
+}
+
+fn main() {
+    let rect = Rect { width: 1.5, height: 2.0 };
+    println!("{}", scaled_area(&rect));
+}
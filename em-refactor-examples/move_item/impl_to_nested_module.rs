@@ -0,0 +1,29 @@
+use std::fmt;
+
+mod format {
+    pub mod display {
+        pub fn separator() -> &'static str {
+            ", "
+        }
+    }
+}
+
+pub struct Point {
+    x: i32,
+    y: i32
+}
+
+fn coordinates(point: &Point) -> [i32; 2] {
+    [point.x, point.y]
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let [x, y] = coordinates(self);
+        write!(f, "({}{}{})", x, format::display::separator(), y)
+    }
+}
+
+fn main() {
+    println!("{}", Point { x: 1, y: 2 });
+}
@@ -0,0 +1,29 @@
+use std::fmt;
+
+mod format {
+    pub mod display {
+        pub fn separator() -> &'static str {
+            ", "
+        }
+
+        impl std::fmt::Display for crate::Point {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                let [x, y] = crate::coordinates(self);
+                write!(f, "({}{}{})", x, separator(), y)
+            }
+        }
+    }
+}
+
+pub struct Point {
+    x: i32,
+    y: i32
+}
+
+fn coordinates(point: &Point) -> [i32; 2] {
+    [point.x, point.y]
+}
+
+fn main() {
+    println!("{}", Point { x: 1, y: 2 });
+}
@@ -0,0 +1,44 @@
+mod shapes {
+    #[derive(Debug, Clone, Copy)]
+    pub enum Unit {
+        Cm,
+        Inch
+    }
+
+    fn to_cm(value: f64, unit: Unit) -> f64 {
+        match unit {
+            Unit::Cm => value,
+            Unit::Inch => value * 2.54
+        }
+    }
+
+    #[derive(Debug)]
+    pub struct Circle {
+        radius: f64,
+        pub unit: Unit
+    }
+
+    impl Circle {
+        pub fn radius_cm(&self) -> f64 {
+            to_cm(self.radius, self.unit)
+        }
+    }
+
+    pub fn unit_circle() -> Circle {
+        Circle { radius: 1.0, unit: Unit::Cm }
+    }
+}
+
+mod geometry {
+    pub fn pi() -> f64 {
+        std::f64::consts::PI
+    }
+}
+
+use shapes::{Circle, Unit};
+
+fn main() {
+    let circle: Circle = shapes::unit_circle();
+    let area = geometry::pi() * circle.radius_cm() * circle.radius_cm();
+    println!("{:?} {} {:?}", circle, area, Unit::Inch);
+}
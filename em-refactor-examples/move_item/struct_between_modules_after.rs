@@ -0,0 +1,47 @@
+mod shapes {
+    use crate::geometry::Circle;
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum Unit {
+        Cm,
+        Inch
+    }
+
+    fn to_cm(value: f64, unit: Unit) -> f64 {
+        match unit {
+            Unit::Cm => value,
+            Unit::Inch => value * 2.54
+        }
+    }
+
+    impl Circle {
+        pub fn radius_cm(&self) -> f64 {
+            to_cm(self.radius, self.unit)
+        }
+    }
+
+    pub fn unit_circle() -> Circle {
+        Circle { radius: 1.0, unit: Unit::Cm }
+    }
+}
+
+mod geometry {
+    pub fn pi() -> f64 {
+        std::f64::consts::PI
+    }
+
+    #[derive(Debug)]
+    pub struct Circle {
+        pub(crate) radius: f64,
+        pub unit: crate::shapes::Unit
+    }
+}
+
+use shapes::{Unit};
+use geometry::Circle;
+
+fn main() {
+    let circle: Circle = shapes::unit_circle();
+    let area = geometry::pi() * circle.radius_cm() * circle.radius_cm();
+    println!("{:?} {} {:?}", circle, area, Unit::Inch);
+}
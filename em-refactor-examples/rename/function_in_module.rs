@@ -0,0 +1,13 @@
+mod shapes {
+    pub fn area(w: u32, h: u32) -> u32 {
+        w * h
+    }
+    pub fn square(s: u32) -> u32 {
+        area(s, s)
+    }
+}
+use shapes::area;
+fn main() {
+    assert_eq!(area(2, 3), 6);
+    assert_eq!(shapes::area(1, 1), shapes::square(1));
+}
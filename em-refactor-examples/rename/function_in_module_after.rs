@@ -0,0 +1,13 @@
+mod shapes {
+    pub fn surface(w: u32, h: u32) -> u32 {
+        w * h
+    }
+    pub fn square(s: u32) -> u32 {
+        surface(s, s)
+    }
+}
+use shapes::surface;
+fn main() {
+    assert_eq!(surface(2, 3), 6);
+    assert_eq!(shapes::surface(1, 1), shapes::square(1));
+}
@@ -0,0 +1,8 @@
+struct Counter { count: usize }
+fn main() {
+    let count = 1;
+    let c = Counter { count };
+    let Counter { count: other } = c;
+    let total = count + other;
+    assert_eq!(total, 2);
+}
@@ -0,0 +1,8 @@
+struct Counter { count: usize }
+fn main() {
+    let n = 1;
+    let c = Counter { count: n };
+    let Counter { count: other } = c;
+    let total = n + other;
+    assert_eq!(total, 2);
+}
@@ -0,0 +1,10 @@
+struct Stack { items: Vec<i32> }
+impl Stack {
+    fn len(&self) -> usize {
+        self.items.len()
+    }
+}
+fn main() {
+    let s = Stack { items: vec![1] };
+    assert_eq!(s.len(), Stack::len(&s));
+}
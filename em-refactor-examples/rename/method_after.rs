@@ -0,0 +1,10 @@
+struct Stack { items: Vec<i32> }
+impl Stack {
+    fn size(&self) -> usize {
+        self.items.len()
+    }
+}
+fn main() {
+    let s = Stack { items: vec![1] };
+    assert_eq!(s.size(), Stack::size(&s));
+}
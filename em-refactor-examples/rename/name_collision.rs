@@ -0,0 +1,5 @@
+fn area() -> u32 { 1 }
+fn volume() -> u32 { 2 }
+fn main() {
+    assert_eq!(area() + volume(), 3);
+}
@@ -0,0 +1,13 @@
+struct Rect { width: u32, height: u32 }
+impl Rect {
+    fn area(&self) -> u32 {
+        self.width * self.height
+    }
+}
+fn main() {
+    let width = 2;
+    let r = Rect { width, height: 3 };
+    let Rect { width, .. } = r;
+    assert_eq!(width, 2);
+    assert_eq!(Rect { width: 1, height: 1 }.area(), 1);
+}
@@ -0,0 +1,13 @@
+struct Rect { w: u32, height: u32 }
+impl Rect {
+    fn area(&self) -> u32 {
+        self.w * self.height
+    }
+}
+fn main() {
+    let width = 2;
+    let r = Rect { w: width, height: 3 };
+    let Rect { w: width, .. } = r;
+    assert_eq!(width, 2);
+    assert_eq!(Rect { w: 1, height: 1 }.area(), 1);
+}
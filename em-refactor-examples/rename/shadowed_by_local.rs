@@ -0,0 +1,5 @@
+fn area() -> u32 { 1 }
+fn main() {
+    let size = 2;
+    assert_eq!(area() + size, 3);
+}
@@ -0,0 +1,16 @@
+#[derive(Debug, PartialEq)]
+struct Point(i32, i32);
+enum Shape { Dot(Point), Line(Point, Point) }
+impl Point {
+    fn origin() -> Point {
+        Point(0, 0)
+    }
+}
+fn main() {
+    let p: Point = Point::origin();
+    let s = Shape::Dot(p);
+    if let Shape::Dot(Point(x, _)) = s {
+        assert_eq!(x, 0);
+    }
+    let _ = Shape::Line(Point(0, 0), Point(1, 1));
+}
@@ -0,0 +1,16 @@
+#[derive(Debug, PartialEq)]
+struct Pos(i32, i32);
+enum Shape { Dot(Pos), Line(Pos, Pos) }
+impl Pos {
+    fn origin() -> Pos {
+        Pos(0, 0)
+    }
+}
+fn main() {
+    let p: Pos = Pos::origin();
+    let s = Shape::Dot(p);
+    if let Shape::Dot(Pos(x, _)) = s {
+        assert_eq!(x, 0);
+    }
+    let _ = Shape::Line(Pos(0, 0), Pos(1, 1));
+}
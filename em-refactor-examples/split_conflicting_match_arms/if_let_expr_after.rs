@@ -0,0 +1,14 @@
+fn main() { }
+fn foo(s1: S) {
+    match s1 {
+        S {f, g: 1} => {
+        let _: i32 = f;
+    },
+        S {f: 1, g: f} => {
+        let _: i32 = f;
+    },
+        _ => {}
+    }
+}
+struct S {f: i32, g: i32}
+// in if let body at line 4: f binds to either S.f or S.g
\ No newline at end of file
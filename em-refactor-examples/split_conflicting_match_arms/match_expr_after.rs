@@ -6,4 +6,4 @@ fn main() {
     }
 }
 struct S {f: i32, g: i32}
-// in match arm body of line 3: f binds to either f or g
+// in match arm body of line 3: f binds to either f or g
\ No newline at end of file
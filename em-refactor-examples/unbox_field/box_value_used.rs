@@ -0,0 +1,8 @@
+struct S { field: Box<i32> }
+fn take(b: Box<i32>) -> i32 { *b }
+fn main() {
+    let b = Box::new(2);
+    let s = S { field: b };
+    let t = S { field: s.field };
+    take(t.field);
+}
\ No newline at end of file
@@ -0,0 +1,5 @@
+struct S { field: i32 }
+fn main() {
+    let s1 = S {field: 0};
+    println!("{}", s1.field);
+}
\ No newline at end of file
@@ -0,0 +1,6 @@
+struct S(T);
+struct T;
+fn main() {
+    let s1 = S (T);
+    let _: T = s1.0;
+}
\ No newline at end of file
@@ -0,0 +1,3 @@
+struct Tree { value: i32, left: Box<Tree> }
+fn main() {
+}
\ No newline at end of file
@@ -0,0 +1,12 @@
+struct S ( i32 );
+fn main() {
+    let s1 = S(0);
+    match s1 {
+        S(a) => {
+            let _: i32 = a;
+        },
+        S(a @ _) => {},
+        S(_) => {},
+        S(..) => {},
+    }
+}
\ No newline at end of file
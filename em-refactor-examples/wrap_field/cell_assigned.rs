@@ -0,0 +1,7 @@
+struct S { field: i32 }
+fn main() {
+    let mut s = S { field: 1 };
+    s.field = 2;
+    s.field *= 3 + 1;
+    assert_eq!(s.field, 8);
+}
@@ -0,0 +1,7 @@
+struct S { field: std::cell::Cell<i32> }
+fn main() {
+    let mut s = S { field: std::cell::Cell::new(1) };
+    s.field.set(2);
+    s.field.set(s.field.get() * (3 + 1));
+    assert_eq!(s.field.get(), 8);
+}
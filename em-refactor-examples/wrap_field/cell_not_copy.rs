@@ -0,0 +1,5 @@
+struct S { field: String }
+fn main() {
+    let s = S { field: String::new() };
+    assert!(s.field.is_empty());
+}
@@ -0,0 +1,7 @@
+struct S(u8, String);
+fn main() {
+    let mut s = S(1, "a".to_owned());
+    s.1.push('b');
+    let f = s.1;
+    assert_eq!(f, "ab");
+}
@@ -0,0 +1,7 @@
+struct S(u8, Option<String>);
+fn main() {
+    let mut s = S(1, Some("a".to_owned()));
+    (*s.1.as_mut().unwrap()).push('b');
+    let f = s.1.unwrap();
+    assert_eq!(f, "ab");
+}
@@ -0,0 +1,5 @@
+struct S { field: Vec<i32> }
+fn main() {
+    let mut s = S { field: vec![] };
+    s.field.push(1);
+}
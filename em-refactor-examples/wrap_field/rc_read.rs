@@ -0,0 +1,6 @@
+struct S { field: String }
+fn main() {
+    let s = S { field: "a".to_owned() };
+    let t = S { field: s.field };
+    assert_eq!(t.field.len(), 1);
+}
@@ -0,0 +1,6 @@
+struct S { field: std::rc::Rc<String> }
+fn main() {
+    let s = S { field: std::rc::Rc::new("a".to_owned()) };
+    let t = S { field: s.field };
+    assert_eq!((*t.field).len(), 1);
+}
@@ -0,0 +1,6 @@
+struct S { field: Vec<i32> }
+fn main() {
+    let mut s = S { field: vec![] };
+    s.field.push(1);
+    assert_eq!(s.field.len(), 1);
+}
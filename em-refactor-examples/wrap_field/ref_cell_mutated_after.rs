@@ -0,0 +1,6 @@
+struct S { field: std::cell::RefCell<Vec<i32>> }
+fn main() {
+    let mut s = S { field: std::cell::RefCell::new(vec![]) };
+    (*s.field.borrow_mut()).push(1);
+    assert_eq!((*s.field.borrow()).len(), 1);
+}
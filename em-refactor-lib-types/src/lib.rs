@@ -3,7 +3,9 @@ use serde::{Serialize, Deserialize};
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct FileStringReplacement {
     pub file_name: String,
+    /// 0-based
     pub line_start: usize,
+    /// 0-based, counted in UTF-16 code units
     pub char_start: usize,
     pub line_end: usize,
     pub char_end: usize,
@@ -145,7 +147,12 @@ impl RefactorOutputs {
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum SelectionType {
+    /// `<byte start>:<byte end>`
     Range(String),
+    /// `<line>:<column>-<line>:<column>`, 1-based, where the column is counted in bytes
+    LineColumn(String),
+    /// `<line>:<column>-<line>:<column>`, 1-based, where the column is counted in UTF-16 code units (as in LSP)
+    LineColumnUtf16(String),
     Comment(String)
 }
 
@@ -157,7 +164,15 @@ pub struct RefactorArgs {
     pub unsafe_: bool,
     pub deps: Vec<String>,
     pub add_comment: bool,
-    pub with_changes: Vec<Vec<FileStringReplacement>>
+    pub with_changes: Vec<Vec<FileStringReplacement>>,
+    /// Options specific to the refactoring, as (name, value)
+    #[serde(default)]
+    pub options: Vec<(String, String)>
+}
+impl RefactorArgs {
+    pub fn get_option(&self, name: &str) -> Option<&str> {
+        self.options.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_str())
+    }
 }
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct CandidateArgs {
@@ -165,6 +180,31 @@ pub struct CandidateArgs {
     pub deps: Vec<String>
 }
 
+/// The arguments, working directory and cargo environment variables of a rustc invocation made by cargo
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RustcInvocation {
+    pub args: Vec<String>,
+    pub cwd: String,
+    pub env: Vec<(String, String)>
+}
+
+/// A request to the refactoring daemon, one per line on stdin
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub enum DaemonRequest {
+    Candidates {
+        refactoring: String
+    },
+    Refactor {
+        refactoring: String,
+        file: String,
+        selection: SelectionType,
+        unsafe_: bool,
+        #[serde(default)]
+        options: Vec<(String, String)>
+    },
+    Shutdown
+}
+
 pub fn create_refactor_tool_marker(item: &str, end: bool) -> String {
     format!("/*{}:{}:{}*/", defs::REFACTOR_TOOL_MARKER, item, if end {"end"} else {"start"})
 }
@@ -172,22 +212,75 @@ pub fn create_refactor_tool_marker(item: &str, end: bool) -> String {
 pub mod defs {
     pub const BOX_FIELD: &str = "box-field";
     pub const BOX_FIELD_CANDIDATES: &str = BOX_FIELD;
+    pub const CHANGE_SIGNATURE: &str = "change-signature";
     pub const CLOSE_OVER_VARIABLES: &str = "close-over-variables";
+    pub const CLOSE_OVER_VARIABLES_SELF: &str = "close-over-variables.self";
     pub const CONVERT_CLOSURE_TO_FUNCTION: &str = "convert-closure-to-function";
     pub const CONVERT_CLOSURE_TO_FUNCTION_FN_DEF: &str = "convert-closure-to-function.fn-def";
+    pub const CONVERT_TO_ASSOCIATED_FUNCTION: &str = "convert-to-associated-function";
+    pub const CONVERT_TO_METHOD: &str = "convert-to-method";
+    pub const ENCAPSULATE_FIELD: &str = "encapsulate-field";
     pub const EXTRACT_BLOCK: &str = "extract-block";
     pub const EXTRACT_BLOCK_BLOCK: &str = "extract-block.block";
     pub const EXTRACT_METHOD: &str = "extract-method";
     pub const EXTRACT_METHOD_CANDIDATES: &str = EXTRACT_METHOD;
+    pub const EXTRACT_VARIABLE: &str = "extract-variable";
+    pub const INLINE_FUNCTION: &str = "inline-function";
+    pub const INLINE_LOCAL: &str = "inline-local";
+    pub const INLINE_MACRO: &str = "inline-macro";
     pub const INTRODUCE_CLOSURE: &str = "introduce-closure";
     pub const INTRODUCE_CLOSURE_CALL_EXPR: &str = "introduce-closure.call-expr";
     pub const LIFT_FUNCTION_DECLARATION: &str = "lift-function-declaration";
+    pub const MOVE_ITEM: &str = "move-item";
     pub const PULL_UP_ITEM_DECLARATIONS: &str = "pull-up-item-declaration";
     pub const PULL_UP_ITEM_DECLARATIONS_STMTS: &str = "pull-up-item-declaration.stmts";
     pub const REMOVE_REFACTORING_COMMENTS: &str = "remove-refactoring-comments";
+    pub const RENAME: &str = "rename";
+    pub const SPLIT_CONFLICTING_MATCH_ARMS: &str = "split-conflicting-match-arms";
+    pub const UNBOX_FIELD: &str = "unbox-field";
+    pub const WRAP_FIELD: &str = "wrap-field";
     pub const REFACTOR_TOOL_MARKER: &str = "refactor-tool";
     pub const ENV_REFACTORING_ARGS: &str = "REFACTORING_ARGS";
     pub const ENV_CANDIDATE_ARGS: &str = "CANDIDATE_ARGS";
+    pub const ENV_CAPTURE_RUSTC_INVOCATION: &str = "CAPTURE_RUSTC_INVOCATION";
+
+    /// All refactorings which can be invoked with `refactor`
+    pub fn refactorings() -> Vec<&'static str> {
+        vec![
+            BOX_FIELD,
+            CHANGE_SIGNATURE,
+            CLOSE_OVER_VARIABLES,
+            CONVERT_CLOSURE_TO_FUNCTION,
+            CONVERT_TO_ASSOCIATED_FUNCTION,
+            CONVERT_TO_METHOD,
+            ENCAPSULATE_FIELD,
+            EXTRACT_BLOCK,
+            EXTRACT_METHOD,
+            EXTRACT_VARIABLE,
+            INLINE_FUNCTION,
+            INLINE_LOCAL,
+            INLINE_MACRO,
+            INTRODUCE_CLOSURE,
+            LIFT_FUNCTION_DECLARATION,
+            MOVE_ITEM,
+            PULL_UP_ITEM_DECLARATIONS,
+            REMOVE_REFACTORING_COMMENTS,
+            RENAME,
+            SPLIT_CONFLICTING_MATCH_ARMS,
+            UNBOX_FIELD,
+            WRAP_FIELD
+        ]
+    }
+
+    /// The refactorings which always fail without options, e.g. `rename` without `name`
+    pub fn refactorings_with_required_options() -> Vec<&'static str> {
+        vec![
+            CHANGE_SIGNATURE,
+            MOVE_ITEM,
+            RENAME,
+            WRAP_FIELD
+        ]
+    }
 
     pub fn extract_method_def() -> Vec<(&'static str, &'static str)> {
         vec![
@@ -45,8 +45,7 @@ impl ExtractBlockCandidateVisitor {
 impl<'ast> Visitor<'ast> for ExtractBlockCandidateVisitor {
     fn visit_item(&mut self, i: &'ast Item) {
         match i.kind {
-            ItemKind::Union(..)
-            | ItemKind::Enum(..) => {},
+            ItemKind::Union(..) => {},
             _ => walk_item(self, i)
         }
     }
@@ -130,13 +129,22 @@ struct G {field: i32}"#;
         assert_eq!(actual, expected);
     }
     #[test]
-    fn shouldnt_collect_nonstructs() {
+    fn should_collect_enum_variants() {
         let input = r#"
 enum Enum {
     EnumItem1,
     EnumTup1(u32),
     EnumStruct1{struct1: u32},
-}
+}"#;
+        let expected = Ok(vec!["u32".to_string(), "struct1".to_string()]);
+
+        let actual = run_ast_query(input, map_all);
+        
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn shouldnt_collect_unions() {
+        let input = r#"
 union Union {
     union_field1: u32,
 }
@@ -1,7 +1,7 @@
 use std::path::Path;
 use crate::refactoring_invocation::get_sys_root;
-use em_refactor_lib_types::{CandidateArgs, RefactorArgs, 
-    defs::{ENV_CANDIDATE_ARGS, ENV_REFACTORING_ARGS}};
+use em_refactor_lib_types::{CandidateArgs, RefactorArgs, RustcInvocation,
+    defs::{ENV_CANDIDATE_ARGS, ENV_CAPTURE_RUSTC_INVOCATION, ENV_REFACTORING_ARGS}};
 
 pub fn arg_value<'a>(
     args: impl IntoIterator<Item = &'a String>,
@@ -32,6 +32,24 @@ pub fn get_candidate_args() -> Option<CandidateArgs> {
     }
 }
 
+/// The driver is linked dynamically against librustc_driver, which is found through these (set by rustup / cargo)
+const DYLIB_PATH_VARS: &[&str] = &["LD_LIBRARY_PATH", "DYLD_LIBRARY_PATH", "DYLD_FALLBACK_LIBRARY_PATH", "PATH"];
+
+///
+/// Returns the invocation made by cargo if the driver was asked to capture it,
+/// so that the same crate can later be compiled without going through cargo
+///
+pub fn get_rustc_invocation(args: &[String]) -> Option<RustcInvocation> {
+    if std::env::var(ENV_CAPTURE_RUSTC_INVOCATION).is_err() {
+        return None;
+    }
+    Some(RustcInvocation {
+        args: args.to_vec(),
+        cwd: std::env::current_dir().ok()?.to_str()?.to_string(),
+        env: std::env::vars().filter(|(k, _)| k.starts_with("CARGO") || k.starts_with("RUSTUP") || DYLIB_PATH_VARS.contains(&k.as_str())).collect()
+    })
+}
+
 pub fn get_refactor_args() -> RefactorArgs {
     std::env::var(ENV_REFACTORING_ARGS)
         .map(|s| serde_json::from_str::<RefactorArgs>(&s).unwrap())
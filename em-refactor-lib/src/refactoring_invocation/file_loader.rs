@@ -22,11 +22,16 @@ impl<T: FileLoader + Send + Sync> InMemoryFileLoader<T> {
     pub fn add_changes(&mut self, changes: Vec<Vec<FileStringReplacement>>) {
         self.changes.extend(changes);
     }
+
+    /// A file which does not exist is created by the changes
+    fn is_created(&self, path: &Path) -> bool {
+        self.changes.iter().flatten().any(|change| Path::new(&change.file_name).eq(path))
+    }
 }
 
 impl<T: FileLoader + Send + Sync> FileLoader for InMemoryFileLoader<T> {
     fn file_exists(&self, path: &Path) -> bool {
-        self.inner_file_loader.file_exists(path)
+        self.inner_file_loader.file_exists(path) || self.is_created(path)
     }
 
     fn abs_path(&self, _: &Path) -> Option<PathBuf> {
@@ -34,7 +39,10 @@ impl<T: FileLoader + Send + Sync> FileLoader for InMemoryFileLoader<T> {
     }
 
     fn read_file(&self, path: &Path) -> io::Result<String> {
-        let mut content = self.inner_file_loader.read_file(path)?;
+        let mut content = match self.inner_file_loader.read_file(path) {
+            Err(_) if self.is_created(path) => String::new(),
+            content => content?
+        };
 
         for changes in &self.changes {
             let mut changes =  changes.clone();
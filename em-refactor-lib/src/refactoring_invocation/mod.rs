@@ -6,6 +6,7 @@ mod run_refactoring;
 mod file_loader;
 mod refactor_definition;
 mod refactor_definition_parser;
+mod refactoring_pipeline;
 mod rustc_pass;
 mod rustc_rerun;
 mod rustc_utils;
@@ -20,9 +21,10 @@ pub(crate) use file_loader::InMemoryFileLoader;
 pub(crate) use my_refactor_callbacks::*;
 pub(crate) use refactor_definition::*;
 pub(crate) use refactor_definition_parser::*;
+pub(crate) use refactoring_pipeline::*;
 pub use run_refactoring::*;
 pub use rustc_pass::{pass_to_rustc, should_pass_to_rustc};
-pub(crate) use rustc_rerun::rustc_rerun;
+pub(crate) use rustc_rerun::{depends_on_changed_crate, remove_changed_outputs, rustc_rerun};
 pub(crate) use rustc_utils::get_sys_root;
 pub(crate) use source_map_context::*;
 pub(crate) use ty_context::*;
@@ -22,11 +22,15 @@ impl RefactoringErrorInternal {
     fn new_int(code: InternalErrorCodes, message: String, is_error: bool) -> Self {
         Self::new(RefactorErrorType::Internal, is_error, message, vec![format!("{:?}", code)])
     }
-    pub(crate) fn used_in_pattern(ident: &str) -> Self {
+    pub(crate) fn bindings_do_not_conflict() -> Self {
+        Self::new_int(InternalErrorCodes::Error,
+            "The alternatives of the pattern bind to the same places, so the arm does not need to be split.".to_owned(), true)
+    }
+    pub(crate) fn not_supported(what: &str, reason: &str) -> Self {
         Self::new_int(InternalErrorCodes::Error,
             format!(
-                "Field: {} is used in a pattern and cannot be boxed.",
-                ident), true)
+                "{}: {}.",
+                what, reason), true)
     }
     pub(crate) fn comment_not_found(name: &str) -> Self {
         Self::new_int(InternalErrorCodes::FileNotFound,
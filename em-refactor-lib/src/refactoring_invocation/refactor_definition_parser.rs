@@ -1,5 +1,7 @@
 use crate::refactoring_invocation::{AstContext, Query, QueryResult, RefactoringErrorInternal};
-use crate::refactorings::{box_field, close_over_variables, convert_closure_to_fn, extract_block, inline_macro, introduce_closure, lift_function_declaration, pull_up_item_declaration, remove_refactoring_comments};
+use crate::refactorings::{box_field, change_signature, close_over_variables, convert_closure_to_fn, convert_to_associated_function, convert_to_method, encapsulate_field, extract_block, extract_variable, inline_function, inline_local, inline_macro, introduce_closure, lift_function_declaration, move_item, pull_up_item_declaration, remove_refactoring_comments, rename, split_conflicting_match_arms, unbox_field, wrap_field};
+use crate::refactorings::change_signature::{NewParam, Param, SignatureChange};
+use crate::refactorings::wrap_field::Wrapper;
 use crate::refactoring_invocation::{AstDiff, TyContext};
 use em_refactor_lib_types::{defs::*, RefactorArgs};
 use rustc_span::Span;
@@ -9,18 +11,119 @@ use rustc_span::Span;
 pub fn argument_list_to_refactor_def(args: RefactorArgs) -> QueryResult<Query<AstDiff>> {
     match args.refactoring.as_ref() {
         BOX_FIELD => Ok(to_ty_query(args, Box::new(box_field::do_refactoring))),
+        CHANGE_SIGNATURE => {
+            let change = parse_signature_change(&args);
+            Ok(Query::AfterParsing(Box::new(move |tcx| {
+                let change = change.clone()?;
+                let span = tcx.map_selection_to_span(args.selection.clone(), args.file.clone())?;
+                change_signature::do_refactoring(tcx, span, args.add_comment, &change)
+            })))
+        },
         CLOSE_OVER_VARIABLES => Ok(to_ty_query(args, Box::new(close_over_variables::do_refactoring))),
-        CONVERT_CLOSURE_TO_FUNCTION => Ok(to_ty_query(args, Box::new(convert_closure_to_fn::do_refactoring))),
+        CONVERT_CLOSURE_TO_FUNCTION => {
+            let name = args.get_option("name").map(|name| name.to_string());
+            Ok(to_ty_query(args, Box::new(move |tcx, span, add_comment| convert_closure_to_fn::do_refactoring(tcx, span, add_comment, name.as_deref()))))
+        },
+        CONVERT_TO_ASSOCIATED_FUNCTION => {
+            let name = args.get_option("name").map(|name| name.to_string());
+            Ok(Query::AfterParsing(Box::new(move |tcx| {
+                let span = tcx.map_selection_to_span(args.selection.clone(), args.file.clone())?;
+                convert_to_associated_function::do_refactoring(tcx, span, args.add_comment, name.as_deref())
+            })))
+        },
+        CONVERT_TO_METHOD => Ok(Query::AfterParsing(Box::new(move |tcx| {
+            let span = tcx.map_selection_to_span(args.selection.clone(), args.file.clone())?;
+            convert_to_method::do_refactoring(tcx, span, args.add_comment)
+        }))),
+        ENCAPSULATE_FIELD => {
+            let getter_mut = parse_bool_option(&args, "getter-mut")?;
+            let setter = parse_bool_option(&args, "setter")?;
+            Ok(to_ty_query(args, Box::new(move |tcx, span, add_comment| encapsulate_field::do_refactoring(tcx, span, add_comment, getter_mut, setter))))
+        },
         EXTRACT_BLOCK => Ok(to_ty_query(args, Box::new(extract_block::do_refactoring))),
+        EXTRACT_VARIABLE => {
+            let name = args.get_option("name").map(|name| name.to_string());
+            let replace_all = parse_bool_option(&args, "replace-all")?;
+            Ok(to_ty_query(args, Box::new(move |tcx, span, add_comment| extract_variable::do_refactoring(tcx, span, add_comment, name.as_deref(), replace_all))))
+        },
         INTRODUCE_CLOSURE => Ok(to_ty_query(args, Box::new(introduce_closure::do_refactoring))),
         LIFT_FUNCTION_DECLARATION => Ok(to_ty_query(args, Box::new(lift_function_declaration::do_refactoring))),
-        "inline-macro" => Ok(to_ast_query(args, Box::new(inline_macro::do_refactoring))),
+        INLINE_FUNCTION => Ok(to_ty_query(args, Box::new(inline_function::do_refactoring))),
+        INLINE_LOCAL => Ok(to_ty_query(args, Box::new(inline_local::do_refactoring))),
+        INLINE_MACRO => Ok(to_ast_query(args, Box::new(inline_macro::do_refactoring))),
+        MOVE_ITEM => {
+            let to = args.get_option("to").map(|to| to.to_string());
+            Ok(Query::AfterParsing(Box::new(move |tcx| {
+                let to = to.as_deref().ok_or_else(|| RefactoringErrorInternal::arg_def("Missing option: to"))?;
+                let span = tcx.map_selection_to_span(args.selection.clone(), args.file.clone())?;
+                move_item::do_refactoring(tcx, span, args.add_comment, to)
+            })))
+        },
         PULL_UP_ITEM_DECLARATIONS => Ok(to_ast_query(args, Box::new(pull_up_item_declaration::do_refactoring))),
         REMOVE_REFACTORING_COMMENTS => Ok(to_ast_query(args, Box::new(remove_refactoring_comments::do_refactoring))),
+        RENAME => {
+            let new_name = args.get_option("name").map(|name| name.to_string());
+            Ok(Query::AfterParsing(Box::new(move |tcx| {
+                let new_name = new_name.as_deref().ok_or_else(|| RefactoringErrorInternal::arg_def("Missing option: name"))?;
+                let span = tcx.map_selection_to_span(args.selection.clone(), args.file.clone())?;
+                rename::do_refactoring(tcx, span, args.add_comment, new_name)
+            })))
+        },
+        SPLIT_CONFLICTING_MATCH_ARMS => Ok(to_ty_query(args, Box::new(split_conflicting_match_arms::do_refactoring))),
+        UNBOX_FIELD => Ok(to_ty_query(args, Box::new(unbox_field::do_refactoring))),
+        WRAP_FIELD => {
+            let wrapper = parse_wrapper_option(&args);
+            Ok(to_ty_query(args, Box::new(move |tcx, span, add_comment| wrap_field::do_refactoring(tcx, span, add_comment, wrapper.clone()?))))
+        },
         s => Err(RefactoringErrorInternal::arg_def(&format!("Unknown refactoring: {}", s)))
     }
 }
 
+fn parse_bool_option(args: &RefactorArgs, name: &str) -> QueryResult<bool> {
+    match args.get_option(name) {
+        None | Some("false") => Ok(false),
+        Some("true") => Ok(true),
+        Some(value) => Err(RefactoringErrorInternal::arg_def(&format!("Invalid value for option {}: {}", name, value)))
+    }
+}
+
+fn parse_wrapper_option(args: &RefactorArgs) -> QueryResult<Wrapper> {
+    match args.get_option("wrapper") {
+        Some(value) => Wrapper::from_name(value)
+            .ok_or_else(|| RefactoringErrorInternal::arg_def(&format!("Invalid value for option wrapper: {}", value))),
+        None => Err(RefactoringErrorInternal::arg_def("Missing option: wrapper"))
+    }
+}
+
+///
+/// `params` is a comma separated list of the indices of the parameters (not counting `self`) in the new order, where `new` is the added parameter.
+/// `add` is the added parameter, and `default` the argument passed for it. At least one of `params` and `add` is required
+///
+fn parse_signature_change(args: &RefactorArgs) -> QueryResult<SignatureChange> {
+    let new_param = match (args.get_option("add"), args.get_option("default")) {
+        (Some(param), Some(arg)) => Some(NewParam { param: param.to_string(), arg: arg.to_string() }),
+        (Some(_), None) => return Err(RefactoringErrorInternal::arg_def("Missing option: default")),
+        (None, Some(_)) => return Err(RefactoringErrorInternal::arg_def("Missing option: add")),
+        (None, None) => None
+    };
+    let params = match args.get_option("params") {
+        Some(value) => Some(value.split(',')
+            .map(str::trim)
+            .filter(|param| !param.is_empty())
+            .map(|param| match param {
+                "new" => Ok(Param::New),
+                _ => param.parse().map(Param::Existing)
+                    .map_err(|_| RefactoringErrorInternal::arg_def(&format!("Invalid value for option params: {}", value)))
+            })
+            .collect::<QueryResult<Vec<_>>>()?),
+        None => None
+    };
+    if params.is_none() && new_param.is_none() {
+        return Err(RefactoringErrorInternal::arg_def("Missing option: params"));
+    }
+    Ok(SignatureChange { params, new_param })
+}
+
 fn to_ast_query(args: RefactorArgs, f: Box<dyn Fn(&AstContext, Span, bool) -> QueryResult<AstDiff> + Send>) -> Query<AstDiff> {
     let args = args.clone();
     Query::AfterExpansion(Box::new(move |ast| {
@@ -35,3 +138,42 @@ fn to_ty_query(args: RefactorArgs, f: Box<dyn Fn(&TyContext, Span, bool) -> Quer
         f(ast, span, args.add_comment)
     }))
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::refactoring_invocation::get_pipeline;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use em_refactor_lib_types::SelectionType;
+
+    #[test]
+    fn all_refactorings_are_defined() {
+        for refactoring in refactorings() {
+            let args = RefactorArgs {
+                refactoring: refactoring.to_owned(),
+                selection: SelectionType::Range("0:0".to_owned()),
+                file: "main.rs".to_owned(),
+                unsafe_: false,
+                deps: vec![],
+                add_comment: false,
+                with_changes: vec![],
+                options: vec![]
+            };
+            assert!(get_pipeline(refactoring).is_some() || argument_list_to_refactor_def(args).is_ok(), "{}", refactoring);
+        }
+    }
+    #[test]
+    fn refactorings_with_required_options_fail_without_options() {
+        let input = "/*refactor-tool:test-id:start*/fn foo(a: i32) {}/*refactor-tool:test-id:end*/\nfn main() { foo(1); }";
+        for refactoring in refactorings() {
+            if get_pipeline(refactoring).is_some() {
+                continue;
+            }
+            let fails_without_options = match run_refactoring(TestInit::from_refactoring(input, refactoring)) {
+                Err(err) => err == RefactoringErrorInternal::arg_def(&err.message),
+                Ok(_) => false
+            };
+            assert_eq!(fails_without_options, refactorings_with_required_options().contains(&refactoring), "{}", refactoring);
+        }
+    }
+}
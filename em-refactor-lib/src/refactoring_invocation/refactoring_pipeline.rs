@@ -0,0 +1,92 @@
+use em_refactor_lib_types::{defs::*, RefactorArgs, RefactorOutputs, SelectionType};
+use crate::refactoring_invocation::{from_error, from_success, run_refactoring};
+
+///
+/// Returns the steps of a composite refactoring, or None if `refactoring` is a single refactoring.
+/// Each step is a refactoring and the id of the comment (inserted by the previous steps) it should select.
+///
+pub(crate) fn get_pipeline(refactoring: &str) -> Option<Vec<(&'static str, &'static str)>> {
+    match refactoring {
+        EXTRACT_METHOD => Some(extract_method_def()),
+        _ => None
+    }
+}
+
+///
+/// Runs all steps of a composite refactoring in this driver invocation.
+/// The changes from each step are not written to disk, but are passed on to the next step through the InMemoryFileLoader.
+///
+/// Outputs one RefactorOutput per step that was run, in order, and stops at the first step that fails.
+///
+pub(crate) fn run_pipeline(refactor_args: &RefactorArgs, steps: &[(&str, &str)], rustc_args: &[String]) -> RefactorOutputs {
+    let mut args = refactor_args.clone();
+    args.add_comment = true;
+
+    let mut outputs = RefactorOutputs::new();
+
+    for (refactoring, comment) in steps {
+        if !comment.is_empty() {
+            args.selection = SelectionType::Comment(comment.to_string());
+        }
+        args.refactoring = refactoring.to_string();
+
+        match run_refactoring(&args, rustc_args, false) {
+            Ok(astdiff) => {
+                outputs.extend(from_success(rustc_args, astdiff.0.clone()));
+                args.with_changes.push(astdiff.0);
+            },
+            Err(err) => {
+                outputs.extend(from_error(rustc_args, err, refactoring));
+                break;
+            }
+        }
+    }
+    outputs
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test_utils::init_main_rs_and_get_args;
+
+    fn run_extract_method(program: &str, selection: &str) -> Vec<(String, usize, usize)> {
+        let (mut rustc_args, d) = init_main_rs_and_get_args(program);
+        rustc_args.push("--crate-name=main".to_owned());
+        let args = RefactorArgs {
+            file: d.path().join("./main.rs").to_str().unwrap().to_owned(),
+            refactoring: EXTRACT_METHOD.to_owned(),
+            selection: SelectionType::Range(selection.to_owned()),
+            unsafe_: false,
+            deps: vec![],
+            add_comment: false,
+            with_changes: vec![],
+            options: vec![]
+        };
+        let steps = get_pipeline(EXTRACT_METHOD).unwrap();
+
+        run_pipeline(&args, &steps, &rustc_args).refactorings.into_iter()
+            .map(|r| (r.errors.first().map(|e| e.at_refactoring.to_string()).unwrap_or_default(), r.replacements.len(), r.errors.len()))
+            .collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn runs_all_steps() {
+        let program = r#"fn bar() {
+    let i = 0;
+    let j = i;
+}"#;
+        let actual = run_extract_method(program, "15:25");
+
+        assert_eq!(actual.len(), extract_method_def().len());
+        assert!(actual.iter().all(|(_, _, errors)| *errors == 0));
+    }
+    #[test]
+    fn stops_at_first_error() {
+        let program = r#"fn bar() {
+    let i = 0;
+}"#;
+        let actual = run_extract_method(program, "0:2");
+
+        assert_eq!(actual, vec![(PULL_UP_ITEM_DECLARATIONS.to_owned(), 0, 1)]);
+    }
+}
@@ -1,26 +1,36 @@
 use em_refactor_lib_types::RefactorArgs;
-use crate::refactoring_invocation::{arg_value, argument_list_to_refactor_def, AstDiff, from_error, from_success, MyRefactorCallbacks, QueryResult, RefactoringErrorInternal, rustc_rerun, serialize, InMemoryFileLoader};
+use crate::refactoring_invocation::{arg_value, argument_list_to_refactor_def, AstDiff, depends_on_changed_crate, from_error, from_success, get_pipeline, MyRefactorCallbacks, pass_to_rustc, QueryResult, RefactoringErrorInternal, remove_changed_outputs, run_pipeline, rustc_rerun, serialize, InMemoryFileLoader};
 use itertools::Itertools;
 
 pub fn run_refactoring_and_output_result(refactor_args: &RefactorArgs, rustc_args: Vec<String>) -> Result<(), i32> {
-    
-    let output = match run_refactoring(refactor_args, &rustc_args) {
-        Err(err) => from_error(&rustc_args, err, &refactor_args.refactoring),
-        Ok(astdiff) => from_success(&rustc_args, astdiff.0)
+    remove_changed_outputs(&rustc_args);
+
+    let output = if let Some(steps) = get_pipeline(&refactor_args.refactoring) {
+        let output = run_pipeline(refactor_args, &steps, &rustc_args);
+        // The steps stop the compilation early, so dependencies must be compiled separately
+        if is_dep(&refactor_args.deps, &rustc_args) {
+            pass_to_rustc(&rustc_args);
+        }
+        output
+    } else {
+        match run_refactoring(refactor_args, &rustc_args, is_dep(&refactor_args.deps, &rustc_args)) {
+            Err(err) => from_error(&rustc_args, err, &refactor_args.refactoring),
+            Ok(astdiff) => from_success(&rustc_args, astdiff.0)
+        }
     };
     print!("{}", serialize(&output).unwrap());
     Ok(())
 }
 
-fn run_refactoring(refactor_args: &RefactorArgs, rustc_args: &Vec<String>) -> QueryResult<AstDiff> {
+pub(crate) fn run_refactoring(refactor_args: &RefactorArgs, rustc_args: &[String], continue_compilation: bool) -> QueryResult<AstDiff> {
 
 
     // 1. Run refactoring callbacks
-    let refactor_res = run_refactoring_internal(rustc_args, refactor_args)?;
+    let refactor_res = run_refactoring_internal(rustc_args, refactor_args, continue_compilation)?;
 
-    // 2. Rerun the compiler to check if any errors were introduced
+    // 2. Rerun the compiler to check if any errors were introduced, in this crate or by the changes to the crates it depends on
     // Runs with default callbacks
-    if !refactor_args.unsafe_ && !refactor_res.0.is_empty() {
+    if !refactor_args.unsafe_ && (!refactor_res.0.is_empty() || depends_on_changed_crate(rustc_args)) {
         let mut combined = refactor_args.with_changes.clone();
         combined.push(refactor_res.0.clone());
         rustc_rerun(combined, &rustc_args)?;
@@ -29,11 +39,11 @@ fn run_refactoring(refactor_args: &RefactorArgs, rustc_args: &Vec<String>) -> Qu
     Ok(refactor_res)
 }
 
-fn run_refactoring_internal(rustc_args: &[String], refactor_args: &RefactorArgs) -> QueryResult<AstDiff> {
+fn run_refactoring_internal(rustc_args: &[String], refactor_args: &RefactorArgs, continue_compilation: bool) -> QueryResult<AstDiff> {
     
     let refactor_def = argument_list_to_refactor_def(refactor_args.clone())?;
 
-    let mut my_refactor = MyRefactorCallbacks::from_arg(refactor_def, is_dep(&refactor_args.deps, rustc_args));
+    let mut my_refactor = MyRefactorCallbacks::from_arg(refactor_def, continue_compilation);
 
     let callbacks: &mut (dyn rustc_driver::Callbacks + Send) = &mut my_refactor;
 
@@ -1,7 +1,8 @@
 use em_refactor_lib_types::FileStringReplacement;
-use crate::refactoring_invocation::{RefactoringErrorInternal, InMemoryFileLoader};
+use crate::refactoring_invocation::{arg_value, RefactoringErrorInternal, InMemoryFileLoader};
 use std::sync::{Arc, Mutex};
 use std::io::Write;
+use std::path::{Path, PathBuf};
 use rustc_session::DiagnosticOutput;
 use serde_json::Value;
 
@@ -51,7 +52,7 @@ pub fn rustc_rerun(changes: Vec<Vec<FileStringReplacement>>, rustc_args: &[Strin
     ));
     file_loader.add_changes(changes);
 
-    let mut rustc_args = rustc_args
+    let mut rustc_args = get_rerun_args(rustc_args)
         .into_iter()
         .filter(|s| !s.starts_with(&"--error-format".to_owned()))
         .filter(|s| !s.starts_with(&"--color".to_owned()))
@@ -69,6 +70,92 @@ pub fn rustc_rerun(changes: Vec<Vec<FileStringReplacement>>, rustc_args: &[Strin
     }
     return Ok(());
 }
+
+///
+/// The outputs of the rerun are written to a separate directory, so that the crates depending on this crate
+/// are analysed with its original metadata, but rerun with the changed metadata.
+///
+const CHANGED_OUTPUT_DIR: &str = "em-refactor";
+
+fn changed_output_dir(out_dir: &Path) -> PathBuf {
+    out_dir.join(CHANGED_OUTPUT_DIR)
+}
+
+///
+/// Maps `<name>=<path>` to the metadata written by the rerun of the crate, if the crate was changed
+///
+fn changed_extern(ext: &str) -> Option<String> {
+    let mut split = ext.splitn(2, '=');
+    let (name, path) = (split.next()?, Path::new(split.next()?));
+    let changed = changed_output_dir(path.parent()?).join(path.file_name()?);
+    if changed.exists() {
+        Some(format!("{}={}", name, changed.to_str()?))
+    } else {
+        None
+    }
+}
+
+///
+/// Maps the values of `flag` in `rustc_args`, which may be given as `flag=value` or `flag value`
+///
+fn map_arg_values(rustc_args: &[String], flag: &str, f: impl Fn(&str) -> String) -> Vec<String> {
+    let mut ret = vec![];
+    let mut args = rustc_args.iter();
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            ret.push(arg.to_string());
+            if let Some(value) = args.next() {
+                ret.push(f(value));
+            }
+        } else if arg.starts_with(&format!("{}=", flag)) {
+            ret.push(format!("{}={}", flag, f(&arg[flag.len() + 1..])));
+        } else {
+            ret.push(arg.to_string());
+        }
+    }
+    ret
+}
+
+fn get_rerun_args(rustc_args: &[String]) -> Vec<String> {
+    let rustc_args = map_arg_values(rustc_args, "--out-dir", |dir| {
+        let dir = changed_output_dir(Path::new(dir));
+        std::fs::create_dir_all(&dir).ok();
+        dir.to_str().unwrap().to_string()
+    });
+    map_changed_externs(&rustc_args)
+}
+
+fn map_changed_externs(rustc_args: &[String]) -> Vec<String> {
+    map_arg_values(rustc_args, "--extern", |ext| changed_extern(ext).unwrap_or_else(|| ext.to_string()))
+}
+
+///
+/// Whether a crate this crate depends on was changed, in which case this crate should be rerun as well
+///
+pub(crate) fn depends_on_changed_crate(rustc_args: &[String]) -> bool {
+    map_changed_externs(rustc_args) != rustc_args
+}
+
+///
+/// Removes the outputs of a previous rerun of this crate, so that they are not used by the crates depending on it
+///
+pub(crate) fn remove_changed_outputs(rustc_args: &[String]) {
+    let out_dir = arg_value(rustc_args, "--out-dir", |_| true);
+    let crate_name = arg_value(rustc_args, "--crate-name", |_| true);
+    let extra_filename = arg_value(rustc_args, "-C", |v| v.starts_with("extra-filename="))
+        .map_or("", |v| &v["extra-filename=".len()..]);
+    if let (Some(out_dir), Some(crate_name)) = (out_dir, crate_name) {
+        let prefix = format!("lib{}{}.", crate_name, extra_filename);
+        if let Ok(entries) = std::fs::read_dir(changed_output_dir(Path::new(out_dir))) {
+            for entry in entries.filter_map(Result::ok) {
+                if entry.file_name().to_str().map_or(false, |f| f.starts_with(&prefix)) {
+                    std::fs::remove_file(entry.path()).ok();
+                }
+            }
+        }
+    }
+}
+
 fn map_rustc_error_to_internal(s: String) -> RefactoringErrorInternal {
 
     let vs = s
@@ -16,10 +16,27 @@ impl<'a> SourceMapContext<'a> {
             SelectionType::Range(r) => {
                 let tup = Self::get_int(&r)?;
                 self.map_span(&file, tup.0, tup.1)
-            }
+            },
+            SelectionType::LineColumn(r) => self.map_line_column(&file, &r, false),
+            SelectionType::LineColumnUtf16(r) => self.map_line_column(&file, &r, true)
         }
     }
 
+    fn map_line_column(&self, file_name: &str, selection: &str, utf16: bool) -> QueryResult<Span> {
+        let file_name_real = FileName::Real(std::path::PathBuf::from(file_name));
+        let source_file = self.source_map.get_source_file(&file_name_real)
+            .ok_or_else(|| RefactoringErrorInternal::file_not_found(file_name))?;
+        let src = match &source_file.src {
+            Some(src) => src.to_string(),
+            // The source of a file imported from another crate is not loaded
+            None => std::fs::read_to_string(file_name)
+                .map_err(|_| RefactoringErrorInternal::int(&format!("The source of {} is not available", file_name)))?
+        };
+        let (from, to) = Self::get_line_column(selection)?;
+
+        self.map_span(file_name, line_column_to_byte(&src, from, utf16)?, line_column_to_byte(&src, to, utf16)?)
+    }
+
     pub(crate) fn map_span(&self, file_name: &str, from: u32, to: u32) -> QueryResult<Span> {
         let file_name_real = FileName::Real(std::path::PathBuf::from(file_name));
         if let Some(source_file) = self.source_map.get_source_file(&file_name_real) {
@@ -41,12 +58,85 @@ impl<'a> SourceMapContext<'a> {
         }
         Err(RefactoringErrorInternal::arg_def("Selection should be formatted as <byte_from>:<byte_to>"))
     }
+    fn get_line_column(selection: &str) -> QueryResult<((u32, u32), (u32, u32))> {
+        let err = || RefactoringErrorInternal::arg_def(&format!("{} should be formatted as <line>:<column>-<line>:<column>", selection));
+        let parse = |s: &str| -> QueryResult<(u32, u32)> {
+            let mut split = s.split(':');
+            match (split.next().map(str::parse), split.next().map(str::parse), split.next()) {
+                (Some(Ok(line)), Some(Ok(col)), None) => Ok((line, col)),
+                _ => Err(err())
+            }
+        };
+        let mut split = selection.split('-');
+        match (split.next(), split.next(), split.next()) {
+            (Some(from), Some(to), None) => Ok((parse(from)?, parse(to)?)),
+            _ => Err(err())
+        }
+    }
     pub(crate) fn span_err(&self, span: Span, is_error: bool) -> RefactoringErrorInternal {
         RefactoringErrorInternal::invalid_selection_with_code(span.lo().0, span.hi().0, &self.get_source(span), is_error)
     }
     pub(crate) fn get_source(&self, span: Span) -> String {
         self.source_map.span_to_snippet(span).unwrap()
     }
+    /// The whitespace at the start of the line containing `span`
+    pub(crate) fn get_indent(&self, span: Span) -> String {
+        let prev = self.source_map.span_to_prev_source(span).unwrap_or_default();
+        let line = prev.rsplit('\n').next().unwrap_or_default();
+        line.chars().take_while(|c| c.is_whitespace()).collect()
+    }
+    ///
+    /// Extends `span` to the whole lines (including one line break), if nothing else is on them
+    ///
+    pub(crate) fn get_lines(&self, span: Span) -> Span {
+        let prev = self.source_map.span_to_prev_source(span).unwrap_or_default();
+        let next = self.source_map.span_to_snippet(span.shrink_to_hi().with_hi(span.hi() + BytePos(1))).unwrap_or_default();
+        let line_start = prev.rsplit('\n').next().unwrap_or_default();
+        if !line_start.trim().is_empty() {
+            return span;
+        }
+        let span = span.with_lo(span.lo() - BytePos(line_start.len() as u32));
+        if next == "\n" {
+            span.with_hi(span.hi() + BytePos(1))
+        } else if prev.len() > line_start.len() {
+            span.with_lo(span.lo() - BytePos(1))
+        } else {
+            span
+        }
+    }
+}
+
+///
+/// Maps a 1-based (line, column) to a byte offset in `src`, where the column is counted in bytes or UTF-16 code units.
+/// The column may point to the end of the line, but not inside a character.
+///
+fn line_column_to_byte(src: &str, (line, col): (u32, u32), utf16: bool) -> QueryResult<u32> {
+    let err = || RefactoringErrorInternal::arg_def(&format!("{}:{} is not a valid position", line, col));
+    if line == 0 || col == 0 {
+        return Err(err());
+    }
+    let mut lines = src.split('\n');
+    let line_start = lines.by_ref().take(line as usize - 1).map(|l| l.len() + 1).sum::<usize>();
+    let line_text = lines.next().ok_or_else(err)?;
+    let col = col as usize - 1;
+
+    let offset = if utf16 {
+        let mut units = 0;
+        let mut offset = None;
+        for (i, c) in line_text.char_indices().chain(std::iter::once((line_text.len(), ' '))) {
+            if units == col {
+                offset = Some(i);
+                break;
+            }
+            units += c.len_utf16();
+        }
+        offset.ok_or_else(err)?
+    } else if col <= line_text.len() && line_text.is_char_boundary(col) {
+        col
+    } else {
+        return Err(err());
+    };
+    Ok((line_start + offset) as u32)
 }
 
 #[cfg(test)]
@@ -59,4 +149,40 @@ mod test {
 
         assert_eq!(actual, expected);
     }
+    #[test]
+    fn line_column_from_args() {
+        let expected = Ok(((12, 5), (18, 20)));
+        let actual = SourceMapContext::get_line_column("12:5-18:20");
+
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn line_column_from_invalid_args() {
+        assert!(SourceMapContext::get_line_column("12:5").is_err());
+        assert!(SourceMapContext::get_line_column("12:5-18").is_err());
+        assert!(SourceMapContext::get_line_column("12:5-18:20:1").is_err());
+    }
+    #[test]
+    fn line_column_to_byte_utf8() {
+        let src = "fn main() {\n    let ø = 1;\n}";
+
+        assert_eq!(line_column_to_byte(src, (1, 1), false), Ok(0));
+        assert_eq!(line_column_to_byte(src, (2, 5), false), Ok(16));
+        assert_eq!(line_column_to_byte(src, (2, 11), false), Ok(22));
+        assert_eq!(line_column_to_byte(src, (2, 16), false), Ok(27));
+        assert!(line_column_to_byte(src, (2, 10), false).is_err()); // inside 'ø'
+        assert!(line_column_to_byte(src, (2, 17), false).is_err());
+        assert!(line_column_to_byte(src, (4, 1), false).is_err());
+    }
+    #[test]
+    fn line_column_to_byte_utf16() {
+        let src = "fn main() {\n    let ø = \"𝄞\";\n}";
+
+        assert_eq!(line_column_to_byte(src, (2, 10), true), Ok(22));
+        assert_eq!(line_column_to_byte(src, (2, 14), true), Ok(26));
+        assert_eq!(line_column_to_byte(src, (2, 16), true), Ok(30));
+        assert_eq!(line_column_to_byte(src, (2, 18), true), Ok(32));
+        assert!(line_column_to_byte(src, (2, 15), true).is_err()); // inside '𝄞'
+        assert!(line_column_to_byte(src, (2, 19), true).is_err());
+    }
 }
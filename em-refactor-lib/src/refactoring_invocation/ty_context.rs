@@ -1,7 +1,8 @@
 use rustc_hir::{StructField, HirId};
+use rustc_hir::def_id::{CRATE_DEF_INDEX, DefId};
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
-use em_refactor_lib_types::FileStringReplacement;
+use em_refactor_lib_types::{FileStringReplacement, SelectionType};
 use crate::refactorings::utils::map_change_from_span;
 use super::{QueryResult, SourceMapContext};
 pub struct TyContext<'a>(pub TyCtxt<'a>);
@@ -22,8 +23,26 @@ impl<'a> TyContext<'a> {
         map_change_from_span(self.0.sess.source_map(), span, replacement)
     }
 
+    ///
+    /// Maps the selection to a span, where the file may also be a source file of another crate (which this crate depends on)
+    ///
+    pub(crate) fn map_selection_to_span(&self, selection: SelectionType, file: String) -> QueryResult<Span> {
+        self.source().map_selection_to_span(selection.clone(), file.clone()).or_else(|err| {
+            if err.is_error {
+                return Err(err);
+            }
+            // The source files of other crates are added to the source map when a span of the crate is used,
+            // and relative paths are stored as absolute paths in the metadata
+            for krate in self.0.crates() {
+                self.0.def_span(DefId { krate: *krate, index: CRATE_DEF_INDEX });
+            }
+            let file = std::env::current_dir().map(|dir| dir.join(&file).to_string_lossy().to_string()).unwrap_or(file);
+            self.source().map_selection_to_span(selection, file).map_err(|_| err)
+        })
+    }
+
+    /// The struct item, or the enum variant, which contains the field
     pub(crate) fn get_struct_hir_id(&self, field: &StructField) -> HirId {
-        let struct_def_id = field.hir_id.owner.to_def_id();
-        self.0.hir().as_local_hir_id(struct_def_id).unwrap()
+        self.0.hir().get_parent_node(field.hir_id)
     }
 }
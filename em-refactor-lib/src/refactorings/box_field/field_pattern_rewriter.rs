@@ -0,0 +1,343 @@
+use em_refactor_lib_types::FileStringReplacement;
+use rustc_hir::{Arm, BinOpKind, Expr, ExprKind, Guard, HirId, LocalSource, MatchSource, Node, Pat, PatKind};
+use rustc_middle::mir::Mutability;
+use rustc_middle::ty::{self, TyCtxt, TypeckTables};
+use rustc_span::{BytePos, Span};
+use crate::refactoring_invocation::{QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::fresh_name;
+use crate::refactorings::visitors::hir::{collect_binding_names, collect_locals};
+
+const CANNOT_BOX: &str = "The field cannot be boxed";
+
+///
+/// A pattern bound to the boxed field, e.g. `Some(x)` in `S { field: Some(x) }`
+///
+struct FieldPattern<'v> {
+    pat: &'v Pat<'v>,
+    /// The field unboxed, as a place which can be used in a match guard
+    place: String,
+    /// The field unboxed, as a value which can be matched against the pattern
+    value: String,
+    is_refutable: bool,
+    bindings: Vec<HirId>
+}
+
+///
+/// Where the struct pattern binding the field occurs
+///
+#[derive(Clone, Copy)]
+enum PatternContext<'v> {
+    /// The arm, the kind of match and all the arms of the match
+    Arm(&'v Arm<'v>, MatchSource, &'v [Arm<'v>]),
+    Local(Span),
+    Param(&'v Expr<'v>)
+}
+
+impl PatternContext<'_> {
+    fn span(&self) -> Span {
+        match self {
+            Self::Arm(arm, ..) => arm.span,
+            Self::Local(span) => *span,
+            Self::Param(body) => body.span
+        }
+    }
+}
+
+///
+/// Rewrites the patterns bound to the field, after the field is boxed.
+///
+/// The field is bound to a fresh variable `x` instead, and the pattern is matched against `*x`:
+/// - in a match arm, with `matches!` in the guard (if the pattern is refutable) and a `let` or `match` in the body
+/// - in `if let` and `while let`, with an `if let` in the body
+/// - in a `let` statement or a parameter, with a `let` after the statement or at the start of the function body
+///
+/// `field_ident` is the name of the field, for named fields, and `name` the name of the fresh variables
+///
+pub fn rewrite_field_patterns(tcx: &TyContext, field_patterns: &[(Span, HirId)], field_ident: Option<&str>, name: &str) -> QueryResult<Vec<FileStringReplacement>> {
+    let hir = tcx.0.hir();
+    let mut changes = vec![];
+    let mut contexts: Vec<(PatternContext, Vec<FieldPattern>)> = vec![];
+    // The names of the new variables, by the span of the context
+    let mut new_names = vec![];
+
+    for (span, hir_id) in field_patterns {
+        let pat = match hir.get(*hir_id) {
+            Node::Pat(pat) | Node::Binding(pat) => pat,
+            _ => return Err(tcx.source().span_err(*span, true))
+        };
+        let body_id = hir.body_owned_by(hir.get_parent_item(*hir_id));
+        let body = hir.body(body_id);
+        let tables = tcx.0.body_tables(body_id);
+        let param_env = tcx.0.param_env(hir.body_owner_def_id(body_id).to_def_id());
+        let (context, ref_mode) = get_context(tcx.0, tables, pat)?;
+
+        let mut bindings = vec![];
+        let (mut moves, mut borrows_mut) = (false, false);
+        pat.each_binding(|_, binding_hir_id, binding_span, _| {
+            bindings.push(binding_hir_id);
+            match tables.pat_binding_modes().get(binding_hir_id) {
+                Some(ty::BindingMode::BindByValue(_)) => {
+                    moves |= !tables.node_type(binding_hir_id).is_copy_modulo_regions(tcx.0, param_env, binding_span);
+                },
+                Some(ty::BindingMode::BindByReference(Mutability::Mut)) => borrows_mut = true,
+                _ => {}
+            }
+        });
+        let is_refutable = is_refutable(tables, pat);
+
+        // The field does not need to be bound if the pattern matches anything and binds nothing
+        let binding = if bindings.is_empty() && !is_refutable {
+            "_".to_owned()
+        } else {
+            let mut used_names = collect_binding_names(tcx.0, body, body.value.span);
+            for param in body.params {
+                param.pat.each_binding(|_, _, _, ident| used_names.push(format!("{}", ident)));
+            }
+            used_names.extend(new_names.iter().filter(|(s, _)| *s == context.span()).map(|(_, n)| n).cloned());
+            let new_name = fresh_name(name, &used_names);
+            new_names.push((context.span(), new_name.to_string()));
+
+            let (mode, place, value) = match ref_mode {
+                Some(Mutability::Mut) => ("", format!("**{}", new_name), format!("&mut **{}", new_name)),
+                Some(Mutability::Not) => ("", format!("**{}", new_name), format!("&**{}", new_name)),
+                None if moves => (if borrows_mut { "mut " } else { "" }, format!("*{}", new_name), format!("*{}", new_name)),
+                None => (if borrows_mut { "ref mut " } else { "ref " }, format!("**{}", new_name), format!("**{}", new_name))
+            };
+            let field_pattern = FieldPattern { pat, place, value, is_refutable, bindings };
+            match contexts.iter_mut().find(|(c, _)| c.span() == context.span()) {
+                Some((_, patterns)) => patterns.push(field_pattern),
+                None => contexts.push((context, vec![field_pattern]))
+            }
+            if field_ident == Some(&new_name) {
+                changes.push(tcx.map_change(*span, format!("{}{}", mode, new_name))?);
+                continue;
+            }
+            format!("{}{}", mode, new_name)
+        };
+        let replacement = match field_ident {
+            Some(ident) => format!("{}: {}", ident, binding),
+            None => binding
+        };
+        changes.push(tcx.map_change(*span, replacement)?);
+    }
+
+    let mut guarded_matches = vec![];
+    for (context, field_patterns) in contexts {
+        let lets = field_patterns.iter()
+            .filter(|p| !p.is_refutable)
+            .map(|p| format!("let {} = {};", tcx.get_source(p.pat.span), p.value))
+            .collect::<Vec<_>>();
+        let refutable = field_patterns.iter().filter(|p| p.is_refutable).collect::<Vec<_>>();
+        let if_lets = |else_: &str| refutable.iter()
+            .map(|p| (format!("{{ if let {} = {} ", tcx.get_source(p.pat.span), p.value), format!("{} }}", else_)))
+            .collect::<Vec<_>>();
+
+        match context {
+            PatternContext::Arm(arm, MatchSource::Normal, arms) => {
+                if let Some(Guard::If(guard)) = &arm.guard {
+                    let locals = collect_locals(guard);
+                    if field_patterns.iter().any(|p| p.bindings.iter().any(|b| locals.contains(b))) {
+                        return Err(RefactoringErrorInternal::not_supported(CANNOT_BOX, "a variable bound in the field's pattern is used in a match guard"));
+                    }
+                }
+                changes.extend(add_guard(tcx, arm, &refutable)?);
+                if !refutable.is_empty() && !guarded_matches.iter().any(|other: &&[Arm]| other.as_ptr() == arms.as_ptr()) {
+                    guarded_matches.push(arms);
+                }
+                let matches = refutable.iter()
+                    .filter(|p| !p.bindings.is_empty())
+                    .map(|p| (format!("match {} {{ {} => ", p.value, tcx.get_source(p.pat.span)), ", _ => unreachable!() }".to_owned()))
+                    .collect::<Vec<_>>();
+                changes.extend(add_to_body(tcx, arm.body, &lets, &matches)?);
+            },
+            PatternContext::Arm(_, MatchSource::IfLetDesugar { contains_else_clause: true }, _) if !refutable.is_empty() => {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_BOX, "the field is matched in an `if let` with an `else` branch"));
+            },
+            PatternContext::Arm(arm, MatchSource::IfLetDesugar { .. }, _) => {
+                changes.extend(add_to_body(tcx, arm.body, &lets, &if_lets(""))?);
+            },
+            PatternContext::Arm(arm, MatchSource::WhileLetDesugar, _) => {
+                changes.extend(add_to_body(tcx, arm.body, &lets, &if_lets(" else { break }"))?);
+            },
+            PatternContext::Local(stmt_span) if refutable.is_empty() => {
+                let indent = tcx.source().get_indent(stmt_span);
+                changes.push(tcx.map_change(
+                    stmt_span.shrink_to_hi(),
+                    lets.iter().map(|l| format!("\n{}{}", indent, l)).collect())?);
+            },
+            PatternContext::Param(body) if refutable.is_empty() => {
+                changes.extend(add_to_body(tcx, body, &lets, &[])?);
+            },
+            _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_BOX, "the field is matched in a pattern which cannot be rewritten"))
+        }
+    }
+
+    // The match may no longer be exhaustive, as the guards are not taken into account
+    for arms in guarded_matches {
+        if let Some(last_arm) = arms.last() {
+            let tables = tcx.0.typeck_tables_of(last_arm.hir_id.owner.to_def_id());
+            if last_arm.guard.is_none() && !is_refutable(tables, last_arm.pat) {
+                continue;
+            }
+            let catch_all = tcx.map_change(
+                last_arm.body.span.shrink_to_hi(),
+                format!(",\n{}_ => unreachable!()", tcx.source().get_indent(last_arm.span)))?;
+            // Added after the changes to the body of the last arm
+            match changes.iter_mut().find(|c| c.file_name == catch_all.file_name && c.byte_start == catch_all.byte_start && c.byte_end == catch_all.byte_end) {
+                Some(change) => change.replacement.push_str(&catch_all.replacement),
+                None => changes.push(catch_all)
+            }
+        }
+    }
+
+    Ok(changes)
+}
+
+///
+/// Where the pattern `pat` occurs, and the default binding mode of the bindings in the pattern,
+/// i.e. if the struct is matched through a reference
+///
+fn get_context<'v>(tcx: TyCtxt<'v>, tables: &TypeckTables<'v>, pat: &'v Pat<'v>) -> QueryResult<(PatternContext<'v>, Option<Mutability>)> {
+    let hir = tcx.hir();
+    let mut ref_mode = None;
+    let mut is_explicit_ref = false;
+    let mut hir_id = pat.hir_id;
+    loop {
+        hir_id = hir.get_parent_node(hir_id);
+        match hir.get(hir_id) {
+            Node::Pat(parent) | Node::Binding(parent) => {
+                match parent.kind {
+                    PatKind::Or(..) => {
+                        return Err(RefactoringErrorInternal::not_supported(CANNOT_BOX, "the field is matched in a pattern with alternatives"));
+                    },
+                    PatKind::Ref(..) => is_explicit_ref = true,
+                    _ => {}
+                }
+                if !is_explicit_ref {
+                    for ty in tables.pat_adjustments().get(parent.hir_id).into_iter().flatten() {
+                        if let ty::Ref(_, _, mutbl) = ty.kind {
+                            ref_mode = if ref_mode == Some(Mutability::Not) { ref_mode } else { Some(mutbl) };
+                        }
+                    }
+                }
+            },
+            Node::Arm(arm) => {
+                if let Node::Expr(Expr { kind: ExprKind::Match(_, arms, source), .. }) = hir.get(hir.get_parent_node(hir_id)) {
+                    return Ok((PatternContext::Arm(arm, *source, arms), ref_mode));
+                }
+                break;
+            },
+            Node::Local(local) if matches!(local.source, LocalSource::Normal) => {
+                return Ok((PatternContext::Local(hir.span(hir.get_parent_node(hir_id))), ref_mode));
+            },
+            Node::Param(_) => {
+                let body = hir.body(hir.body_owned_by(hir.get_parent_node(hir_id)));
+                return Ok((PatternContext::Param(&body.value), ref_mode));
+            },
+            _ => break
+        }
+    }
+    Err(RefactoringErrorInternal::not_supported(CANNOT_BOX, "the field is matched in a pattern which cannot be rewritten"))
+}
+
+fn is_refutable(tables: &TypeckTables, pat: &Pat) -> bool {
+    let mut is_refutable = false;
+    pat.walk(|p| {
+        match p.kind {
+            PatKind::Wild | PatKind::Binding(..) | PatKind::Tuple(..) | PatKind::Ref(..) | PatKind::Box(..) => {},
+            PatKind::Struct(..) | PatKind::TupleStruct(..) => {
+                is_refutable |= !matches!(tables.pat_ty(p).kind, ty::Adt(adt_def, _) if adt_def.variants.len() == 1);
+            },
+            _ => is_refutable = true
+        }
+        !is_refutable
+    });
+    is_refutable
+}
+
+///
+/// The pattern with the bindings removed, e.g. `Some(_)` for `Some(x)`, so that it can be used in a match guard
+///
+fn get_pattern_without_bindings(tcx: &TyContext, pat: &Pat) -> String {
+    let mut replacements: Vec<(Span, String)> = vec![];
+    pat.walk(|p| {
+        if replacements.iter().any(|(span, _)| span.contains(p.span)) {
+            return false;
+        }
+        match p.kind {
+            PatKind::Binding(_, _, _, None) => replacements.push((p.span, "_".to_owned())),
+            PatKind::Binding(_, _, _, Some(sub_pat)) => replacements.push((p.span, get_pattern_without_bindings(tcx, sub_pat))),
+            PatKind::Struct(_, fields, _) => {
+                for field in fields.iter().filter(|f| f.is_shorthand) {
+                    replacements.push((field.span, format!("{}: _", field.ident)));
+                }
+                return true;
+            },
+            _ => return true
+        }
+        false
+    });
+
+    let mut source = tcx.get_source(pat.span);
+    replacements.sort_by_key(|(span, _)| span.lo());
+    for (span, replacement) in replacements.into_iter().rev() {
+        let lo = (span.lo() - pat.span.lo()).0 as usize;
+        let hi = (span.hi() - pat.span.lo()).0 as usize;
+        source.replace_range(lo..hi, &replacement);
+    }
+    source
+}
+
+fn add_guard(tcx: &TyContext, arm: &Arm, field_patterns: &[&FieldPattern]) -> QueryResult<Vec<FileStringReplacement>> {
+    if field_patterns.is_empty() {
+        return Ok(vec![]);
+    }
+    let conditions = field_patterns.iter()
+        .map(|p| format!("matches!({}, {})", p.place, get_pattern_without_bindings(tcx, p.pat)))
+        .collect::<Vec<_>>()
+        .join(" && ");
+
+    match &arm.guard {
+        Some(Guard::If(guard)) if matches!(guard.kind, ExprKind::Binary(op, ..) if op.node == BinOpKind::Or) => Ok(vec![
+            tcx.map_change(guard.span.shrink_to_lo(), format!("{} && (", conditions))?,
+            tcx.map_change(guard.span.shrink_to_hi(), ")".to_owned())?
+        ]),
+        Some(Guard::If(guard)) => Ok(vec![tcx.map_change(guard.span.shrink_to_lo(), format!("{} && ", conditions))?]),
+        None => Ok(vec![tcx.map_change(arm.pat.span.shrink_to_hi(), format!(" if {}", conditions))?])
+    }
+}
+
+///
+/// Adds the `lets` at the start of `body`, and wraps the body in the prefixes and suffixes of `wrappers`
+///
+fn add_to_body(tcx: &TyContext, body: &Expr, lets: &[String], wrappers: &[(String, String)]) -> QueryResult<Vec<FileStringReplacement>> {
+    let mut prefix = wrappers.iter().map(|(prefix, _)| prefix.as_str()).collect::<String>();
+    let mut suffix = wrappers.iter().rev().map(|(_, suffix)| suffix.as_str()).collect::<String>();
+    let mut changes = vec![];
+
+    if !lets.is_empty() {
+        let source = tcx.get_source(body.span);
+        let block_start = body.span.with_lo(body.span.lo() + BytePos(1)).shrink_to_lo();
+        match body.kind {
+            ExprKind::Block(block, None) if source.starts_with('{') && block.stmts.is_empty() && block.expr.is_none() => {
+                return Ok(vec![tcx.map_change(body.span, format!("{}{{ {} }}{}", prefix, lets.join(" "), suffix))?]);
+            },
+            ExprKind::Block(..) if source.starts_with('{') && source.contains('\n') => {
+                let indent = tcx.source().get_indent(body.span);
+                changes.push(tcx.map_change(block_start, lets.iter().map(|l| format!("\n{}    {}", indent, l)).collect())?);
+            },
+            ExprKind::Block(..) if source.starts_with('{') => {
+                changes.push(tcx.map_change(block_start, lets.iter().map(|l| format!(" {}", l)).collect())?);
+            },
+            _ => {
+                prefix.push_str(&format!("{{ {} ", lets.join(" ")));
+                suffix.insert_str(0, " }");
+            }
+        }
+    }
+    if !prefix.is_empty() {
+        changes.push(tcx.map_change(body.span.shrink_to_lo(), prefix)?);
+        changes.push(tcx.map_change(body.span.shrink_to_hi(), suffix)?);
+    }
+    Ok(changes)
+}
@@ -1,13 +1,28 @@
 use rustc_hir::HirId;
+use rustc_hir::def::Res;
+use rustc_middle::ty::{AdtDef, TyCtxt};
 use rustc_span::Span;
 
 use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
 use super::{box_named_field, box_tuple_field};
 use super::visitors::collect_field;
 
+pub mod field_pattern_rewriter;
+
 pub struct StructPatternCollection {
     pub new_bindings: Vec<HirId>,
-    pub other: Vec<Span>
+    /// The field patterns which are not bindings, as the span of the field pattern and the pattern bound to the field
+    pub other: Vec<(Span, HirId)>
+}
+
+///
+/// Checks if the path of a struct expression, constructor call or pattern (resolved to `res`, with the type `adt_def`)
+/// is the struct or enum variant `struct_hir_id`
+///
+pub fn is_struct_or_variant(tcx: TyCtxt, adt_def: &AdtDef, res: Res, struct_hir_id: HirId) -> bool {
+    adt_def.did == struct_hir_id.owner.to_def_id()
+        && res != Res::Err
+        && adt_def.variant_of_res(res).def_id == tcx.hir().local_def_id(struct_hir_id)
 }
 
 /// Box field refactoring
@@ -20,16 +35,17 @@ pub struct StructPatternCollection {
 ///
 /// Steps
 /// - F <- the field which should be boxed
-/// - T <- the type of the struct (from Items -> StructStruct)
+/// - T <- the struct (from Items -> StructStruct) or the enum variant which contains F
 /// - Ps <- All StructPattern where PathInExpression has type T and F is in StructPatternFields
 /// - Vs <- All StructExpressions where PathInExpression has type T and F is in StructExprFields
 /// - Fs <- All FieldExpressions where Expression has type T and F is IDENTIFIER
-/// - if |Ps| > 0 then abort
 /// - Change F's type in T to Box<>
 /// - for V in Vs:
 ///   - Add Box::new around V
 /// - for F' in Fs
 ///   - Add * around F'
+/// - for P in Ps where F is bound to anything else than a variable:
+///   - Bind F to a fresh variable x instead, and match the pattern against *x (in the match guard, the arm body or a `let`)
 
 pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
     if let Some((field, index)) = collect_field(tcx.0, span) {
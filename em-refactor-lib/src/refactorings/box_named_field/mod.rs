@@ -1,25 +1,24 @@
 use rustc_hir::{HirId, Item, ItemKind};
 use rustc_span::Span;
 
-use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
+use super::box_field::field_pattern_rewriter::rewrite_field_patterns;
 use super::visitors::{collect_local_variable_use, collect_struct_field_access_expressions};
 use struct_expression_collector::collect_struct_expressions;
 use struct_named_pattern_collector::collect_struct_named_patterns;
 
-mod struct_expression_collector;
+pub mod struct_expression_collector;
 pub mod struct_named_pattern_collector;
 
 pub fn do_refactoring(tcx: &TyContext, struct_hir_id: HirId, field_ident: &str, field_ty_span: Span) -> QueryResult<AstDiff> {
 
     let struct_patterns = collect_struct_named_patterns(tcx, struct_hir_id, field_ident);
 
-    if !struct_patterns.other.is_empty() {
-        return Err(RefactoringErrorInternal::used_in_pattern(&field_ident));
-    }
     let mut changes = vec![tcx.map_change(
         field_ty_span,
         format!("Box<{}>", tcx.get_source(field_ty_span)),
     )?];
+    changes.extend(rewrite_field_patterns(tcx, &struct_patterns.other, Some(field_ident), field_ident)?);
 
     let (struct_expressions, struct_expression_shorthands) = collect_struct_expressions(tcx, struct_hir_id, field_ident)?;
 
@@ -1,9 +1,10 @@
-use rustc_hir::{BodyId, Expr, ExprKind, Field, HirId, ImplItem, ImplItemKind, Item, ItemKind};
+use rustc_hir::{BodyId, Expr, ExprKind, Field, HirId, ImplItem, ImplItemKind, Item, ItemKind, QPath};
 use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr, walk_impl_item, walk_item, walk_crate};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
 use crate::refactoring_invocation::{QueryResult, TyContext};
+use crate::refactorings::box_field::is_struct_or_variant;
 
 ///
 /// Collect all places where a given struct occurs in a struct expression where also `field_ident` occurs.
@@ -58,7 +59,7 @@ struct StructExpressionCollector<'v> {
 }
 
 impl StructExpressionCollector<'_> {
-    fn expr_resolves_to_struct(&mut self, expr: &Expr) -> bool {
+    fn expr_resolves_to_struct(&mut self, expr: &Expr, qpath: &QPath) -> bool {
         if self.body_ids.is_empty() {
             return false;
         }
@@ -67,13 +68,13 @@ impl StructExpressionCollector<'_> {
         let typecheck_table = self.tcx.typeck_tables_of(def_id);
         if let Some(expr_type) = typecheck_table.expr_ty_adjusted_opt(expr) {
             if let Some(adt_def) = expr_type.ty_adt_def() {
-                return adt_def.did == self.struct_hir_id.owner.to_def_id();
+                return is_struct_or_variant(self.tcx, adt_def, typecheck_table.qpath_res(qpath, expr.hir_id), self.struct_hir_id);
             }
         } 
         false
     }
-    fn handle_expr(&mut self, expr: &Expr, fields: &[Field]) {
-        if self.expr_resolves_to_struct(expr) {
+    fn handle_expr(&mut self, expr: &Expr, qpath: &QPath, fields: &[Field]) {
+        if self.expr_resolves_to_struct(expr, qpath) {
             for fp in fields.iter() {
                 if format!("{}", fp.ident) == self.field_ident {
                     if fp.is_shorthand {
@@ -93,8 +94,8 @@ impl<'v> Visitor<'v> for StructExpressionCollector<'v> {
         NestedVisitorMap::All(self.tcx.hir())
     }
     fn visit_expr(&mut self, expr: &'v Expr) {
-        if let ExprKind::Struct(_, fields, _) = &expr.kind {
-            self.handle_expr(expr, fields);
+        if let ExprKind::Struct(qpath, fields, _) = &expr.kind {
+            self.handle_expr(expr, qpath, fields);
         }
         walk_expr(self, expr);
     }
@@ -147,7 +148,7 @@ mod test {
         Box::new(move |ty| {
             let span = ty.source().map_span(&file_name, from, to)?;
             let (field, _) = collect_field(ty.0, span).unwrap();
-            let (span1, span2) = collect_struct_expressions(&ty, field.hir_id, &ty.get_source(span)).unwrap();
+            let (span1, span2) = collect_struct_expressions(&ty, ty.get_struct_hir_id(field), &ty.get_source(span)).unwrap();
 
             Ok((
                 span1.iter().map(|s| ty.get_source(*s)).collect::<Vec<_>>(),
@@ -1,9 +1,9 @@
-use rustc_hir::{HirId, Item, Pat, PatKind};
+use rustc_hir::{HirId, Item, Pat, PatKind, QPath};
 use rustc_hir::intravisit::{walk_item, walk_pat, walk_crate, NestedVisitorMap, Visitor};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
-use super::super::box_field::StructPatternCollection;
+use super::super::box_field::{is_struct_or_variant, StructPatternCollection};
 use if_chain::if_chain;
 use crate::refactoring_invocation::TyContext;
 
@@ -64,12 +64,12 @@ struct StructPatternCollector<'v> {
 }
 
 impl StructPatternCollector<'_> {
-    fn path_resolves_to_struct(&self, pat: &Pat) -> bool {
+    fn path_resolves_to_struct(&self, pat: &Pat, qpath: &QPath) -> bool {
         let typecheck_table = self.tcx.typeck_tables_of(pat.hir_id.owner.to_def_id());
         if_chain! {
             if let Some(pat_type) = typecheck_table.pat_ty_opt(pat);
             if let Some(adt_def) = pat_type.ty_adt_def();
-            if adt_def.did == self.struct_hir_id.owner.to_def_id();
+            if is_struct_or_variant(self.tcx, adt_def, typecheck_table.qpath_res(qpath, pat.hir_id), self.struct_hir_id);
             then {
                 true
             } else {
@@ -77,8 +77,8 @@ impl StructPatternCollector<'_> {
             }
         }
     }
-    fn struct_pattern_used(&mut self, pattern_kind: &PatKind, span: Span) {
-        match pattern_kind {
+    fn struct_pattern_used(&mut self, pat: &Pat, span: Span) {
+        match &pat.kind {
             // Wildcard patterns match anything, so no changes are needed
             PatKind::Wild => {},
             PatKind::Binding(_, hir_id, _, None) => { 
@@ -88,11 +88,11 @@ impl StructPatternCollector<'_> {
                 if let PatKind::Wild = at_pattern.kind {
                     self.patterns.new_bindings.push(*hir_id);
                 } else {
-                    self.patterns.other.push(span);
+                    self.patterns.other.push((span, pat.hir_id));
                 }
             },
             _ =>  {
-                self.patterns.other.push(span);
+                self.patterns.other.push((span, pat.hir_id));
             }
         }
     } 
@@ -104,13 +104,12 @@ impl<'v> Visitor<'v> for StructPatternCollector<'v> {
         NestedVisitorMap::All(self.tcx.hir())
     }
     fn visit_pat(&mut self, p: &'v Pat) {
-        if self.path_resolves_to_struct(p) {
-            if_chain! {
-                if let PatKind::Struct(_, fields, _) = &p.kind;
-                if let Some(fp) = fields.iter().find(|e| {format!("{}", e.ident) == self.field_ident});
-                then {
-                    self.struct_pattern_used(&fp.pat.kind, fp.span);
-                }
+        if_chain! {
+            if let PatKind::Struct(qpath, fields, _) = &p.kind;
+            if self.path_resolves_to_struct(p, qpath);
+            if let Some(fp) = fields.iter().find(|e| {format!("{}", e.ident) == self.field_ident});
+            then {
+                self.struct_pattern_used(fp.pat, fp.span);
             }
         }
         walk_pat(self, p);
@@ -139,7 +138,7 @@ mod test {
 
             Ok((
                 fields.new_bindings.len(),
-                fields.other.iter().map(|s| ty.get_source(*s)).collect::<Vec<_>>()))
+                fields.other.iter().map(|(s, _)| ty.get_source(*s)).collect::<Vec<_>>()))
         })
     }
     #[test]
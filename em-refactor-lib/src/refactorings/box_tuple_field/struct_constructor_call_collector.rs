@@ -1,10 +1,12 @@
 use rustc_hir::{BodyId, Expr, ExprKind, FnDecl, HirId};
+use rustc_hir::def::Res;
 use rustc_hir::intravisit::{FnKind, walk_expr, walk_fn, walk_crate, NestedVisitorMap, Visitor};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
 use if_chain::if_chain;
 use crate::refactoring_invocation::TyContext;
+use crate::refactorings::box_field::is_struct_or_variant;
 
 ///
 /// Collect all places where a given struct occurs in a call expression.
@@ -47,11 +49,11 @@ struct StructConstructorCallCollector<'v> {
 }
 
 impl StructConstructorCallCollector<'_> {
-    fn expr_resolves_to_struct(&self, expr: &Expr) -> bool {
+    fn expr_resolves_to_struct(&self, expr: &Expr, res: Res) -> bool {
         let typecheck_table = self.tcx.typeck_tables_of(expr.hir_id.owner.to_def_id());
         if let Some(expr_type) = typecheck_table.expr_ty_adjusted_opt(expr) {
             if let Some(adt_def) = expr_type.ty_adt_def() {
-                return adt_def.did == self.struct_hir_id.owner.to_def_id();
+                return is_struct_or_variant(self.tcx, adt_def, res, self.struct_hir_id);
             }
         } 
         false
@@ -60,10 +62,11 @@ impl StructConstructorCallCollector<'_> {
         if let ExprKind::Path(qpath) = &function.kind {
             let typecheck_table = self.tcx.typeck_tables_of(expr.hir_id.owner.to_def_id());
 
+            let res = typecheck_table.qpath_res(qpath, function.hir_id);
             if_chain! {
-                if let Some(defid) = typecheck_table.qpath_res(qpath, function.hir_id).opt_def_id();
+                if let Some(defid) = res.opt_def_id();
                 if self.tcx.is_constructor(defid);
-                if self.expr_resolves_to_struct(expr);
+                if self.expr_resolves_to_struct(expr, res);
                 if let Some(expr_init) = args.get(self.field_index);
                 then {
                     self.field.push(expr_init.span);
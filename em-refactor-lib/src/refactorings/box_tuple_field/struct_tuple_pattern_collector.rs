@@ -1,9 +1,9 @@
-use rustc_hir::{HirId, Pat, PatKind};
+use rustc_hir::{HirId, Pat, PatKind, QPath};
 use rustc_hir::intravisit::{walk_pat, walk_crate, NestedVisitorMap, Visitor};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
-use super::super::box_field::StructPatternCollection;
+use super::super::box_field::{is_struct_or_variant, StructPatternCollection};
 use crate::refactoring_invocation::TyContext;
 use if_chain::if_chain;
 
@@ -64,21 +64,25 @@ struct StructPatternCollector<'v> {
 }
 
 impl StructPatternCollector<'_> {
-    fn path_resolves_to_struct(&self, pat: &Pat) -> bool {
+    ///
+    /// The number of fields of the struct or variant if the pattern is the struct or variant
+    ///
+    fn path_resolves_to_struct(&self, pat: &Pat, qpath: &QPath) -> Option<usize> {
         let typecheck_table = self.tcx.typeck_tables_of(pat.hir_id.owner.to_def_id());
+        let res = typecheck_table.qpath_res(qpath, pat.hir_id);
         if_chain! {
             if let Some(pat_type) = typecheck_table.pat_ty_opt(pat);
             if let Some(adt_def) = pat_type.ty_adt_def();
-            if adt_def.did == self.struct_hir_id.owner.to_def_id();
+            if is_struct_or_variant(self.tcx, adt_def, res, self.struct_hir_id);
             then {
-                true
+                Some(adt_def.variant_of_res(res).fields.len())
             } else {
-                false
+                None
             }
         }
     }
-    fn struct_pattern_used(&mut self, pattern_kind: &PatKind, span: Span) {
-        match pattern_kind {
+    fn struct_pattern_used(&mut self, pat: &Pat, span: Span) {
+        match &pat.kind {
             // Wildcard patterns match anything, so no changes are needed
             PatKind::Wild => {},
             PatKind::Binding(_, hir_id, _, None) => { 
@@ -88,11 +92,11 @@ impl StructPatternCollector<'_> {
                 if let PatKind::Wild = at_pattern.kind {
                     self.patterns.new_bindings.push(*hir_id);
                 } else {
-                    self.patterns.other.push(span);
+                    self.patterns.other.push((span, pat.hir_id));
                 }
             },
             _ =>  {
-                self.patterns.other.push(span);
+                self.patterns.other.push((span, pat.hir_id));
             }
         }
     } 
@@ -104,19 +108,35 @@ impl<'v> Visitor<'v> for StructPatternCollector<'v> {
         NestedVisitorMap::All(self.tcx.hir())
     }
     fn visit_pat(&mut self, p: &'v Pat) {
-        if self.path_resolves_to_struct(p) {
-            if_chain! {
-                if let PatKind::TupleStruct(_, fields, _) = &p.kind;
-                if let Some(field) = fields.get(self.field_index);
-                then {
-                    self.struct_pattern_used(&field.kind, field.span);
-                }
+        if_chain! {
+            if let PatKind::TupleStruct(qpath, fields, ddpos) = &p.kind;
+            if let Some(field_count) = self.path_resolves_to_struct(p, qpath);
+            if let Some(field) = get_field_pattern(fields, *ddpos, field_count, self.field_index);
+            then {
+                self.struct_pattern_used(field, field.span);
             }
         }
         walk_pat(self, p);
     }
 }
 
+///
+/// The pattern of the field at `field_index`, taking into account the fields skipped by `..` at `ddpos`
+///
+fn get_field_pattern<'v>(fields: &[&'v Pat<'v>], ddpos: Option<usize>, field_count: usize, field_index: usize) -> Option<&'v Pat<'v>> {
+    match ddpos {
+        Some(ddpos) if field_index >= ddpos => {
+            let skipped = field_count - fields.len();
+            if field_index < ddpos + skipped {
+                None
+            } else {
+                fields.get(field_index - skipped).copied()
+            }
+        },
+        _ => fields.get(field_index).copied()
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -134,7 +154,7 @@ mod test {
 
             Ok((
                 fields.new_bindings.len(),
-                fields.other.iter().map(|s| ty.get_source(*s)).collect::<Vec<_>>()))
+                fields.other.iter().map(|(s, _)| ty.get_source(*s)).collect::<Vec<_>>()))
         })
     }
 
@@ -0,0 +1,326 @@
+use rustc_hir::{BindingAnnotation, Body, Expr, ExprKind, FnDecl, Generics, ImplItem, ImplItemKind, Item, ItemKind, Node, Pat, PatKind, Path, QPath};
+use rustc_hir::def::Res;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::AssocItemContainer;
+use rustc_span::{BytePos, Span, symbol::Ident};
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::get_source_with_replacements;
+use crate::refactorings::visitors::collect_local_variable_use;
+use crate::refactorings::visitors::hir::{collect_all_calls, collect_calls, collect_function_definition, collect_method_definition, collect_qpaths, is_pure, FnCall};
+
+const CANNOT_CHANGE: &str = "The signature cannot be changed";
+
+/// A parameter of the new signature
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Param {
+    /// The existing parameter with this index, not counting `self`
+    Existing(usize),
+    /// The added parameter
+    New
+}
+
+/// The added parameter, and the argument which is passed for it at the call sites
+#[derive(Debug, Clone, PartialEq)]
+pub struct NewParam {
+    /// E.g. `verbose: bool`
+    pub param: String,
+    pub arg: String
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SignatureChange {
+    /// The parameters in the new order, where the parameters which are not listed are removed.
+    /// None keeps the existing parameters
+    pub params: Option<Vec<Param>>,
+    pub new_param: Option<NewParam>
+}
+
+impl SignatureChange {
+    ///
+    /// The parameters of the new signature, given the number of parameters (not counting `self`) of the function.
+    /// The added parameter is last, unless its position is given
+    ///
+    fn get_params(&self, param_count: usize) -> QueryResult<Vec<Param>> {
+        let mut params = self.params.clone().unwrap_or_else(|| (0..param_count).map(Param::Existing).collect());
+        if self.new_param.is_some() && !params.contains(&Param::New) {
+            params.push(Param::New);
+        }
+        for (i, param) in params.iter().enumerate() {
+            match param {
+                Param::Existing(index) if *index >= param_count => {
+                    return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, &format!("the function has no parameter with index {}", index)));
+                },
+                Param::New if self.new_param.is_none() => {
+                    return Err(RefactoringErrorInternal::arg_def("Missing option: add"));
+                },
+                _ => {}
+            }
+            if params[..i].contains(param) {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "a parameter is listed more than once"));
+            }
+        }
+        if params == (0..param_count).map(Param::Existing).collect::<Vec<_>>() {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "the signature is not changed"));
+        }
+        Ok(params)
+    }
+}
+
+/// Change signature
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the selected function or inherent method
+/// - P <- the new parameters, as indices of the existing parameters, and possibly an added parameter
+/// - for each removed parameter: abort if it is used in F
+/// - for each path to F: abort if it is not the callee of a call (e.g. F is passed as a function pointer)
+/// - for each call of F (`f(a)`, `S::f(s, a)` or `s.f(a)`):
+///   - abort if the call is in a macro, or if an argument of a removed parameter may have side effects
+///   - abort if the arguments are reordered, and an argument may have side effects which change the others
+///   - replace the arguments (not counting the receiver) with the arguments of P, where the added parameter is passed the default argument
+/// - replace the parameters (not counting `self`) of F with P
+///
+/// Calls inside the arguments of another call of F are rewritten as well.
+/// When the selection is in a source file of another (local) crate, the calls of the function defined at the selection are rewritten.
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, change: &SignatureChange) -> QueryResult<AstDiff> {
+    let def_id = match get_function(tcx, span)? {
+        Some(def_id) => def_id,
+        None => return Ok(AstDiff(vec![]))
+    };
+
+    let has_self = match tcx.0.opt_associated_item(def_id) {
+        Some(item) => {
+            let is_inherent = match item.container {
+                AssocItemContainer::ImplContainer(impl_def_id) => tcx.0.impl_trait_ref(impl_def_id).is_none(),
+                AssocItemContainer::TraitContainer(_) => false
+            };
+            if !is_inherent {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "methods of traits are not supported"));
+            }
+            item.fn_has_self_parameter
+        },
+        None => false
+    };
+    let self_count = if has_self { 1 } else { 0 };
+    let param_count = tcx.0.fn_sig(def_id).skip_binder().inputs().len() - self_count;
+    let params = change.get_params(param_count)?;
+
+    let calls = collect_calls(tcx, def_id);
+    if collect_qpaths(tcx, def_id).iter().any(|path| !calls.iter().any(|call| call.callee == Some(*path))) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "the function is used other than being called"));
+    }
+
+    // Calls in the arguments of other calls are rewritten first
+    let mut calls = calls.iter().collect::<Vec<_>>();
+    calls.sort_by_key(|call| call.expr.span.hi() - call.expr.span.lo());
+    let mut replacements = vec![];
+    for call in calls {
+        if call.expr.span.from_expansion() {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "the function is called in a macro"));
+        }
+        replacements.push(rewrite_call(tcx, call, has_self, &params, change, &replacements)?);
+    }
+
+    if def_id.is_local() {
+        let hir_id = tcx.0.hir().as_local_hir_id(def_id).unwrap();
+        let (ident, sig, generics, body_id) = match tcx.0.hir().get(hir_id) {
+            Node::Item(Item { kind: ItemKind::Fn(sig, generics, body_id), ident, .. }) => (*ident, sig, generics, *body_id),
+            Node::ImplItem(ImplItem { kind: ImplItemKind::Fn(sig, body_id), ident, generics, .. }) => (*ident, sig, generics, *body_id),
+            _ => return Err(tcx.source().span_err(span, false))
+        };
+        let body = tcx.0.hir().body(body_id);
+        check_removed_params_are_unused(tcx, body, self_count, &params)?;
+        replacements.push(rewrite_definition(tcx, ident, generics, sig.decl, body, self_count, &params, change)?);
+    }
+
+    let mut changes = vec![];
+    for (span, replacement) in &replacements {
+        if !replacements.iter().any(|(other, _)| other != span && other.contains(*span)) {
+            changes.push(tcx.map_change(*span, replacement.to_string())?);
+        }
+    }
+    Ok(AstDiff(changes))
+}
+
+///
+/// The function or method defined at `span`. When `span` is in a source file of another (local) crate,
+/// this is the function defined there if it is called in this crate, and None otherwise
+///
+pub(crate) fn get_function(tcx: &TyContext, span: Span) -> QueryResult<Option<DefId>> {
+    if tcx.0.sess.source_map().lookup_char_pos(span.lo()).file.is_imported() {
+        return Ok(collect_all_calls(tcx).iter().map(|call| call.def_id).find(|def_id| !def_id.is_local() && tcx.0.def_span(*def_id) == span));
+    }
+    collect_function_definition(tcx, span).ok().map(|fn_def| fn_def.hir_id)
+        .or_else(|| collect_method_definition(tcx, span))
+        .map(Some)
+        .ok_or_else(|| tcx.source().span_err(span, false))
+}
+
+fn check_removed_params_are_unused(tcx: &TyContext, body: &Body, self_count: usize, params: &[Param]) -> QueryResult<()> {
+    for (i, param) in body.params[self_count..].iter().enumerate() {
+        if params.contains(&Param::Existing(i)) {
+            continue;
+        }
+        let mut used = None;
+        param.pat.each_binding(|_, hir_id, _, ident| {
+            if used.is_none() && !collect_local_variable_use(tcx, hir_id).is_empty() {
+                used = Some(ident);
+            }
+        });
+        if let Some(ident) = used {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, &format!("the parameter {} is used in the function", ident)));
+        }
+    }
+    Ok(())
+}
+
+fn rewrite_call(tcx: &TyContext, call: &FnCall, has_self: bool, params: &[Param], change: &SignatureChange, replacements: &[(Span, String)]) -> QueryResult<(Span, String)> {
+    let (receiver, args) = if has_self {
+        (call.args.first(), call.args.get(1..).unwrap_or_default())
+    } else {
+        (None, call.args)
+    };
+    let arg_spans = args.iter().map(|arg| arg.span.source_callsite()).collect::<Vec<_>>();
+
+    for (i, arg) in args.iter().enumerate() {
+        if !params.contains(&Param::Existing(i)) && !is_pure(arg) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, &format!("the argument {} of a removed parameter may have side effects", tcx.get_source(arg_spans[i]))));
+        }
+    }
+    let kept = params.iter().filter_map(|p| match p { Param::Existing(i) => Some(*i), Param::New => None }).collect::<Vec<_>>();
+    let is_reordered = kept.windows(2).any(|w| w[0] > w[1]);
+    let order_dependent = kept.iter().filter(|i| !is_order_independent(tcx, &args[**i])).count();
+    if is_reordered && order_dependent > 1 && kept.iter().any(|i| !is_pure(&args[*i])) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "the arguments would be evaluated in a different order"));
+    }
+
+    let new_args = params.iter()
+        .map(|param| match param {
+            Param::Existing(i) => get_source_with_replacements(tcx.0, arg_spans[*i], replacements),
+            Param::New => change.new_param.as_ref().map(|p| p.arg.to_string()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+    // The receiver of a method call is not inside the parentheses
+    let receiver = receiver.filter(|_| !call.is_method_call()).map(|receiver| receiver.span.source_callsite());
+    let close = call.expr.span.hi() - BytePos(1);
+
+    Ok(rewrite_list(receiver, &arg_spans, new_args, close))
+}
+
+///
+/// The value of a literal or an immutable local variable cannot be changed by evaluating the other arguments
+///
+fn is_order_independent(tcx: &TyContext, arg: &Expr) -> bool {
+    match &arg.kind {
+        ExprKind::Lit(_) => true,
+        ExprKind::Path(QPath::Resolved(None, Path { res: Res::Local(hir_id), .. })) => match tcx.0.hir().get(*hir_id) {
+            Node::Binding(Pat { kind: PatKind::Binding(BindingAnnotation::Unannotated, ..), .. }) => true,
+            _ => false
+        },
+        _ => false
+    }
+}
+
+fn rewrite_definition(tcx: &TyContext, ident: Ident, generics: &Generics, decl: &FnDecl, body: &Body, self_count: usize, params: &[Param], change: &SignatureChange) -> QueryResult<(Span, String)> {
+    // The span of a parameter may include the following `,` or `)`
+    let spans = body.params.iter().zip(decl.inputs).map(|(param, ty)| param.pat.span.to(ty.span)).collect::<Vec<_>>();
+    let self_param = spans.first().cloned().filter(|_| self_count == 1);
+    let param_spans = spans[self_count..].to_vec();
+    let new_params = params.iter()
+        .map(|param| match param {
+            Param::Existing(i) => tcx.get_source(param_spans[*i]),
+            Param::New => change.new_param.as_ref().map(|p| p.param.to_string()).unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    // The parameters are followed by `)`, and preceded by `(` after the name and the generic parameters
+    let after_params = param_spans.last().cloned().or(self_param).unwrap_or_else(|| {
+        let start = if generics.params.is_empty() { ident.span } else { generics.span };
+        tcx.get_source(start.between(body.value.span)).find('(')
+            .map_or(start.shrink_to_hi(), |i| start.shrink_to_hi().with_hi(start.hi() + BytePos(i as u32 + 1)))
+    });
+    let close = tcx.get_source(after_params.between(body.value.span)).find(')')
+        .map(|i| after_params.hi() + BytePos(i as u32))
+        .ok_or_else(|| RefactoringErrorInternal::int("The parameters of the function were not found"))?;
+
+    Ok(rewrite_list(self_param, &param_spans, new_params, close))
+}
+
+///
+/// Replaces the elements of a comma separated list with `new`, where the list may start with the element `first`, which is kept.
+/// `close` is the position of the closing delimiter of the list
+///
+fn rewrite_list(first: Option<Span>, elements: &[Span], new: Vec<String>, close: BytePos) -> (Span, String) {
+    match (elements.first(), elements.last(), first) {
+        (Some(lo), Some(hi), _) if !new.is_empty() => (lo.to(*hi), new.join(", ")),
+        (Some(_), Some(hi), Some(first)) => (first.shrink_to_hi().to(*hi), "".to_owned()),
+        // Also removes a trailing comma
+        (Some(lo), _, None) => (lo.with_hi(close), "".to_owned()),
+        (_, _, Some(first)) if !new.is_empty() => (first.shrink_to_hi(), format!(", {}", new.join(", "))),
+        _ => (Span::with_root_ctxt(close, close), new.join(", "))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_CHANGE;
+    const NAME: &str = "change-signature";
+
+    #[test]
+    fn nested_calls() {
+        let input = r#"/*refactor-tool:test-id:start*/fn foo(a: i32, b: i32) -> i32 { a - b }/*refactor-tool:test-id:end*/
+fn bar() -> i32 {
+    foo(foo(1, 2), 3)
+}"#;
+        let expected = Ok(r#"/*refactor-tool:test-id:start*/fn foo(b: i32, a: i32) -> i32 { a - b }/*refactor-tool:test-id:end*/
+fn bar() -> i32 {
+    foo(3, foo(2, 1))
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("params", "1,0"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn remove_all_params() {
+        let input = r#"/*refactor-tool:test-id:start*/fn foo(_a: i32, _b: i32,) {}/*refactor-tool:test-id:end*/
+fn bar() {
+    foo(1, 2,);
+}"#;
+        let expected = Ok(r#"/*refactor-tool:test-id:start*/fn foo() {}/*refactor-tool:test-id:end*/
+fn bar() {
+    foo();
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("params", ""));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn add_param_to_generic_fn() {
+        let input = r#"/*refactor-tool:test-id:start*/fn foo<T: Fn() -> i32>() {}/*refactor-tool:test-id:end*/
+fn bar() {
+    foo::<fn() -> i32>();
+}"#;
+        let expected = Ok(r#"/*refactor-tool:test-id:start*/fn foo<T: Fn() -> i32>(n: i32) {}/*refactor-tool:test-id:end*/
+fn bar() {
+    foo::<fn() -> i32>(1);
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("add", "n: i32").with_option("default", "1"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn trait_method() {
+        let input = r#"trait T { fn foo(&self, a: i32); }
+struct S;
+impl T for S {
+    /*refactor-tool:test-id:start*/fn foo(&self, _a: i32) {}/*refactor-tool:test-id:end*/
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_CHANGE, "methods of traits are not supported"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("params", ""));
+        assert_eq!(actual, expected);
+    }
+}
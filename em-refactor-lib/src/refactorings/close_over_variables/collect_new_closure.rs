@@ -3,15 +3,32 @@ use rustc_span::Span;
 use crate::refactoring_invocation::{QueryResult, TyContext};
 use super::{expr_use_visit::{collect_vars, TypeKind}, local_use_collector::collect_local_uses};
 use itertools::Itertools;
+use em_refactor_lib_types::{create_refactor_tool_marker, defs::CLOSE_OVER_VARIABLES_SELF};
 
 pub struct NewClosure {
-    pub params: String,
-    pub args: String,
+    /// The names and types of the parameters
+    pub params: Vec<(String, String)>,
+    pub args: Vec<String>,
     /// Used to introduce deref when we introduce & or &mut
     pub uses: Vec<Span>,
     /// Used to rewrite occurences of 'self' with self_
     /// Assuming that there isnt already a variable called self_ in scope
-    pub selfs: Vec<Span>
+    pub selfs: Vec<Span>,
+    /// The index of the argument `self`, which becomes the receiver when the function is lifted into the impl
+    pub self_arg: Option<usize>
+}
+
+impl NewClosure {
+    ///
+    /// The arguments, where `self` is marked with a comment if `add_comment` is set, so lift function declaration can make it the receiver
+    ///
+    pub fn get_args(&self, add_comment: bool) -> Vec<String> {
+        let mut args = self.args.clone();
+        if let (Some(i), true) = (self.self_arg, add_comment) {
+            args[i] = format!("{}{}{}", create_refactor_tool_marker(CLOSE_OVER_VARIABLES_SELF, false), args[i], create_refactor_tool_marker(CLOSE_OVER_VARIABLES_SELF, true));
+        }
+        args
+    }
 }
 
 pub fn collect_vars3<'v>(tcx: &'v TyContext, body_id: BodyId) -> QueryResult<NewClosure> {
@@ -23,6 +40,7 @@ pub fn collect_vars3<'v>(tcx: &'v TyContext, body_id: BodyId) -> QueryResult<New
 
     let mut hir_ids = vec![];
     let mut self_hir_ids = vec![];
+    let mut self_arg = None;
 
     for (k, val) in &vars.iter()
         .sorted_by_key(|(_, id, ..)| id.to_string())
@@ -61,6 +79,7 @@ pub fn collect_vars3<'v>(tcx: &'v TyContext, body_id: BodyId) -> QueryResult<New
         }
         if k == "self" {
             self_hir_ids.push(val[0].0);
+            self_arg = Some(args.len());
         }
 
         let param_ident = if k == "self" {
@@ -68,8 +87,13 @@ pub fn collect_vars3<'v>(tcx: &'v TyContext, body_id: BodyId) -> QueryResult<New
         } else {
             k.to_string()
         };
+        // `&mut self` which is only read is passed as `&Self`
+        let ty = match ty.get(..5) {
+            Some("&mut ") if k == "self" && !is_moved && !is_mutated => format!("&{}", &ty[5..]),
+            _ => ty.to_string()
+        };
 
-        params.push(format!("{}: {}{}", param_ident, modif_param, ty));
+        params.push((param_ident, format!("{}{}", modif_param, ty)));
         args.push(format!("{}{}", modif_arg, k));
     }
 
@@ -77,9 +101,10 @@ pub fn collect_vars3<'v>(tcx: &'v TyContext, body_id: BodyId) -> QueryResult<New
     let selfs = collect_local_uses(tcx, self_hir_ids, body_id)?;
 
     Ok(NewClosure{
-        params: params.join(", "),
-        args: args.join(", "),
+        params,
+        args,
         uses,
-        selfs
+        selfs,
+        self_arg
     })
 }
\ No newline at end of file
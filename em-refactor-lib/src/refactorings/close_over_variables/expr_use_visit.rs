@@ -1,8 +1,9 @@
 use rustc_hir::{BodyId, Node, hir_id::HirId};
 use rustc_infer::infer::{TyCtxtInferExt};
-use rustc_middle::ty::{self, TyCtxt, print::with_crate_prefix};
+use rustc_middle::ty::{self, TyCtxt};
 use rustc_typeck::expr_use_visitor::{ConsumeMode, Delegate, ExprUseVisitor, Place, PlaceBase};
 use rustc_span::Span;
+use crate::refactorings::utils::format_ty;
 use crate::refactorings::visitors::hir::ExpressionUseKind;
 use crate::refactoring_invocation::{QueryResult, RefactoringErrorInternal};
 
@@ -68,7 +69,7 @@ impl<'tcx> VariableCollectorDelegate<'tcx> {
                 _ => TypeKind::None
             };
 
-            let type_ = with_crate_prefix(|| format!("{}", pat_type));
+            let type_ = format_ty(self.tcx, pat_type);
 
             return (type_, kind);
         }
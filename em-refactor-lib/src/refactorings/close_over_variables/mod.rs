@@ -1,11 +1,13 @@
 use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
 use rustc_span::Span;
-use crate::refactorings::visitors::hir::{collect_anonymous_closure};
+use crate::refactorings::visitors::hir::{collect_anonymous_closure, collect_awaited_async_block};
 
 mod expr_use_visit;
 mod local_use_collector;
 mod collect_new_closure;
 
+pub(crate) use collect_new_closure::collect_vars3;
+
 /// Close Over Variables
 /// 
 /// ## Algorithm
@@ -15,7 +17,14 @@ mod collect_new_closure;
 ///    a. Add V' as parameters of C'
 ///    b. Add V' as arguments of M'
 ///    c. If V' is a borrow, add deref to all occurences of V' in C'
-pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+///    d. If V' is `self`, rename it to `self_` in C', and mark the argument with a comment (if `add_comment` is set)
+///
+/// An awaited async block (introduced instead of a closure for a selection containing `.await`) is left as is,
+/// and its variables are closed over when it is converted to a function
+pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryResult<AstDiff> {
+    if collect_awaited_async_block(tcx, span).is_some() {
+        return Ok(AstDiff(vec![]));
+    }
     let closure = collect_anonymous_closure(tcx, span)?;
 
     let new_closure = collect_new_closure::collect_vars3(tcx, closure.body_id)?;
@@ -23,19 +32,21 @@ pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryR
     let mut changes = vec![];
 
     if new_closure.params.len() > 0 {
+        let params = new_closure.params.iter().map(|(ident, ty)| format!("{}: {}", ident, ty)).collect::<Vec<_>>().join(", ");
         let params = if closure.has_params {
-            format!(", {}", new_closure.params)
+            format!(", {}", params)
         } else {
-            new_closure.params.to_string()
+            params
         };
         changes.push(tcx.map_change(closure.get_next_param_pos(), params)?);
     }
 
     if new_closure.args.len() > 0 {
+        let args = new_closure.get_args(add_comment).join(", ");
         let args = if closure.has_params {
-            format!(", {}", new_closure.args)
+            format!(", {}", args)
         } else {
-            new_closure.args.to_string()
+            args
         };
         changes.push(tcx.map_change(closure.get_next_arg_pos(), args)?);
     }
@@ -1,8 +1,12 @@
-use rustc_hir::{BodyId, Expr, ExprKind, Param, PatKind};
-use rustc_middle::ty::{TyS, print::with_crate_prefix};
+use rustc_hir::{BodyId, BorrowKind, Expr, ExprKind, Generics, GenericParamKind, HirId, ImplItem, Item, ItemKind, LifetimeParamKind, Local, Mutability, Node, Param, PatKind, Path, QPath, TyKind};
+use rustc_hir::def::Res;
+use rustc_middle::ty::TyS;
+use rustc_middle::ty::adjustment::{Adjust, Adjustment};
 use rustc_span::Span;
 use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
-use crate::refactorings::visitors::hir::collect_anonymous_closure;
+use crate::refactorings::close_over_variables::collect_vars3;
+use crate::refactorings::utils::{format_ty, get_source_with_replacements, is_identifier};
+use crate::refactorings::visitors::hir::{collect_anonymous_closure, collect_awaited_async_block};
 use em_refactor_lib_types::{create_refactor_tool_marker, defs::CONVERT_CLOSURE_TO_FUNCTION_FN_DEF};
 
 /// Convert anonymous closure to function
@@ -18,16 +22,33 @@ use em_refactor_lib_types::{create_refactor_tool_marker, defs::CONVERT_CLOSURE_T
 ///     Run inference on where?
 /// 
 /// Change Closure Expr to block return fn
-pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryResult<AstDiff> {
+///
+/// The function is named by the option `name`. A parameter passed a variable (or a reference to it) gets the type from the declaration of the variable,
+/// and the generic parameters and where clauses of the enclosing item, which are used by the types of the function, are added to it
+///
+/// An awaited async block `async { .. }.await` (introduced instead of a closure for a selection containing `.await`) becomes an async function
+pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool, name: Option<&str>) -> QueryResult<AstDiff> {
+    let ident = match name {
+        Some(name) if !is_identifier(name) => return Err(RefactoringErrorInternal::invalid_argument(format!("`{}` is not a valid function name", name))),
+        Some(name) => name.to_string(),
+        None => fresh_name()
+    };
+    if let Some((await_expr, body_id)) = collect_awaited_async_block(tcx, span) {
+        return convert_async_block(tcx, await_expr, body_id, ident, add_comment);
+    }
+
     let closure = collect_anonymous_closure(tcx, span)?;
 
     let mut changes = vec![];
     let body = tcx.0.hir().body(closure.body_id);
 
     let mut new_fn = FunctionDefinition {
-        ident: fresh_name(),
+        is_async: false,
+        ident,
+        generics: vec![],
         params: vec![],
         output: None,
+        where_predicates: vec![],
         body: tcx.get_source(body.value.span),
         body_is_block: if let ExprKind::Block(..) = body.value.kind {true} else {false}
     };
@@ -35,33 +56,90 @@ pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryRe
     let mut i = 0;
     for param in body.params {
         let ident = get_ident(param);
-        let type_s = 
-            format_ty(get_type_of_expression(tcx, &closure.args_1[i], closure.body_id)?);
+        let arg = &closure.args_1[i];
+        let type_s = match get_declared_ty(tcx, arg, closure.body_id) {
+            Some(ty) => ty,
+            None => format_ty(tcx.0, get_type_of_expression(tcx, arg, closure.body_id)?)
+        };
         new_fn.params.push((ident, type_s));
         i += 1;
     }
 
     let out = get_type_of_expression(tcx, &body.value, closure.body_id)?;
     if !out.is_unit() {
-        new_fn.output = Some(format_ty(out));
+        new_fn.output = Some(format_ty(tcx.0, out));
     }
 
+    let mut types = new_fn.params.iter().map(|(_, ty)| ty.to_string()).collect::<Vec<_>>();
+    types.extend(new_fn.output.clone());
+    let (generics, where_predicates) = get_used_generics(tcx, closure.call_expr.hir_id, &types);
+    new_fn.generics = generics;
+    new_fn.where_predicates = where_predicates;
+
     changes.push(tcx.map_change(closure.call_fn_expr.span, format!("({{{}{}{}\n{}}})", create_comment(add_comment, false), new_fn.formatted(), create_comment(add_comment, true), new_fn.ident))?);
 
 
     Ok(AstDiff(changes))
 }
 
+///
+/// Replaces `async { .. }.await` with a call of an async function, which takes the variables used in the block as parameters (as close over variables does for a closure)
+///
+fn convert_async_block(tcx: &TyContext, await_expr: &Expr, body_id: BodyId, ident: String, add_comment: bool) -> QueryResult<AstDiff> {
+    let body = tcx.0.hir().body(body_id);
+    let new_closure = collect_vars3(tcx, body_id)?;
+
+    let mut replacements = new_closure.uses.iter()
+        .map(|span| (*span, format!("(*{})", tcx.get_source(*span))))
+        .collect::<Vec<_>>();
+    replacements.extend(new_closure.selfs.iter().map(|span| (*span, "self_".to_owned())));
+
+    let mut new_fn = FunctionDefinition {
+        is_async: true,
+        ident,
+        generics: vec![],
+        params: new_closure.params.clone(),
+        output: None,
+        where_predicates: vec![],
+        body: get_source_with_replacements(tcx.0, body.value.span, &replacements),
+        body_is_block: if let ExprKind::Block(..) = body.value.kind {true} else {false}
+    };
+
+    let out = get_type_of_expression(tcx, &body.value, body_id)?;
+    if !out.is_unit() {
+        new_fn.output = Some(format_ty(tcx.0, out));
+    }
+
+    let mut types = new_fn.params.iter().map(|(_, ty)| ty.to_string()).collect::<Vec<_>>();
+    types.extend(new_fn.output.clone());
+    let (generics, where_predicates) = get_used_generics(tcx, await_expr.hir_id, &types);
+    new_fn.generics = generics;
+    new_fn.where_predicates = where_predicates;
+
+    let call = format!("({{{}{}{}\n{}}})({}).await", create_comment(add_comment, false), new_fn.formatted(), create_comment(add_comment, true), new_fn.ident, new_closure.get_args(add_comment).join(", "));
+    Ok(AstDiff(vec![tcx.map_change(await_expr.span, call)?]))
+}
+
 struct FunctionDefinition {
+    is_async: bool,
     ident: String,
+    generics: Vec<String>,
     params: Vec<(String, String)>,
     output: Option<String>,
+    where_predicates: Vec<String>,
     body: String,
     body_is_block: bool
 }
 impl FunctionDefinition {
     fn formatted(&self) -> String {
-        format!("fn {}({}){} {}", self.ident, self.format_params(), self.format_output(), self.format_body())
+        format!("{}fn {}{}({}){}{} {}", if self.is_async {"async "} else {""}, self.ident, self.format_generics(), self.format_params(), self.format_output(), self.format_where_clause(), self.format_body())
+    }
+    fn format_generics(&self) -> String {
+        if self.generics.is_empty() {
+            "".to_owned()
+        } else {
+            format!("<{}>", self.generics.join(", "))
+        }
     }
     fn format_params(&self) -> String {
         self.params.iter().map(|(id, ty)| format!("{}: {}", id, ty)).collect::<Vec<_>>().join(", ")
@@ -73,6 +151,13 @@ impl FunctionDefinition {
             "".to_owned()
         }
     }
+    fn format_where_clause(&self) -> String {
+        if self.where_predicates.is_empty() {
+            "".to_owned()
+        } else {
+            format!(" where {}", self.where_predicates.join(", "))
+        }
+    }
     fn format_body(&self) -> String {
         if self.body_is_block {
             self.body.to_string()
@@ -90,8 +175,135 @@ fn get_ident(ty: &Param) -> String {
 }
 fn fresh_name() -> String {"foo".to_owned()}
 
-fn format_ty(ty: &TyS) -> String {
-    with_crate_prefix(||  format!("{}", ty))
+///
+/// The type in the declaration of the variable `x` passed as `x`, `&x` or `&mut x`, which has the names of the lifetimes unlike the inferred type
+///
+fn get_declared_ty(tcx: &TyContext, arg: &Expr, body_id: BodyId) -> Option<String> {
+    let hir = tcx.0.hir();
+    let tables = tcx.0.typeck_tables_of(hir.body_owner_def_id(body_id).to_def_id());
+    // A reference passed as is is reborrowed, which keeps the type
+    let keeps_type = match tables.expr_adjustments(arg) {
+        [] => true,
+        [Adjustment { kind: Adjust::Deref(None), .. }, Adjustment { kind: Adjust::Borrow(_), target }] => *target == tables.expr_ty(arg),
+        _ => false
+    };
+    if !keeps_type || arg.span.from_expansion() {
+        return None;
+    }
+    let (prefix, expr) = match arg.kind {
+        ExprKind::AddrOf(BorrowKind::Ref, Mutability::Not, expr) => ("&", expr),
+        ExprKind::AddrOf(BorrowKind::Ref, Mutability::Mut, expr) => ("&mut ", expr),
+        _ => ("", arg)
+    };
+    let hir_id = match expr.kind {
+        ExprKind::Path(QPath::Resolved(None, Path { res: Res::Local(hir_id), .. })) => *hir_id,
+        _ => return None
+    };
+    let ty = match hir.find(hir.get_parent_node(hir_id))? {
+        Node::Local(Local { pat, ty: Some(ty), .. }) if pat.hir_id == hir_id => ty,
+        Node::Param(param) if param.pat.hir_id == hir_id => {
+            let owner = hir.get_parent_node(param.hir_id);
+            let body = hir.body(hir.maybe_body_owned_by(owner)?);
+            let index = body.params.iter().position(|p| p.hir_id == param.hir_id)?;
+            let decl = hir.fn_decl_by_hir_id(owner)?;
+            // The type of `self` in `&self` has no source
+            if index == 0 && decl.implicit_self.has_implicit_self() {
+                return None;
+            }
+            decl.inputs.get(index)?
+        },
+        _ => return None
+    };
+    let source = tcx.get_source(ty.span);
+    // `Self` is not valid outside of the impl
+    if ty.span.from_expansion() || matches!(ty.kind, TyKind::Infer) || contains_name(&source, "Self") {
+        return None;
+    }
+    Some(format!("{}{}", prefix, source))
+}
+
+///
+/// The generic parameters and where predicates of the item containing `hir_id` (and of its trait impl), which are used by `types` or by the bounds of used parameters
+///
+fn get_used_generics(tcx: &TyContext, hir_id: HirId, types: &[String]) -> (Vec<String>, Vec<String>) {
+    let hir = tcx.0.hir();
+    let item_hir_id = hir.get_parent_item(hir_id);
+    let mut scopes: Vec<&Generics> = vec![];
+    match hir.get(item_hir_id) {
+        Node::Item(Item { kind: ItemKind::Fn(_, generics, _), .. }) => scopes.push(generics),
+        Node::ImplItem(ImplItem { generics, .. }) => {
+            // A function in an inherent impl is moved into the impl, where the generic parameters of the impl can be used
+            if let Node::Item(Item { kind: ItemKind::Impl { generics, of_trait: Some(_), .. }, .. }) = hir.get(hir.get_parent_item(item_hir_id)) {
+                scopes.push(generics);
+            }
+            scopes.push(generics);
+        },
+        _ => {}
+    }
+
+    let params = scopes.iter()
+        .flat_map(|generics| generics.params.iter())
+        .filter(|param| match param.kind {
+            GenericParamKind::Lifetime { kind } => kind == LifetimeParamKind::Explicit,
+            GenericParamKind::Type { synthetic, .. } => synthetic.is_none(),
+            GenericParamKind::Const { .. } => true
+        })
+        .map(|param| {
+            let declaration = match param.kind {
+                GenericParamKind::Const { ty } => format!("const {}: {}", param.name.ident(), tcx.get_source(ty.span)),
+                _ => tcx.get_source(param.bounds.iter().fold(param.span, |span, bound| span.to(bound.span())))
+            };
+            (format!("{}", param.name.ident()), declaration, matches!(param.kind, GenericParamKind::Lifetime { .. }))
+        })
+        .collect::<Vec<_>>();
+    let predicates = scopes.iter()
+        .flat_map(|generics| generics.where_clause.predicates.iter())
+        .map(|predicate| tcx.get_source(predicate.span()))
+        .collect::<Vec<_>>();
+
+    // The bounds of a used parameter can use other parameters
+    let mut used_text = types.join(" ");
+    let mut used_params = vec![false; params.len()];
+    let mut used_predicates = vec![false; predicates.len()];
+    loop {
+        let mut changed = false;
+        for (i, (name, declaration, _)) in params.iter().enumerate() {
+            if !used_params[i] && contains_name(&used_text, name) {
+                used_params[i] = true;
+                used_text = format!("{} {}", used_text, declaration);
+                changed = true;
+            }
+        }
+        for (i, predicate) in predicates.iter().enumerate() {
+            if !used_predicates[i] && params.iter().enumerate().any(|(j, (name, _, _))| used_params[j] && contains_name(predicate, name)) {
+                used_predicates[i] = true;
+                used_text = format!("{} {}", used_text, predicate);
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    // Lifetimes are declared before the other parameters
+    let mut generics = params.iter().enumerate().filter(|(i, _)| used_params[*i]).map(|(_, param)| param).collect::<Vec<_>>();
+    generics.sort_by_key(|(_, _, is_lifetime)| !is_lifetime);
+    (
+        generics.into_iter().map(|(_, declaration, _)| declaration.to_string()).collect(),
+        predicates.into_iter().enumerate().filter(|(i, _)| used_predicates[*i]).map(|(_, predicate)| predicate).collect()
+    )
+}
+
+///
+/// Checks if `text` contains `name` (e.g. `T` or `'a`) as a whole word
+///
+fn contains_name(text: &str, name: &str) -> bool {
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    text.match_indices(name).any(|(i, _)| {
+        !text[..i].chars().next_back().map_or(false, |c| is_ident_char(c) || c == '\'')
+            && !text[i + name.len()..].chars().next().map_or(false, is_ident_char)
+    })
 }
 
 fn get_type_of_expression<'v>(tcx: &'v TyContext, expr: &Expr, body_id: BodyId) -> QueryResult<&'v TyS<'v>> {
@@ -0,0 +1,223 @@
+use rustc_hir::{ImplItem, ImplItemKind, Node, PatKind};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{AssocItemContainer, Ty, TyKind};
+use rustc_middle::ty::adjustment::{Adjust, Adjustment};
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::change_signature::get_function;
+use crate::refactorings::utils::{fresh_name, get_absolute_path, get_receiver, get_relative_path, get_source_with_replacements, to_snake_case};
+use crate::refactorings::visitors::collect_local_variable_use;
+use crate::refactorings::visitors::hir::{collect_binding_names, collect_calls, FnCall};
+
+const CANNOT_CONVERT: &str = "The method cannot be converted to an associated function";
+
+/// Convert to associated function
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the selected method of an inherent impl for the type S
+/// - replace the `self` parameter of F with a parameter of the same type, and each use of `self` in F with the parameter
+/// - for each method call `s.f(a)` of F:
+///   - abort if the call is in a macro
+///   - replace it with `S::f(s, a)`, where the autoref and autoderef of `s` are made explicit
+///
+/// The parameter is named by the option `name`, or after S. Calls inside an impl of S use `Self::f`.
+/// When the selection is in a source file of another (local) crate, the calls of the method defined at the selection are rewritten.
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, name: Option<&str>) -> QueryResult<AstDiff> {
+    let def_id = match get_function(tcx, span)? {
+        Some(def_id) => def_id,
+        None => return Ok(AstDiff(vec![]))
+    };
+    let self_ty = get_self_ty(tcx, def_id)?;
+
+    let mut calls = collect_calls(tcx, def_id).into_iter()
+        .filter(FnCall::is_method_call)
+        .collect::<Vec<_>>();
+    // Calls in the receivers or arguments of other calls are rewritten first
+    calls.sort_by_key(|call| call.expr.span.hi() - call.expr.span.lo());
+    let mut replacements = vec![];
+    for call in &calls {
+        if call.expr.span.from_expansion() {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the method is called in a macro"));
+        }
+        replacements.push(rewrite_call(tcx, call, self_ty, &replacements)?);
+    }
+
+    if let Some(hir_id) = tcx.0.hir().as_local_hir_id(def_id) {
+        let (decl, body_id) = match tcx.0.hir().get(hir_id) {
+            Node::ImplItem(ImplItem { kind: ImplItemKind::Fn(sig, body_id), .. }) => (sig.decl, *body_id),
+            _ => return Err(tcx.source().span_err(span, false))
+        };
+        let body = tcx.0.hir().body(body_id);
+        let param = &body.params[0];
+        let ident = match param.pat.kind {
+            PatKind::Binding(_, _, ident, _) => ident,
+            _ => return Err(tcx.source().span_err(span, false))
+        };
+
+        let mut used_names = collect_binding_names(tcx.0, body, body.value.span);
+        for param in body.params {
+            param.pat.each_binding(|_, _, _, ident| used_names.push(format!("{}", ident)));
+        }
+        let name = match name {
+            Some(name) if used_names.iter().any(|used| used == name) => {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, &format!("the name {} is already used in the method", name)));
+            },
+            Some(name) => name.to_string(),
+            None => fresh_name(&get_default_name(tcx, self_ty), &used_names)
+        };
+
+        // `self` has the implicit type `Self`, `&Self` or `&mut Self`, unless it is given as in `self: Box<Self>`
+        let param_span = param.pat.span.to(decl.inputs[0].span);
+        let param_source = tcx.get_source(param_span);
+        let new_param = if param_source.contains(':') {
+            get_source_with_replacements(tcx.0, param_span, &[(ident.span, name.to_string())])
+        } else if param_source.starts_with('&') {
+            format!("{}: {}Self", name, param_source.trim_end_matches("self"))
+        } else if param_source.starts_with("mut") {
+            format!("mut {}: Self", name)
+        } else {
+            format!("{}: Self", name)
+        };
+        replacements.push((param_span, new_param));
+
+        for use_ in collect_local_variable_use(tcx, param.pat.hir_id) {
+            if use_.from_expansion() {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "`self` is used in a macro"));
+            }
+            replacements.push((use_, name.to_string()));
+        }
+    }
+
+    let mut changes = vec![];
+    for (span, replacement) in &replacements {
+        if !replacements.iter().any(|(other, _)| other != span && other.contains(*span)) {
+            changes.push(tcx.map_change(*span, replacement.to_string())?);
+        }
+    }
+    Ok(AstDiff(changes))
+}
+
+///
+/// The type of `Self` in the impl of the method
+///
+fn get_self_ty<'v>(tcx: &TyContext<'v>, def_id: DefId) -> QueryResult<Ty<'v>> {
+    let item = tcx.0.opt_associated_item(def_id)
+        .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the function is not a method"))?;
+    let impl_def_id = match item.container {
+        AssocItemContainer::ImplContainer(impl_def_id) if tcx.0.impl_trait_ref(impl_def_id).is_none() => impl_def_id,
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "methods of traits are not supported"))
+    };
+    if !item.fn_has_self_parameter {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the function has no `self` parameter"));
+    }
+    Ok(tcx.0.type_of(impl_def_id))
+}
+
+///
+/// The name of the type in snake case, e.g. `point` for `Point<T>`
+///
+fn get_default_name(tcx: &TyContext, self_ty: Ty) -> String {
+    self_ty.ty_adt_def()
+        .map(|adt_def| to_snake_case(&format!("{}", tcx.0.item_name(adt_def.did))))
+        .unwrap_or_else(|| "this".to_owned())
+}
+
+///
+/// `s.f(a)` becomes `S::f(s, a)`, or `Self::f(s, a)` inside an impl of `S`
+///
+fn rewrite_call<'v>(tcx: &TyContext<'v>, call: &FnCall, self_ty: Ty<'v>, replacements: &[(Span, String)]) -> QueryResult<(Span, String)> {
+    let owner = call.expr.hir_id.owner.to_def_id();
+    let tables = tcx.0.typeck_tables_of(owner);
+    let in_impl = tcx.0.opt_associated_item(owner)
+        .map_or(false, |item| match item.container {
+            AssocItemContainer::ImplContainer(impl_def_id) => tcx.0.type_of(impl_def_id) == self_ty,
+            AssocItemContainer::TraitContainer(_) => false
+        });
+    let ty = if in_impl {
+        "Self".to_owned()
+    } else {
+        let adt_def = self_ty.ty_adt_def().ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the type cannot be named at a call"))?;
+        let module_path = get_absolute_path(tcx.0, tcx.0.parent_module(call.expr.hir_id).to_def_id());
+        get_relative_path(&get_absolute_path(tcx.0, adt_def.did), &module_path)
+    };
+
+    // The name of the method with its generic arguments, e.g. `f::<T>` in `s.f::<T>(a)`
+    let receiver = &call.args[0];
+    let name = get_method_name(&tcx.get_source(receiver.span.source_callsite().between(call.expr.span.shrink_to_hi())));
+
+    // A reference is passed as is, instead of the reborrow `&*r`
+    let is_reborrow = match tables.expr_adjustments(receiver) {
+        [Adjustment { kind: Adjust::Deref(None), .. }, Adjustment { kind: Adjust::Borrow(_), .. }] => matches!(tables.expr_ty(receiver).kind, TyKind::Ref(..)),
+        _ => false
+    };
+    let receiver = if is_reborrow {
+        get_source_with_replacements(tcx.0, receiver.span.source_callsite(), replacements)
+    } else {
+        get_receiver(tcx.0, tables, receiver, replacements)
+    };
+    let mut args = vec![receiver];
+    args.extend(call.args[1..].iter().map(|arg| get_source_with_replacements(tcx.0, arg.span.source_callsite(), replacements)));
+
+    Ok((call.expr.span, format!("{}::{}({})", ty, name, args.join(", "))))
+}
+
+///
+/// The name and generic arguments of the method in `.f::<T>(a)`, i.e. the source up to the `(` which is not inside the generic arguments
+///
+fn get_method_name(call_source: &str) -> String {
+    let mut depth = 0;
+    let mut prev = ' ';
+    for (i, c) in call_source.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' if prev != '-' => depth -= 1,
+            '(' if depth == 0 => return call_source[..i].trim_start_matches(|c: char| c == '.' || c.is_whitespace()).trim_end().to_string(),
+            _ => {}
+        }
+        prev = c;
+    }
+    call_source.to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{run_refactoring, TestInit};
+    const NAME: &str = "convert-to-associated-function";
+
+    #[test]
+    fn nested_calls_with_generic_args() {
+        let input = r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn add<T: Into<i32>>(self, n: T) -> S { S(self.0 + n.into()) }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S(1).add(2).add::<u8>(3)
+}"#;
+        let expected = Ok(r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn add<T: Into<i32>>(s: Self, n: T) -> S { S(s.0 + n.into()) }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S::add::<u8>(S::add(S(1), 2), 3)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn fresh_name() {
+        let input = r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn get(&self, s: i32) -> i32 { self.0 + s }/*refactor-tool:test-id:end*/
+}"#;
+        let expected = Ok(r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn get(s_1: &Self, s: i32) -> i32 { s_1.0 + s }/*refactor-tool:test-id:end*/
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,200 @@
+use rustc_hir::{BindingAnnotation, BorrowKind, Expr, ExprKind, HirId, ImplItem, ImplItemKind, Node, PatKind, QPath, TyKind as HirTyKind};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{AssocItemContainer, TyKind};
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::change_signature::get_function;
+use crate::refactorings::utils::{get_shorthand_field, get_source_with_replacements};
+use crate::refactorings::visitors::collect_local_variable_use_exprs;
+use crate::refactorings::visitors::hir::{collect_calls, FnCall};
+
+const CANNOT_CONVERT: &str = "The function cannot be converted to a method";
+
+/// Convert to method
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the selected associated function of an inherent impl
+/// - abort if F has a `self` parameter, or if the first parameter P of F does not have the type `Self`, `&Self` or `&mut Self`
+/// - replace P with `self`, `&self` or `&mut self`, and each use of P in F with `self`
+/// - for each call of F as `S::f(s, a)`, which is not in a macro:
+///   - replace it with `s.f(a)`, where `&s` and `&mut s` are replaced with `s`
+///
+/// Other paths to F (e.g. when F is passed as a function pointer) are valid for methods as well, and are not changed.
+/// When the selection is in a source file of another (local) crate, the calls of the function defined at the selection are rewritten.
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+    let def_id = match get_function(tcx, span)? {
+        Some(def_id) => def_id,
+        None => return Ok(AstDiff(vec![]))
+    };
+    check_is_inherent_associated_function(tcx, def_id)?;
+
+    let mut calls = collect_calls(tcx, def_id).into_iter()
+        .filter(|call| !call.expr.span.from_expansion())
+        .collect::<Vec<_>>();
+    // Calls in the arguments of other calls are rewritten first
+    calls.sort_by_key(|call| call.expr.span.hi() - call.expr.span.lo());
+    let mut replacements = vec![];
+    for call in &calls {
+        replacements.push(rewrite_call(tcx, call, &replacements));
+    }
+
+    if let Some(hir_id) = tcx.0.hir().as_local_hir_id(def_id) {
+        replacements.extend(rewrite_definition(tcx, span, hir_id)?);
+    }
+
+    let mut changes = vec![];
+    for (span, replacement) in &replacements {
+        if !replacements.iter().any(|(other, _)| other != span && other.contains(*span)) {
+            changes.push(tcx.map_change(*span, replacement.to_string())?);
+        }
+    }
+    Ok(AstDiff(changes))
+}
+
+fn check_is_inherent_associated_function(tcx: &TyContext, def_id: DefId) -> QueryResult<()> {
+    let item = tcx.0.opt_associated_item(def_id)
+        .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the function is not in an impl"))?;
+    let impl_def_id = match item.container {
+        AssocItemContainer::ImplContainer(impl_def_id) if tcx.0.impl_trait_ref(impl_def_id).is_none() => impl_def_id,
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "methods of traits are not supported"))
+    };
+    if item.fn_has_self_parameter {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the function already has a `self` parameter"));
+    }
+    let self_ty = tcx.0.type_of(impl_def_id);
+    let is_self = match tcx.0.fn_sig(def_id).skip_binder().inputs().first() {
+        Some(ty) => match ty.kind {
+            TyKind::Ref(_, ty, _) => ty == self_ty,
+            _ => *ty == self_ty
+        },
+        None => false
+    };
+    if !is_self {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the first parameter does not have the type `Self`, `&Self` or `&mut Self`"));
+    }
+    Ok(())
+}
+
+///
+/// `S::f(s, a)` becomes `s.f(a)`, where the autoref of the method call replaces `&s` or `&mut s`
+///
+fn rewrite_call(tcx: &TyContext, call: &FnCall, replacements: &[(Span, String)]) -> (Span, String) {
+    let receiver = match &call.args[0].kind {
+        ExprKind::AddrOf(BorrowKind::Ref, _, expr) if !call.args[0].span.from_expansion() => expr,
+        _ => &call.args[0]
+    };
+    let mut receiver_source = get_source_with_replacements(tcx.0, receiver.span.source_callsite(), replacements);
+    if !is_postfix(receiver) {
+        receiver_source = format!("({})", receiver_source);
+    }
+    let args = call.args[1..].iter()
+        .map(|arg| get_source_with_replacements(tcx.0, arg.span.source_callsite(), replacements))
+        .collect::<Vec<_>>();
+
+    // The name of the function with its generic arguments, e.g. `f::<T>` in `S::f::<T>`
+    let callee = call.callee.unwrap();
+    let segment = match call.expr.kind {
+        ExprKind::Call(Expr { kind: ExprKind::Path(QPath::Resolved(_, path)), .. }, _) => path.segments.last().map(|segment| segment.ident.span),
+        ExprKind::Call(Expr { kind: ExprKind::Path(QPath::TypeRelative(_, segment)), .. }, _) => Some(segment.ident.span),
+        _ => None
+    };
+    let name = tcx.get_source(segment.map_or(callee, |segment| segment.with_hi(callee.hi())));
+
+    (call.expr.span, format!("{}.{}({})", receiver_source, name, args.join(", ")))
+}
+
+///
+/// Checks if `expr` can be the receiver of a method call without parentheses
+///
+pub(crate) fn is_postfix(expr: &Expr) -> bool {
+    match expr.kind {
+        ExprKind::Path(..) | ExprKind::Field(..) | ExprKind::Call(..) | ExprKind::MethodCall(..) | ExprKind::Index(..)
+        | ExprKind::Tup(..) | ExprKind::Array(..) => true,
+        _ => false
+    }
+}
+
+fn rewrite_definition(tcx: &TyContext, span: Span, hir_id: HirId) -> QueryResult<Vec<(Span, String)>> {
+    let (decl, body_id) = match tcx.0.hir().get(hir_id) {
+        Node::ImplItem(ImplItem { kind: ImplItemKind::Fn(sig, body_id), .. }) => (sig.decl, *body_id),
+        _ => return Err(tcx.source().span_err(span, false))
+    };
+    let body = tcx.0.hir().body(body_id);
+    let param = &body.params[0];
+    let ty = &decl.inputs[0];
+    let (binding_hir_id, is_mut) = match param.pat.kind {
+        PatKind::Binding(BindingAnnotation::Unannotated, hir_id, _, None) => (hir_id, false),
+        PatKind::Binding(BindingAnnotation::Mutable, hir_id, _, None) => (hir_id, true),
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the first parameter is not a variable"))
+    };
+
+    let self_param = match (&ty.kind, is_mut) {
+        // `&'a mut self`
+        (HirTyKind::Rptr(_, mut_ty), false) => format!("{}self", tcx.get_source(ty.span.until(mut_ty.ty.span))),
+        (HirTyKind::Rptr(..), true) => format!("mut self: {}", tcx.get_source(ty.span)),
+        (_, false) => "self".to_owned(),
+        (_, true) => "mut self".to_owned()
+    };
+    let mut replacements = vec![(param.pat.span.to(ty.span), self_param)];
+
+    for use_ in collect_local_variable_use_exprs(tcx, binding_hir_id) {
+        if use_.span.from_expansion() {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_CONVERT, "the parameter is used in a macro"));
+        }
+        let replacement = match get_shorthand_field(tcx.0, use_) {
+            Some(field) => format!("{}: self", field),
+            None => "self".to_owned()
+        };
+        replacements.push((use_.span, replacement));
+    }
+    Ok(replacements)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{run_refactoring, TestInit};
+    const NAME: &str = "convert-to-method";
+
+    #[test]
+    fn nested_calls_with_generic_args() {
+        let input = r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn add<T: Into<i32>>(s: S, n: T) -> S { S(s.0 + n.into()) }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S::add::<u8>(S::add(S(1), 2), 3)
+}"#;
+        let expected = Ok(r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn add<T: Into<i32>>(self, n: T) -> S { S(self.0 + n.into()) }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S(1).add(2).add::<u8>(3)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn mut_binding() {
+        let input = r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn inc(mut s: Self) -> Self { s.0 += 1; s }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S::inc(S(1))
+}"#;
+        let expected = Ok(r#"struct S(i32);
+impl S {
+    /*refactor-tool:test-id:start*/fn inc(mut self) -> Self { self.0 += 1; self }/*refactor-tool:test-id:end*/
+}
+fn f() -> S {
+    S(1).inc()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,265 @@
+use rustc_hir::{CRATE_HIR_ID, Expr, ExprKind, GenericParamKind, Generics, HirId, ItemKind, Node, VisibilityKind};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::DefIdTree;
+use rustc_span::{BytePos, Span};
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use super::box_named_field::struct_expression_collector::collect_struct_expressions;
+use super::box_named_field::struct_named_pattern_collector::collect_struct_named_patterns;
+use super::visitors::{collect_field, collect_struct_field_access_expressions};
+use super::visitors::hir::collect_expr;
+use super::wrap_field::get_use_kind;
+
+const CANNOT_ENCAPSULATE: &str = "The field cannot be encapsulated";
+
+/// Encapsulate field refactoring
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the (public) field which should be encapsulated, with the type U
+/// - T <- the struct which contains F
+/// - Ps <- All StructPatterns where PathInExpression has type T and F is bound
+/// - Vs <- All StructExpressions where PathInExpression has type T and F is initialized
+/// - Fs <- All FieldExpressions where Expression has type T and F is IDENTIFIER
+/// - if any P in Ps or V in Vs is outside of the module of T then abort
+/// - Make F private
+/// - Add the accessors `fn f(&self) -> &U`, `fn f_mut(&mut self) -> &mut U` and `fn set_f(&mut self, f: U)` to T, where the last two are only added if they are used (or requested)
+/// - for F' in Fs
+///   - Replace F' with a call to the accessor, depending on if F' is read, mutated or assigned to
+///   - if F' is moved, keep F' if it is in the module of T, otherwise abort
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, getter_mut: bool, setter: bool) -> QueryResult<AstDiff> {
+    let (field, _) = collect_field(tcx.0, span).ok_or_else(|| tcx.source().span_err(span, false))?;
+    let struct_hir_id = tcx.get_struct_hir_id(&field);
+    let hir = tcx.0.hir();
+
+    let item = match hir.get(struct_hir_id) {
+        Node::Item(item) => item,
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is not in a struct"))
+    };
+    let generics = match &item.kind {
+        ItemKind::Struct(_, generics) => generics,
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is not in a struct"))
+    };
+    if field.is_positional() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is not a named field"));
+    }
+    if let VisibilityKind::Inherited = field.vis.node {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is already private"));
+    }
+    if generics.params.iter().any(|p| matches!(p.kind, GenericParamKind::Type { default: Some(_), .. })) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the struct has a type parameter with a default"));
+    }
+
+    let field_ident = field.ident.to_string();
+    let getter_name = field_ident.clone();
+    let getter_mut_name = format!("{}_mut", field_ident);
+    let setter_name = format!("set_{}", field_ident);
+    check_no_method_collisions(tcx, struct_hir_id, &[&getter_name, &getter_mut_name, &setter_name])?;
+
+    let struct_module = get_module(tcx, item.span);
+    let is_in_module = |span: Span| tcx.0.is_descendant_of(get_module(tcx, span.source_callsite()), struct_module);
+
+    let struct_patterns = collect_struct_named_patterns(tcx, struct_hir_id, &field_ident);
+    if !struct_patterns.new_bindings.iter().all(|hir_id| is_in_module(hir.span(*hir_id)))
+        || !struct_patterns.other.iter().all(|(span, _)| is_in_module(*span)) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is matched in a pattern outside of its module"));
+    }
+    let (struct_expressions, struct_expression_shorthands) = collect_struct_expressions(tcx, struct_hir_id, &field_ident)?;
+    for struct_expression in struct_expressions.into_iter().chain(struct_expression_shorthands.into_iter().map(|(span, _)| span)) {
+        if !is_in_module(struct_expression) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the struct is constructed outside of its module"));
+        }
+    }
+
+    let whitespace_after_vis = tcx.get_source(field.vis.span.with_lo(field.vis.span.hi()).with_hi(field.ident.span.lo()))
+        .chars().take_while(|c| c.is_whitespace()).count();
+    let mut changes = vec![tcx.map_change(field.vis.span.with_hi(field.vis.span.hi() + BytePos(whitespace_after_vis as u32)), "".to_owned())?];
+    let mut local_uses = vec![];
+    let (mut uses_getter_mut, mut uses_setter) = (getter_mut, setter);
+    for field_access_expression in collect_struct_field_access_expressions(tcx, struct_hir_id, &field_ident) {
+        let use_ = get_expr(tcx, field_access_expression)?;
+        let base = match use_.kind {
+            ExprKind::Field(base, _) => tcx.get_source(base.span),
+            _ => return Err(tcx.source().span_err(field_access_expression, true))
+        };
+        let parent = match hir.get(hir.get_parent_node(use_.hir_id)) {
+            Node::Expr(parent) => Some(parent),
+            _ => None
+        };
+        if let Some(Expr { span: parent_span, kind: ExprKind::Assign(lhs, rhs, _), .. }) = parent {
+            if lhs.hir_id == use_.hir_id {
+                uses_setter = true;
+                changes.push(tcx.map_change(*parent_span, format!("{}.{}({})", base, setter_name, tcx.get_source(rhs.span.source_callsite())))?);
+                continue;
+            }
+        }
+
+        let use_kind = get_use_kind(tcx, use_, &mut local_uses);
+        let replacement = if use_kind.is_mutated() {
+            uses_getter_mut = true;
+            format!("(*{}.{}())", base, getter_mut_name)
+        } else if use_kind.is_moved() {
+            if is_in_module(use_.span) {
+                continue;
+            }
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the field is moved outside of its module"));
+        } else {
+            format!("(*{}.{}())", base, getter_name)
+        };
+        changes.push(tcx.map_change(use_.span, replacement)?);
+    }
+
+    let vis = tcx.get_source(field.vis.span);
+    let ty = tcx.get_source(field.ty.span);
+    let mut accessors = vec![format!("{} fn {}(&self) -> &{} {{\n    &self.{}\n}}", vis, getter_name, ty, field_ident)];
+    if uses_getter_mut {
+        accessors.push(format!("{} fn {}(&mut self) -> &mut {} {{\n    &mut self.{}\n}}", vis, getter_mut_name, ty, field_ident));
+    }
+    if uses_setter {
+        accessors.push(format!("{} fn {}(&mut self, {}: {}) {{\n    self.{2} = {2};\n}}", vis, setter_name, field_ident, ty));
+    }
+    let indent = tcx.source().get_indent(item.span);
+    let body = accessors.join("\n").lines().map(|l| format!("\n{}    {}", indent, l)).collect::<String>();
+    let impl_item = format!("\n\n{0}{1}{{{2}\n{0}}}", indent, get_impl_header(tcx, &item.ident.to_string(), generics), body);
+    changes.push(tcx.map_change(item.span.shrink_to_hi(), impl_item)?);
+
+    Ok(AstDiff(changes))
+}
+
+fn get_expr<'v>(tcx: &TyContext<'v>, span: Span) -> QueryResult<&'v Expr<'v>> {
+    collect_expr(tcx.0, span)
+        .map(|(expr, _)| expr)
+        .ok_or_else(|| tcx.source().span_err(span, true))
+}
+
+///
+/// The innermost module which contains `span`
+///
+fn get_module(tcx: &TyContext, span: Span) -> DefId {
+    let hir = tcx.0.hir();
+    hir.krate().items.values()
+        .filter_map(|item| match &item.kind {
+            ItemKind::Mod(module) if module.inner.contains(span) => Some((module.inner, item.hir_id)),
+            _ => None
+        })
+        .min_by_key(|(inner, _)| inner.hi() - inner.lo())
+        .map_or_else(|| hir.local_def_id(CRATE_HIR_ID), |(_, hir_id)| hir.local_def_id(hir_id))
+}
+
+///
+/// `impl<P> S<P> where .. `, for the struct `S` with the generics `<P> where ..`
+///
+fn get_impl_header(tcx: &TyContext, struct_ident: &str, generics: &Generics) -> String {
+    let mut header = "impl".to_owned();
+    if !generics.params.is_empty() {
+        let args = generics.params.iter().map(|p| p.name.ident().to_string()).collect::<Vec<_>>();
+        header.push_str(&format!("{} {}<{}>", tcx.get_source(generics.span), struct_ident, args.join(", ")));
+    } else {
+        header.push_str(&format!(" {}", struct_ident));
+    }
+    if !generics.where_clause.predicates.is_empty() {
+        header.push_str(&format!(" {}", tcx.get_source(generics.where_clause.span)));
+    }
+    header.push(' ');
+    header
+}
+
+///
+/// The inherent impls of the struct must not already contain any of the accessors
+///
+fn check_no_method_collisions(tcx: &TyContext, struct_hir_id: HirId, names: &[&str]) -> QueryResult<()> {
+    let struct_def_id = tcx.0.hir().local_def_id(struct_hir_id);
+    for impl_def_id in tcx.0.inherent_impls(struct_def_id).iter() {
+        for item_def_id in tcx.0.associated_item_def_ids(*impl_def_id).iter() {
+            let item_name = tcx.0.item_name(*item_def_id).to_string();
+            if names.contains(&item_name.as_str()) {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, &format!("the struct already has an item named {}", item_name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_ENCAPSULATE;
+    const NAME: &str = "encapsulate-field";
+
+    #[test]
+    fn read_mutated_and_assigned() {
+        let input = r#"mod m {
+    pub struct S { pub /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+    pub fn new() -> S {
+        S { field: vec![] }
+    }
+}
+fn foo() -> usize {
+    let mut s = m::new();
+    s.field.push(1);
+    s.field = vec![];
+    s.field.len()
+}"#;
+        let expected = Ok(r#"mod m {
+    pub struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+
+    impl S {
+        pub fn field(&self) -> &Vec<i32> {
+            &self.field
+        }
+        pub fn field_mut(&mut self) -> &mut Vec<i32> {
+            &mut self.field
+        }
+        pub fn set_field(&mut self, field: Vec<i32>) {
+            self.field = field;
+        }
+    }
+    pub fn new() -> S {
+        S { field: vec![] }
+    }
+}
+fn foo() -> usize {
+    let mut s = m::new();
+    (*s.field_mut()).push(1);
+    s.set_field(vec![]);
+    (*s.field()).len()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn generic_struct() {
+        let input = r#"pub struct S<'a, T: Clone> where T: Copy { pub(crate) /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: &'a T }
+fn foo<'a>(s: &S<'a, i32>) -> i32 {
+    *s.field
+}"#;
+        let expected = Ok(r#"pub struct S<'a, T: Clone> where T: Copy { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: &'a T }
+
+impl<'a, T: Clone> S<'a, T> where T: Copy {
+    pub(crate) fn field(&self) -> &&'a T {
+        &self.field
+    }
+}
+fn foo<'a>(s: &S<'a, i32>) -> i32 {
+    *(*s.field())
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn constructed_outside_of_module() {
+        let input = r#"mod m {
+    pub struct S { pub /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: i32 }
+}
+fn foo() -> m::S {
+    m::S { field: 1 }
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_ENCAPSULATE, "the struct is constructed outside of its module"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,5 +1,8 @@
-use super::visitors::hir::collect_innermost_block;
+use super::visitors::hir::{collect_expr, collect_innermost_block};
 use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
+use rustc_hir::{Expr, ExprKind, Node, QPath, UnOp};
+use rustc_hir::def::{DefKind, Res};
+use rustc_middle::ty::adjustment::Adjust;
 use rustc_span::Span;
 use em_refactor_lib_types::{create_refactor_tool_marker, defs::EXTRACT_BLOCK_BLOCK};
 
@@ -21,6 +24,11 @@ mod variable_use_collection;
 ///    Add { around B } and return 
 /// End
 /// 
+/// If B is an expression inside a statement (e.g. a condition, an argument or the initializer of a `let`):
+///    Abort if B is a place (a local, a field, an index or a deref) which is assigned to or borrowed, since { B } moves or copies it into a temporary
+///    Add { around B } and return
+/// End
+/// 
 /// Vs <- Locals declared in B and used in C
 /// 
 /// 
@@ -31,6 +39,11 @@ mod variable_use_collection;
 pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryResult<AstDiff> {
     let (block, body_id) = collect_innermost_block(tcx, span)?;
 
+    if is_sub_expression(tcx, span)? {
+        let source = format!("{}{}{}", get_block_start(add_comment), tcx.get_source(span), get_block_end(add_comment));
+        return Ok(AstDiff(vec![tcx.map_change(span, source)?]));
+    }
+
     let vars = push_stmt_into_block::collect_variables_overlapping_span(tcx, body_id, span)?;
     let statements_source = tcx.get_source(span);
 
@@ -61,6 +74,62 @@ pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryRe
     )?]))
 }
 
+///
+/// Checks if the selection is an expression inside a statement, instead of statements (and the expression) of a block
+///
+fn is_sub_expression(tcx: &TyContext, span: Span) -> QueryResult<bool> {
+    let expr = match collect_expr(tcx.0, span) {
+        Some((expr, _)) => expr,
+        None => return Ok(false)
+    };
+    // The value of the block is a temporary, so assigning to or borrowing it would not affect the place
+    if is_place_expr(expr) && is_place_context(tcx, expr) {
+        return Err(tcx.source().span_err(span, false));
+    }
+    let hir = tcx.0.hir();
+    match hir.find(hir.get_parent_node(expr.hir_id)) {
+        Some(Node::Expr(_)) | Some(Node::Local(_)) => Ok(true),
+        _ => Ok(false)
+    }
+}
+
+///
+/// Checks if `expr` is a local variable, a static, or a field, an index or a deref of a place
+///
+fn is_place_expr(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Path(QPath::Resolved(None, path)) => matches!(path.res, Res::Local(..) | Res::Def(DefKind::Static, _)),
+        ExprKind::Field(..) | ExprKind::Index(..) | ExprKind::Unary(UnOp::UnDeref, _) => true,
+        _ => false
+    }
+}
+
+///
+/// Checks if the place `expr` is assigned to or borrowed, e.g. `x` in `x = 1`, `&x`, `x.len()`, `&x.f`, or moved out of by `x.f`
+///
+fn is_place_context(tcx: &TyContext, expr: &Expr) -> bool {
+    let hir = tcx.0.hir();
+    let def_id = expr.hir_id.owner.to_def_id();
+    let tables = tcx.0.typeck_tables_of(def_id);
+    // Autoref of a method receiver, or an overloaded deref
+    if tables.expr_adjustments(expr).iter().any(|adjustment| matches!(adjustment.kind, Adjust::Borrow(_) | Adjust::Deref(Some(_)))) {
+        return true;
+    }
+    let parent = match hir.find(hir.get_parent_node(expr.hir_id)) {
+        Some(Node::Expr(parent)) => parent,
+        _ => return false
+    };
+    match parent.kind {
+        ExprKind::Assign(lhs, ..) | ExprKind::AssignOp(_, lhs, _) => lhs.hir_id == expr.hir_id,
+        ExprKind::AddrOf(..) => true,
+        ExprKind::Field(base, _) | ExprKind::Index(base, _) | ExprKind::Unary(UnOp::UnDeref, base) if base.hir_id == expr.hir_id => {
+            let is_copy = tables.expr_ty(expr).is_copy_modulo_regions(tcx.0, tcx.0.param_env(def_id), expr.span);
+            !is_copy || is_place_context(tcx, parent)
+        },
+        _ => false
+    }
+}
+
 fn get_block_start(add_comment: bool) -> String {
     if add_comment {
         format!("{}{{", create_refactor_tool_marker(EXTRACT_BLOCK_BLOCK, false))
@@ -78,6 +147,7 @@ fn get_block_end(add_comment: bool) -> String {
 
 #[cfg(test)]
 mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
     use crate::test_utils::{run_refactoring, TestInit};
     const NAME: &str = "extract-block";
 
@@ -105,4 +175,76 @@ mod test {
         let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
         assert_eq!(actual, expected);
     }
-}
\ No newline at end of file
+    #[test]
+    fn sub_expression() {
+        let input = r#"fn foo(i: i32) -> bool {
+    let j = i + 1;
+    j > 0 && /*refactor-tool:test-id:start*/i * 2 < 10/*refactor-tool:test-id:end*/
+}"#;
+        let expected = Ok(r#"fn foo(i: i32) -> bool {
+    let j = i + 1;
+    j > 0 && /*refactor-tool:test-id:start*/{i * 2 < 10}/*refactor-tool:test-id:end*/
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn sub_expression_borrowed_mutably() {
+        let input = r#"fn foo() {
+    let mut x = 0;
+    let r = &mut /*refactor-tool:test-id:start*/x/*refactor-tool:test-id:end*/;
+    *r += 1;
+}"#;
+        let expected = Err(RefactoringErrorInternal::invalid_selection_with_code(78, 79, "x", false));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn sub_expression_mutable_receiver() {
+        let input = r#"fn foo() {
+    let mut v = vec![];
+    /*refactor-tool:test-id:start*/v/*refactor-tool:test-id:end*/.push(1);
+}"#;
+        let expected = Err(RefactoringErrorInternal::invalid_selection_with_code(70, 71, "v", false));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn sub_expression_assigned_field() {
+        let input = r#"struct S { f: i32 }
+fn foo() {
+    let mut s = S { f: 0 };
+    /*refactor-tool:test-id:start*/s/*refactor-tool:test-id:end*/.f = 1;
+}"#;
+        let expected = Err(RefactoringErrorInternal::invalid_selection_with_code(94, 95, "s", false));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }    #[test]
+    fn sub_expression_borrowed_field() {
+        let input = r#"struct S { name: String }
+fn len(s: &String) -> usize { s.len() }
+fn foo(s: S) -> usize {
+    let n = len(&/*refactor-tool:test-id:start*/s.name/*refactor-tool:test-id:end*/);
+    n + s.name.len()
+}"#;
+        let expected = Err(RefactoringErrorInternal::invalid_selection_with_code(138, 144, "s.name", false));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn sub_expression_shared_receiver() {
+        let input = r#"fn foo(v: Vec<i32>) -> usize {
+    let n = /*refactor-tool:test-id:start*/v/*refactor-tool:test-id:end*/.len();
+    n + v.len()
+}"#;
+        let expected = Err(RefactoringErrorInternal::invalid_selection_with_code(74, 75, "v", false));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
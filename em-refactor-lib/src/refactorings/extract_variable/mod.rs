@@ -0,0 +1,329 @@
+use rustc_hir::{BinOpKind, BodyId, Expr, ExprKind, Node, QPath, StmtKind, UnOp};
+use rustc_hir::def::{DefKind, Res};
+use rustc_middle::ty::{Ty, TyKind, print::with_crate_prefix, subst::GenericArgKind};
+use rustc_middle::ty::adjustment::{Adjust, AutoBorrow, AutoBorrowMutability};
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::{fresh_name, get_shorthand_field, is_identifier, to_snake_case};
+use crate::refactorings::visitors::hir::{collect_binding_names, collect_expr, collect_innermost_block, collect_local_uses, collect_locals, contains_side_effects, is_pure};
+use occurrence_collector::collect_occurrences;
+
+mod occurrence_collector;
+
+const CANNOT_EXTRACT: &str = "The expression cannot be extracted";
+
+/// Extract variable
+///
+/// ## Algorithm
+/// 1. A `let` statement with the selected expression as initializer is inserted in the innermost block,
+///    before the statement containing the (first) occurrence
+/// 2. The selected expression (or with the option `replace-all`, each syntactically identical expression in the block) is replaced with the variable
+///
+/// The name of the variable is given by the option `name`, or derived from the expression.
+///
+/// Preconditions
+/// - The expression has a value (not `()` or `!`) and is not assigned to or borrowed mutably
+/// - The variables used by the expression are declared before the statement, and are not mutated before the last occurrence
+/// - The expression is not evaluated conditionally, repeatedly or in a closure (relative to the statement)
+/// - If the expression can have side effects, nothing with side effects is evaluated between the start of the statement and the expression,
+///   and it cannot be combined with `replace-all`
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, name: Option<&str>, replace_all: bool) -> QueryResult<AstDiff> {
+    let (expr, body_id) = collect_expr(tcx.0, span).ok_or_else(|| tcx.source().span_err(span, false))?;
+    if expr.span.from_expansion() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is part of a macro"));
+    }
+    let hir = tcx.0.hir();
+    let body = hir.body(body_id);
+    let tables = tcx.0.typeck_tables_of(hir.body_owner_def_id(body_id).to_def_id());
+    let ty = tcx.0.erase_regions(&tables.expr_ty(expr));
+    if ty.is_unit() || ty.is_never() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression has no value"));
+    }
+
+    let pure = is_pure(expr);
+    if replace_all && !pure {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression can have side effects, so the occurrences cannot be replaced"));
+    }
+    let source = tcx.get_source(expr.span);
+    let read_locals = collect_locals(expr);
+    let (block, _) = collect_innermost_block(tcx, expr.span)?;
+    let occurrences = if replace_all {
+        collect_occurrences(tcx.0.sess.source_map(), block, &source).into_iter()
+            .filter(|occurrence| collect_locals(occurrence) == read_locals)
+            .collect::<Vec<_>>()
+    } else {
+        vec![expr]
+    };
+    let first = occurrences.iter().map(|occurrence| occurrence.span).min_by_key(|span| span.lo()).unwrap_or(expr.span);
+    let last = occurrences.iter().map(|occurrence| occurrence.span.hi()).max().unwrap_or(expr.span.hi());
+
+    let anchor = block.stmts.iter()
+        .filter(|stmt| !matches!(stmt.kind, StmtKind::Item(..)))
+        .map(|stmt| stmt.span.source_callsite())
+        .chain(block.expr.map(|e| e.span.source_callsite()))
+        .find(|span| span.contains(first))
+        .ok_or_else(|| tcx.source().span_err(span, false))?;
+
+    if read_locals.iter().any(|id| anchor.contains(hir.span(*id))) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression uses a variable declared in the same statement"));
+    }
+    for occurrence in &occurrences {
+        check_place(tcx, occurrence, body_id, ty, occurrences.len() > 1)?;
+        if is_evaluated_conditionally(tcx, occurrence, anchor) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is evaluated conditionally, repeatedly or in a closure"));
+        }
+    }
+    if !pure && contains_side_effects(body, anchor.shrink_to_lo().with_hi(expr.span.lo())) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the side effects of the expression would be reordered"));
+    }
+    let before_last = anchor.shrink_to_lo().with_hi(last);
+    if collect_local_uses(tcx.0, body_id).iter().any(|(id, span, use_kind)| read_locals.contains(id) && use_kind.is_mutated() && before_last.contains(*span)) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "a variable used by the expression is mutated before the last occurrence"));
+    }
+
+    let mut used_names = collect_binding_names(tcx.0, body, body.value.span);
+    used_names.extend(body.params.iter().map(|param| tcx.get_source(param.pat.span)));
+    let name = match name {
+        Some(name) if !is_identifier(name) => return Err(RefactoringErrorInternal::invalid_argument(format!("`{}` is not a valid variable name", name))),
+        Some(name) if used_names.iter().any(|used| used == name) => return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, &format!("the name `{}` is already used", name))),
+        Some(name) => name.to_string(),
+        None => fresh_name(&get_name(tcx, expr), &used_names)
+    };
+
+    let let_stmt = match format_ty(ty) {
+        Some(ty) => format!("let {}: {} = {};", name, ty, source),
+        None => format!("let {} = {};", name, source)
+    };
+    let mut changes = vec![tcx.map_change(anchor.shrink_to_lo(), format!("{}\n{}", let_stmt, tcx.source().get_indent(anchor)))?];
+    for occurrence in &occurrences {
+        let replacement = if let Some(field) = get_shorthand_field(tcx.0, occurrence) {
+            format!("{}: {}", field, name)
+        } else {
+            name.to_string()
+        };
+        changes.push(tcx.map_change(occurrence.span, replacement)?);
+    }
+
+    Ok(AstDiff(changes))
+}
+
+///
+/// A place expression (e.g. `a.b`) is only extracted if it is used by value, as the variable would otherwise be a copy or move of the place
+///
+fn check_place<'tcx>(tcx: &TyContext<'tcx>, expr: &Expr, body_id: BodyId, ty: Ty<'tcx>, is_used_more_than_once: bool) -> QueryResult<()> {
+    let hir = tcx.0.hir();
+    let param_env = tcx.0.param_env(hir.local_def_id(hir.get_parent_item(expr.hir_id)));
+    let is_copy = ty.is_copy_modulo_regions(tcx.0, param_env, expr.span);
+    if !is_copy && is_used_more_than_once {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the type of the expression is not Copy, so it can only replace one occurrence"));
+    }
+    if !is_place(expr) {
+        return Ok(());
+    }
+    let is_borrowed_mut = tcx.0.typeck_tables_of(expr.hir_id.owner.to_def_id()).expr_adjustments(expr).iter()
+        .any(|adjustment| matches!(adjustment.kind, Adjust::Borrow(AutoBorrow::Ref(_, AutoBorrowMutability::Mut { .. }))));
+    let is_written = match hir.get(hir.get_parent_node(expr.hir_id)) {
+        Node::Expr(Expr { kind: ExprKind::AddrOf(..), .. }) => true,
+        Node::Expr(Expr { kind: ExprKind::Assign(lhs, ..), .. }) | Node::Expr(Expr { kind: ExprKind::AssignOp(_, lhs, _), .. }) => lhs.hir_id == expr.hir_id,
+        _ => false
+    };
+    if is_borrowed_mut || is_written {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is assigned to or borrowed"));
+    }
+    let is_moved = || collect_local_uses(tcx.0, body_id).iter().any(|(_, span, use_kind)| *span == expr.span && use_kind.is_moved());
+    if !is_copy && !is_moved() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is a place which is borrowed and its type is not Copy"));
+    }
+    Ok(())
+}
+
+fn is_place(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Path(QPath::Resolved(None, path)) => matches!(path.res, Res::Local(..) | Res::Def(DefKind::Static, _)),
+        ExprKind::Field(..) | ExprKind::Index(..) | ExprKind::Unary(UnOp::UnDeref, _) => true,
+        _ => false
+    }
+}
+
+///
+/// Checks if `expr` is in a closure, loop, match arm or on the right hand side of `&&` / `||` inside `anchor` (including `anchor` itself)
+///
+fn is_evaluated_conditionally(tcx: &TyContext, expr: &Expr, anchor: Span) -> bool {
+    let hir = tcx.0.hir();
+    let mut hir_id = expr.hir_id;
+    let mut prev_span = expr.span;
+    loop {
+        hir_id = hir.get_parent_node(hir_id);
+        let span = hir.span(hir_id);
+        if !anchor.contains(span) {
+            return false;
+        }
+        match hir.get(hir_id) {
+            Node::Arm(..)
+            | Node::Expr(Expr { kind: ExprKind::Loop(..), .. })
+            | Node::Expr(Expr { kind: ExprKind::Closure(..), .. }) => return true,
+            Node::Expr(Expr { kind: ExprKind::Binary(op, _, rhs), .. })
+                if (op.node == BinOpKind::And || op.node == BinOpKind::Or) && rhs.span.contains(prev_span) => return true,
+            _ => {}
+        }
+        if span.source_callsite() == anchor {
+            return false;
+        }
+        prev_span = span;
+    }
+}
+
+///
+/// The type annotation of the variable, or None if the type cannot be written
+///
+fn format_ty(ty: Ty) -> Option<String> {
+    let is_nameable = ty.walk().all(|arg| match arg.unpack() {
+        GenericArgKind::Type(t) => match t.kind {
+            TyKind::Closure(..) | TyKind::Generator(..) | TyKind::Opaque(..) | TyKind::FnDef(..) | TyKind::Infer(..) | TyKind::Error => false,
+            _ => true
+        },
+        _ => true
+    });
+    if is_nameable {
+        Some(with_crate_prefix(|| format!("{}", ty)))
+    } else {
+        None
+    }
+}
+
+///
+/// The name of the method, field or function, e.g. `len` for `v.len()`
+///
+fn get_name(tcx: &TyContext, expr: &Expr) -> String {
+    let name = match &expr.kind {
+        ExprKind::MethodCall(segment, ..) => {
+            let name = format!("{}", segment.ident);
+            ["get_", "to_", "as_", "into_"].iter()
+                .find(|prefix| name.starts_with(*prefix))
+                .map(|prefix| name[prefix.len()..].to_string())
+                .unwrap_or(name)
+        },
+        ExprKind::Field(_, ident) => format!("{}", ident),
+        ExprKind::Call(callee, _) => match &callee.kind {
+            ExprKind::Path(QPath::Resolved(_, path)) => path.segments.last().map(|s| format!("{}", s.ident)).unwrap_or_default(),
+            ExprKind::Path(QPath::TypeRelative(ty, segment)) if format!("{}", segment.ident) == "new" => to_snake_case(&tcx.get_source(ty.span)),
+            ExprKind::Path(QPath::TypeRelative(_, segment)) => format!("{}", segment.ident),
+            _ => "".to_owned()
+        },
+        _ => "".to_owned()
+    };
+    if is_identifier(&name) && name.chars().any(|c| c.is_lowercase()) {
+        name
+    } else {
+        "value".to_owned()
+    }
+}
+
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_EXTRACT;
+    const NAME: &str = "extract-variable";
+
+    #[test]
+    fn extract_method_call_before_statement() {
+        let input = r#"fn foo(v: Vec<i32>) -> usize {
+    let n = 2;
+    let m = n * /*refactor-tool:test-id:start*/v.len()/*refactor-tool:test-id:end*/ + 1;
+    m
+}"#;
+        let expected = Ok(r#"fn foo(v: Vec<i32>) -> usize {
+    let n = 2;
+    let len: usize = v.len();
+    let m = n * /*refactor-tool:test-id:start*/len/*refactor-tool:test-id:end*/ + 1;
+    m
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn replace_all_occurrences_with_name() {
+        let input = r#"fn foo(width: i32, height: i32) -> i32 {
+    let a = /*refactor-tool:test-id:start*/width * height/*refactor-tool:test-id:end*/ * 2;
+    let width_1 = 3;
+    a + width * height + width_1 * height
+}"#;
+        let expected = Ok(r#"fn foo(width: i32, height: i32) -> i32 {
+    let area: i32 = width * height;
+    let a = /*refactor-tool:test-id:start*/area/*refactor-tool:test-id:end*/ * 2;
+    let width_1 = 3;
+    a + area + width_1 * height
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("name", "area").with_option("replace-all", "true"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn fresh_name_for_field() {
+        let input = r#"struct P { x: f64 }
+fn foo(p: &P) -> f64 {
+    let x = 1.0;
+    x + /*refactor-tool:test-id:start*/p.x/*refactor-tool:test-id:end*/.abs()
+}"#;
+        let expected = Ok(r#"struct P { x: f64 }
+fn foo(p: &P) -> f64 {
+    let x = 1.0;
+    let x_1: f64 = p.x;
+    x + /*refactor-tool:test-id:start*/x_1/*refactor-tool:test-id:end*/.abs()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn side_effects_would_be_reordered() {
+        let input = r#"fn bar() -> i32 { 1 }
+fn foo(v: &mut Vec<i32>) -> i32 {
+    v.pop().unwrap() + /*refactor-tool:test-id:start*/bar()/*refactor-tool:test-id:end*/
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the side effects of the expression would be reordered"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn evaluated_conditionally() {
+        let input = r#"fn foo(a: Option<i32>, b: i32) -> i32 {
+    match a {
+        Some(a) => a,
+        None => /*refactor-tool:test-id:start*/b / 2/*refactor-tool:test-id:end*/
+    }
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is evaluated conditionally, repeatedly or in a closure"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn variable_mutated_before_last_occurrence() {
+        let input = r#"fn foo() -> i32 {
+    let mut a = 1;
+    let b = /*refactor-tool:test-id:start*/a + 1/*refactor-tool:test-id:end*/;
+    a += 1;
+    a + 1 + b
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "a variable used by the expression is mutated before the last occurrence"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("replace-all", "true"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn borrowed_place_is_not_extracted() {
+        let input = r#"struct S { name: String }
+fn foo(s: S) -> usize {
+    /*refactor-tool:test-id:start*/s.name/*refactor-tool:test-id:end*/.len()
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_EXTRACT, "the expression is a place which is borrowed and its type is not Copy"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,38 @@
+use rustc_hir::{Block, Expr, ExprKind};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr};
+use rustc_middle::hir::map::Map;
+use rustc_span::source_map::SourceMap;
+
+///
+/// Collects the expressions in `block` (but not in closures) where the source is `source`
+///
+pub fn collect_occurrences<'v>(source_map: &SourceMap, block: &'v Block<'v>, source: &str) -> Vec<&'v Expr<'v>> {
+    let mut v = OccurrenceCollector {
+        source_map,
+        source,
+        occurrences: vec![]
+    };
+    v.visit_block(block);
+    v.occurrences
+}
+
+struct OccurrenceCollector<'v, 'a> {
+    source_map: &'a SourceMap,
+    source: &'a str,
+    occurrences: Vec<&'v Expr<'v>>
+}
+
+impl<'v, 'a> Visitor<'v> for OccurrenceCollector<'v, 'a> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::None
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if !ex.span.from_expansion() && !matches!(ex.kind, ExprKind::DropTemps(..))
+            && self.source_map.span_to_snippet(ex.span).ok().as_deref() == Some(self.source) {
+            self.occurrences.push(ex);
+            return;
+        }
+        walk_expr(self, ex);
+    }
+}
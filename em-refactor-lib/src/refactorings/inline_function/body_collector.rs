@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use rustc_hir::{Body, Expr, ExprKind, HirId, Pat, PatKind, QPath};
+use rustc_hir::def::Res;
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr, walk_pat};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+///
+/// Collects the bindings declared in the parameters and the body (including closure parameters)
+///
+pub fn collect_bindings<'v>(tcx: TyCtxt<'v>, body: &'v Body<'v>) -> Vec<(HirId, String)> {
+    let mut v = BodyCollector {
+        tcx,
+        renames: HashMap::new(),
+        bindings: vec![],
+        replacements: vec![],
+        has_return: false
+    };
+    v.visit_body(body);
+    v.bindings
+}
+
+///
+/// Checks if the body contains `return` (or `?`), which would return from the caller when inlined.
+/// Returns in closures are allowed
+///
+pub fn contains_return<'v>(tcx: TyCtxt<'v>, body: &'v Body<'v>) -> bool {
+    let mut v = BodyCollector {
+        tcx,
+        renames: HashMap::new(),
+        bindings: vec![],
+        replacements: vec![],
+        has_return: false
+    };
+    v.visit_expr(&body.value);
+    v.has_return
+}
+
+///
+/// Collects the replacements needed to rename the bindings in `renames` and their uses, in the parameters and the body.
+/// Shorthand fields (`S {x}`) are expanded (`S {x: y}`)
+///
+pub fn collect_renames<'v>(tcx: TyCtxt<'v>, body: &'v Body<'v>, renames: HashMap<HirId, String>) -> Vec<(Span, String)> {
+    let mut v = BodyCollector {
+        tcx,
+        renames,
+        bindings: vec![],
+        replacements: vec![],
+        has_return: false
+    };
+    v.visit_body(body);
+    v.replacements
+}
+
+struct BodyCollector<'v> {
+    tcx: TyCtxt<'v>,
+    renames: HashMap<HirId, String>,
+    bindings: Vec<(HirId, String)>,
+    replacements: Vec<(Span, String)>,
+    has_return: bool
+}
+
+impl BodyCollector<'_> {
+    fn get_local_rename(&self, ex: &Expr) -> Option<String> {
+        if let ExprKind::Path(QPath::Resolved(None, path)) = ex.kind {
+            if let Res::Local(hir_id) = path.res {
+                return self.renames.get(&hir_id).cloned();
+            }
+        }
+        None
+    }
+}
+
+impl<'v> Visitor<'v> for BodyCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::OnlyBodies(self.tcx.hir())
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        match ex.kind {
+            ExprKind::Ret(..) => {
+                self.has_return = true;
+            },
+            ExprKind::Closure(..) => {
+                // A return in a closure returns from the closure
+                let has_return = self.has_return;
+                walk_expr(self, ex);
+                self.has_return = has_return;
+                return;
+            },
+            ExprKind::Path(..) => {
+                // Shorthand fields are already replaced
+                if !self.replacements.iter().any(|(span, _)| *span == ex.span) {
+                    if let Some(new_name) = self.get_local_rename(ex) {
+                        self.replacements.push((ex.span, new_name));
+                    }
+                }
+            },
+            ExprKind::Struct(_, fields, _) => {
+                for field in fields.iter().filter(|f| f.is_shorthand) {
+                    if let Some(new_name) = self.get_local_rename(&field.expr) {
+                        self.replacements.push((field.expr.span, format!("{}: {}", field.ident, new_name)));
+                    }
+                }
+            },
+            _ => {}
+        }
+        walk_expr(self, ex);
+    }
+    fn visit_pat(&mut self, p: &'v Pat<'v>) {
+        match p.kind {
+            PatKind::Binding(_, hir_id, ident, _) => {
+                self.bindings.push((hir_id, format!("{}", ident)));
+                if let Some(new_name) = self.renames.get(&hir_id) {
+                    self.replacements.push((ident.span, new_name.to_string()));
+                }
+            },
+            PatKind::Struct(_, fields, _) => {
+                for field in fields.iter().filter(|f| f.is_shorthand) {
+                    if let PatKind::Binding(_, hir_id, ..) = field.pat.kind {
+                        if self.renames.contains_key(&hir_id) {
+                            self.replacements.push((field.pat.span.shrink_to_lo(), format!("{}: ", field.ident)));
+                        }
+                    }
+                }
+            },
+            _ => {}
+        }
+        walk_pat(self, p);
+    }
+}
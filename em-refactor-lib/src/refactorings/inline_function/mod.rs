@@ -0,0 +1,422 @@
+use std::collections::{HashMap, HashSet};
+use rustc_ast::ast::Attribute;
+use rustc_hir::{BindingAnnotation, Body, Expr, ExprKind, FnDecl, ImplItem, ImplItemKind, IsAsync, Item, ItemKind, Node, Pat, PatKind, QPath};
+use rustc_hir::def::Res;
+use rustc_hir::def_id::{DefId, LOCAL_CRATE};
+use rustc_middle::ty::TypeckTables;
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::{fresh_name, get_item_span, get_receiver, get_source_with_replacements, replacement_needs_parens};
+use crate::refactorings::visitors::hir::{collect_call, collect_calls, collect_function_definition, collect_method_definition, collect_qpaths, FnCall};
+use body_collector::{collect_bindings, collect_renames, contains_return};
+
+mod body_collector;
+
+const CANNOT_INLINE: &str = "The function cannot be inlined";
+
+/// Inline function
+///
+/// ## Algorithm
+/// 1. If a function or method definition is selected, all calls of it are inlined.
+///    If a call is selected, only that call is inlined
+/// 2. Each call is replaced with the body of the function, where
+///    a. arguments which are local variables of a Copy type are substituted for the parameters
+///    b. other arguments are bound to the parameters with a single `let`, so all are evaluated before any parameter is in scope
+///    c. bindings in the function with the same name as a substituted argument are renamed
+///    d. `self` is renamed to `self_`
+/// 3. The definition, with its attributes and doc comments, is removed if no uses remain
+///
+/// Calls inside macros, in the function itself, or in the arguments of another call which is inlined, are not inlined.
+///
+/// Preconditions
+/// - The function is defined in this crate, and is not async or a method of a trait
+/// - The body does not contain `return` or `?`
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+    let selected_def_id = collect_function_definition(tcx, span).ok().map(|fn_def| fn_def.hir_id)
+        .or_else(|| collect_method_definition(tcx, span));
+
+    let (fn_def, calls) = if let Some(def_id) = selected_def_id {
+        (get_fn_def(tcx, def_id, span)?, collect_calls(tcx, def_id))
+    } else if let Some(call) = collect_call(tcx, span) {
+        (get_fn_def(tcx, call.def_id, span)?, vec![call])
+    } else {
+        return Err(tcx.source().span_err(span, false));
+    };
+
+    if contains_return(tcx.0, fn_def.body) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the body contains return or `?`"));
+    }
+
+    let calls = calls.iter()
+        .filter(|call| !call.expr.span.from_expansion() && !fn_def.span.contains(call.expr.span))
+        .collect::<Vec<_>>();
+    let calls = calls.iter()
+        .filter(|call| !calls.iter().any(|other| other.expr.span != call.expr.span && other.expr.span.contains(call.expr.span)))
+        .collect::<Vec<_>>();
+    if calls.is_empty() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "no calls of the function can be inlined"));
+    }
+
+    let mut changes = vec![];
+    for call in &calls {
+        changes.push(tcx.map_change(call.expr.span, inline_call(tcx, &fn_def, call)?)?);
+    }
+
+    let inlined_paths = calls.iter().filter_map(|call| call.callee).collect::<Vec<_>>();
+    let other_uses = collect_qpaths(tcx, fn_def.def_id).into_iter()
+        .filter(|span| !inlined_paths.contains(span))
+        .count();
+    let other_method_calls = collect_calls(tcx, fn_def.def_id).into_iter()
+        .filter(|call| call.is_method_call() && !calls.iter().any(|inlined| inlined.expr.span == call.expr.span))
+        .count();
+    if other_uses + other_method_calls == 0 && !fn_def.is_exported {
+        changes.push(tcx.map_change(tcx.source().get_lines(get_item_span(tcx.0, fn_def.attrs, fn_def.span)), "".to_owned())?);
+    }
+
+    Ok(AstDiff(changes))
+}
+
+struct FnDef<'v> {
+    def_id: DefId,
+    span: Span,
+    attrs: &'v [Attribute],
+    decl: &'v FnDecl<'v>,
+    body: &'v Body<'v>,
+    /// The parameter types can't be used for the bindings of the arguments if they contain generic parameters
+    has_generics: bool,
+    is_exported: bool
+}
+
+fn get_fn_def<'v>(tcx: &TyContext<'v>, def_id: DefId, span: Span) -> QueryResult<FnDef<'v>> {
+    let hir = tcx.0.hir();
+    let hir_id = hir.as_local_hir_id(def_id)
+        .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the function is not defined in this crate"))?;
+
+    let (item_span, attrs, sig, body_id, has_generics) = match hir.get(hir_id) {
+        Node::Item(Item { kind: ItemKind::Fn(sig, generics, body_id), span, attrs, .. }) => {
+            (*span, *attrs, sig, *body_id, !generics.params.is_empty())
+        },
+        Node::ImplItem(ImplItem { kind: ImplItemKind::Fn(sig, body_id), generics, span, attrs, .. }) => {
+            match &hir.expect_item(hir.get_parent_item(hir_id)).kind {
+                ItemKind::Impl { of_trait: None, generics: impl_generics, .. } => {
+                    (*span, *attrs, sig, *body_id, !generics.params.is_empty() || !impl_generics.params.is_empty())
+                },
+                _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "methods of traits cannot be inlined"))
+            }
+        },
+        _ => return Err(tcx.source().span_err(span, false))
+    };
+    if let IsAsync::Async = sig.header.asyncness {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "async functions cannot be inlined"));
+    }
+
+    Ok(FnDef {
+        def_id,
+        span: item_span,
+        attrs,
+        decl: sig.decl,
+        body: hir.body(body_id),
+        has_generics,
+        is_exported: tcx.0.privacy_access_levels(LOCAL_CRATE).is_exported(hir_id)
+    })
+}
+
+enum ParamBinding<'v> {
+    /// The argument is used in place of the parameter
+    Substituted,
+    /// `self` is bound to `self_`
+    SelfParam { is_mut: bool },
+    /// The argument is bound to the pattern of the parameter
+    Bound(&'v Pat<'v>)
+}
+
+fn inline_call<'v>(tcx: &TyContext<'v>, fn_def: &FnDef<'v>, call: &FnCall) -> QueryResult<String> {
+    let caller_tables = tcx.0.typeck_tables_of(call.expr.hir_id.owner.to_def_id());
+    let callee_tables = tcx.0.typeck_tables_of(fn_def.def_id);
+
+    let mut renames = HashMap::new();
+    let mut substituted = HashSet::new();
+    let mut params = vec![];
+    for (i, (param, arg)) in fn_def.body.params.iter().zip(call.args).enumerate() {
+        params.push(match param.pat.kind {
+            PatKind::Binding(annotation, hir_id, ..) if i == 0 && fn_def.decl.implicit_self.has_implicit_self() => {
+                renames.insert(hir_id, "self_".to_owned());
+                ParamBinding::SelfParam { is_mut: annotation == BindingAnnotation::Mutable }
+            },
+            PatKind::Binding(BindingAnnotation::Unannotated, hir_id, _, None) if is_copy_local(tcx, caller_tables, callee_tables, arg, param.pat) => {
+                let arg = tcx.get_source(arg.span);
+                renames.insert(hir_id, arg.to_string());
+                substituted.insert(arg);
+                ParamBinding::Substituted
+            },
+            _ => ParamBinding::Bound(param.pat)
+        });
+    }
+
+    // The bindings of the function must not capture the substituted arguments
+    let bindings = collect_bindings(tcx.0, fn_def.body);
+    let mut taken = bindings.iter().map(|(_, name)| name.to_string()).collect::<Vec<_>>();
+    taken.extend(substituted.iter().cloned());
+    for (hir_id, name) in bindings {
+        if substituted.contains(&name) && !renames.contains_key(&hir_id) {
+            let new_name = fresh_name(&name, &taken);
+            taken.push(new_name.to_string());
+            renames.insert(hir_id, new_name);
+        }
+    }
+    let replacements = collect_renames(tcx.0, fn_def.body, renames);
+
+    let mut bound = vec![];
+    for (i, (param, arg)) in params.iter().zip(call.args).enumerate() {
+        let arg = if i == 0 && call.is_method_call() {
+            get_receiver(tcx.0, caller_tables, arg, &[])
+        } else {
+            tcx.get_source(arg.span)
+        };
+        match param {
+            ParamBinding::Substituted => {},
+            ParamBinding::SelfParam { is_mut } => {
+                let pat = if *is_mut { "mut self_" } else { "self_" };
+                bound.push((pat.to_owned(), None, arg));
+            },
+            ParamBinding::Bound(pat) => {
+                let ty = if fn_def.has_generics { None } else { Some(tcx.get_source(fn_def.decl.inputs[i].span)) };
+                bound.push((get_source_with_replacements(tcx.0, pat.span, &replacements), ty, arg));
+            }
+        }
+    }
+
+    let body = get_source_with_replacements(tcx.0, fn_def.body.value.span, &replacements)
+        .replace(&format!("\n{}", tcx.source().get_indent(fn_def.span)), &format!("\n{}", tcx.source().get_indent(call.expr.span)));
+    let block = match get_let_stmt(&bound) {
+        Some(let_stmt) => {
+            let rest = &body[1..];
+            let whitespace = rest.chars().take_while(|c| c.is_whitespace()).collect::<String>();
+            format!("{{{}{}{}", whitespace, let_stmt, rest)
+        },
+        None => body
+    };
+
+    if replacement_needs_parens(tcx.0, call.expr) {
+        Ok(format!("({})", block))
+    } else {
+        Ok(block)
+    }
+}
+
+///
+/// A local variable can be used in place of the parameter if it has the same type as the parameter, and is not moved by the call
+///
+fn is_copy_local<'v>(tcx: &TyContext<'v>, caller_tables: &TypeckTables<'v>, callee_tables: &TypeckTables<'v>, arg: &Expr, param: &Pat) -> bool {
+    if let ExprKind::Path(QPath::Resolved(None, path)) = &arg.kind {
+        if let Res::Local(_) = path.res {
+            let arg_ty = tcx.0.erase_regions(&caller_tables.expr_ty_adjusted(arg));
+            let param_ty = tcx.0.erase_regions(&callee_tables.pat_ty(param));
+            return arg_ty == param_ty
+                && arg_ty.is_copy_modulo_regions(tcx.0, tcx.0.param_env(arg.hir_id.owner.to_def_id()), arg.span);
+        }
+    }
+    false
+}
+
+///
+/// A single `let` which binds all arguments, such that the parameters are not in scope when the arguments are evaluated
+///
+fn get_let_stmt(bound: &[(String, Option<String>, String)]) -> Option<String> {
+    match bound {
+        [] => None,
+        [(pat, ty, arg)] => Some(format!("let {}{} = {};", pat, ty.as_ref().map(|ty| format!(": {}", ty)).unwrap_or_default(), arg)),
+        _ => {
+            let pats = bound.iter().map(|(pat, _, _)| pat.to_string()).collect::<Vec<_>>().join(", ");
+            let args = bound.iter().map(|(_, _, arg)| arg.to_string()).collect::<Vec<_>>().join(", ");
+            let ty = if bound.iter().all(|(_, ty, _)| ty.is_none()) {
+                "".to_owned()
+            } else {
+                let tys = bound.iter().map(|(_, ty, _)| ty.as_deref().unwrap_or("_")).collect::<Vec<_>>().join(", ");
+                format!(": ({})", tys)
+            };
+            Some(format!("let ({}){} = ({});", pats, ty, args))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_INLINE;
+    const NAME: &str = "inline-function";
+
+    #[test]
+    fn inline_call_binding_non_copy_argument() {
+        let input = r#"fn foo(a: i32) -> i32 {
+    let b = /*refactor-tool:test-id:start*/bar(a, String::new())/*refactor-tool:test-id:end*/;
+    b
+}
+fn bar(x: i32, s: String) -> i32 {
+    let y = s.len() as i32;
+    x + y
+}
+fn baz() -> i32 { bar(1, String::new()) }"#;
+        let expected = Ok(r#"fn foo(a: i32) -> i32 {
+    let b = /*refactor-tool:test-id:start*/{
+        let s: String = String::new();
+        let y = s.len() as i32;
+        a + y
+    }/*refactor-tool:test-id:end*/;
+    b
+}
+fn bar(x: i32, s: String) -> i32 {
+    let y = s.len() as i32;
+    x + y
+}
+fn baz() -> i32 { bar(1, String::new()) }"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_call_renames_captured_local() {
+        let input = r#"fn foo() -> i32 {
+    let y = 1;
+    /*refactor-tool:test-id:start*/bar(y)/*refactor-tool:test-id:end*/
+}
+fn bar(x: i32) -> i32 {
+    let y = 2;
+    x + y
+}"#;
+        let expected = Ok(r#"fn foo() -> i32 {
+    let y = 1;
+    /*refactor-tool:test-id:start*/{
+        let y_1 = 2;
+        y + y_1
+    }/*refactor-tool:test-id:end*/
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_call_removes_attributes_and_doc_comments() {
+        let input = r#"fn foo() -> i32 {
+    /*refactor-tool:test-id:start*/bar(1)/*refactor-tool:test-id:end*/
+}
+/// Adds one
+#[inline]
+fn bar(x: i32) -> i32 {
+    x + 1
+}
+fn baz() {}"#;
+        let expected = Ok(r#"fn foo() -> i32 {
+    /*refactor-tool:test-id:start*/{
+        let x: i32 = 1;
+        x + 1
+    }/*refactor-tool:test-id:end*/
+}
+fn baz() {}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_call_binding_arguments_at_once() {
+        let input = r#"fn foo(a: String, b: String) -> String {
+    /*refactor-tool:test-id:start*/bar(b, a)/*refactor-tool:test-id:end*/
+}
+fn bar(a: String, b: String) -> String {
+    a + &b
+}"#;
+        let expected = Ok(r#"fn foo(a: String, b: String) -> String {
+    /*refactor-tool:test-id:start*/{
+        let (a, b): (String, String) = (b, a);
+        a + &b
+    }/*refactor-tool:test-id:end*/
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_method_call() {
+        let input = r#"struct S { f: i32 }
+impl S {
+    fn get(&self) -> i32 {
+        self.f
+    }
+}
+fn foo(s: S) -> i32 {
+    /*refactor-tool:test-id:start*/s.get()/*refactor-tool:test-id:end*/ + 1
+}"#;
+        let expected = Ok(r#"struct S { f: i32 }
+impl S {
+}
+fn foo(s: S) -> i32 {
+    /*refactor-tool:test-id:start*/({
+        let self_ = &s;
+        self_.f
+    })/*refactor-tool:test-id:end*/ + 1
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_all_calls_of_selected_function() {
+        let input = r#"fn foo(a: i32) -> i32 {
+    bar(a) * bar(2)
+}
+/*refactor-tool:test-id:start*/fn bar(x: i32) -> i32 {
+    x + 1
+}/*refactor-tool:test-id:end*/"#;
+        let expected = Ok(r#"fn foo(a: i32) -> i32 {
+    ({
+        a + 1
+    }) * ({
+        let x: i32 = 2;
+        x + 1
+    })
+}
+/*refactor-tool:test-id:start*//*refactor-tool:test-id:end*/"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn function_used_as_value_is_kept() {
+        let input = r#"fn foo(a: i32) -> i32 {
+    let f = bar;
+    bar(a) + f(a)
+}
+/*refactor-tool:test-id:start*/fn bar(x: i32) -> i32 {
+    x + 1
+}/*refactor-tool:test-id:end*/"#;
+        let expected = Ok(r#"fn foo(a: i32) -> i32 {
+    let f = bar;
+    ({
+        a + 1
+    }) + f(a)
+}
+/*refactor-tool:test-id:start*/fn bar(x: i32) -> i32 {
+    x + 1
+}/*refactor-tool:test-id:end*/"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn function_with_return_is_not_inlined() {
+        let input = r#"fn foo(a: i32) -> i32 {
+    /*refactor-tool:test-id:start*/bar(a)/*refactor-tool:test-id:end*/
+}
+fn bar(x: i32) -> i32 {
+    if x > 0 {
+        return x;
+    }
+    0
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the body contains return or `?`"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
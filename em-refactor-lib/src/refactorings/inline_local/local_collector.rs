@@ -0,0 +1,49 @@
+use rustc_hir::{BodyId, Local, Stmt, StmtKind};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_crate, walk_stmt};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+///
+/// Finds the `let` statement where the statement, the `let` or its pattern is `pos`, and the body containing it
+///
+pub fn collect_local<'v>(tcx: TyCtxt<'v>, pos: Span) -> Option<(&'v Stmt<'v>, &'v Local<'v>, BodyId)> {
+    let mut v = LocalCollector {
+        tcx,
+        pos,
+        body_ids: vec![],
+        result: None
+    };
+    walk_crate(&mut v, tcx.hir().krate());
+    v.result
+}
+
+struct LocalCollector<'v> {
+    tcx: TyCtxt<'v>,
+    pos: Span,
+    body_ids: Vec<BodyId>,
+    result: Option<(&'v Stmt<'v>, &'v Local<'v>, BodyId)>
+}
+
+impl<'v> Visitor<'v> for LocalCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_nested_body(&mut self, id: BodyId) {
+        self.body_ids.push(id);
+        let body = self.tcx.hir().body(id);
+        self.visit_body(body);
+        self.body_ids.pop();
+    }
+    fn visit_stmt(&mut self, stmt: &'v Stmt<'v>) {
+        if let StmtKind::Local(local) = &stmt.kind {
+            if self.pos == stmt.span || self.pos == local.span || self.pos == local.pat.span {
+                if let Some(body_id) = self.body_ids.last() {
+                    self.result = Some((stmt, local, *body_id));
+                }
+            }
+        }
+        walk_stmt(self, stmt);
+    }
+}
@@ -0,0 +1,210 @@
+use rustc_hir::{BindingAnnotation, Expr, ExprKind, Node, PatKind};
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::{get_shorthand_field, replacement_needs_parens};
+use crate::refactorings::visitors::collect_local_variable_use_exprs;
+use crate::refactorings::visitors::hir::{collect_binding_names, collect_local_uses, collect_locals, contains_side_effects, is_pure};
+use local_collector::collect_local;
+
+mod local_collector;
+
+const CANNOT_INLINE: &str = "The variable cannot be inlined";
+
+/// Inline local
+///
+/// ## Algorithm
+/// 1. Each use of the variable declared by the selected `let` is replaced with the initializer
+/// 2. The `let` statement is removed
+///
+/// Preconditions
+/// - The pattern is a single binding (`x` or `mut x`) and has an initializer
+/// - The variable is not mutated (or mutably borrowed), and is moved at most once
+/// - The variables used by the initializer are not mutated or shadowed before the last use
+/// - If the initializer can have side effects, the variable is used exactly once (not in a loop or closure),
+///   and nothing with side effects is evaluated between the `let` and the use
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+    let (stmt, local, body_id) = collect_local(tcx.0, span).ok_or_else(|| tcx.source().span_err(span, false))?;
+    let init = local.init.ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable has no initializer"))?;
+    let hir_id = match local.pat.kind {
+        PatKind::Binding(BindingAnnotation::Unannotated, hir_id, _, None)
+        | PatKind::Binding(BindingAnnotation::Mutable, hir_id, _, None) => hir_id,
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the pattern is not a single variable"))
+    };
+
+    let hir = tcx.0.hir();
+    let body = hir.body(body_id);
+    let uses = collect_local_variable_use_exprs(tcx, hir_id);
+    let local_uses = collect_local_uses(tcx.0, body_id);
+
+    // The binding itself is reported as a mutation
+    let use_kinds = local_uses.iter()
+        .filter(|(id, span, _)| *id == hir_id && !local.span.contains(*span))
+        .map(|(_, _, use_kind)| *use_kind)
+        .collect::<Vec<_>>();
+    if use_kinds.iter().any(|use_kind| use_kind.is_mutated()) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable is mutated"));
+    }
+    if use_kinds.iter().filter(|use_kind| use_kind.is_moved()).count() > 1 {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable is moved more than once"));
+    }
+    if uses.iter().any(|use_| use_.span.from_expansion()) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable is used in a macro"));
+    }
+
+    if let Some(last_use) = uses.iter().map(|use_| use_.span.hi()).max() {
+        let after_let = stmt.span.shrink_to_hi().with_hi(last_use);
+        let read_locals = collect_locals(init);
+        if local_uses.iter().any(|(id, span, use_kind)| read_locals.contains(id) && use_kind.is_mutated() && after_let.contains(*span)) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "a variable used by the initializer is mutated before the last use"));
+        }
+        let read_names = read_locals.iter().map(|id| format!("{}", hir.name(*id))).collect::<Vec<_>>();
+        if collect_binding_names(tcx.0, body, after_let).iter().any(|name| read_names.contains(name)) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "a variable used by the initializer is shadowed before the last use"));
+        }
+    }
+
+    if !is_pure(init) {
+        match uses.as_slice() {
+            [use_] if !contains_side_effects(body, stmt.span.shrink_to_hi().with_hi(use_.span.lo()))
+                && !is_evaluated_repeatedly(tcx, use_, stmt.span) => {},
+            [_] => return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the side effects of the initializer would be reordered")),
+            _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the initializer can have side effects and the variable is not used exactly once"))
+        }
+    }
+
+    let init_source = tcx.get_source(init.span.source_callsite());
+    let mut changes = vec![];
+    for use_ in &uses {
+        let replacement = if let Some(field) = get_shorthand_field(tcx.0, use_) {
+            format!("{}: {}", field, init_source)
+        } else if !is_atomic(init) && replacement_needs_parens(tcx.0, use_) {
+            format!("({})", init_source)
+        } else {
+            init_source.to_string()
+        };
+        changes.push(tcx.map_change(use_.span, replacement)?);
+    }
+    changes.push(tcx.map_change(tcx.source().get_lines(stmt.span), "".to_owned())?);
+
+    Ok(AstDiff(changes))
+}
+
+///
+/// Expressions which never need parentheses
+///
+fn is_atomic(expr: &Expr) -> bool {
+    if expr.span.from_expansion() {
+        return true;
+    }
+    match expr.kind {
+        ExprKind::Path(..) | ExprKind::Lit(..) | ExprKind::Call(..) | ExprKind::MethodCall(..) | ExprKind::Field(..)
+        | ExprKind::Index(..) | ExprKind::Tup(..) | ExprKind::Array(..) | ExprKind::Struct(..) => true,
+        _ => false
+    }
+}
+
+///
+/// Checks if `expr` is in a loop or closure which does not contain the `let`
+///
+fn is_evaluated_repeatedly(tcx: &TyContext, expr: &Expr, let_span: Span) -> bool {
+    let hir = tcx.0.hir();
+    let mut hir_id = expr.hir_id;
+    loop {
+        hir_id = hir.get_parent_node(hir_id);
+        if hir.span(hir_id).contains(let_span) {
+            return false;
+        }
+        if let Node::Expr(Expr { kind: ExprKind::Loop(..), .. }) | Node::Expr(Expr { kind: ExprKind::Closure(..), .. }) = hir.get(hir_id) {
+            return true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_INLINE;
+    const NAME: &str = "inline-local";
+
+    #[test]
+    fn inline_pure_initializer_at_each_use() {
+        let input = r#"fn foo(a: i32, b: i32) -> i32 {
+    let /*refactor-tool:test-id:start*/c/*refactor-tool:test-id:end*/ = a + b;
+    c * c + c.pow(2)
+}"#;
+        let expected = Ok(r#"fn foo(a: i32, b: i32) -> i32 {
+    (a + b) * (a + b) + (a + b).pow(2)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn inline_call_into_shorthand_field() {
+        let input = r#"struct S { len: usize }
+fn foo(v: Vec<i32>) -> S {
+    let /*refactor-tool:test-id:start*/len/*refactor-tool:test-id:end*/ = v.len();
+    S { len }
+}"#;
+        let expected = Ok(r#"struct S { len: usize }
+fn foo(v: Vec<i32>) -> S {
+    S { len: v.len() }
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn variable_moved_more_than_once() {
+        let input = r#"fn foo(b: bool, t: String) -> String {
+    let /*refactor-tool:test-id:start*/s/*refactor-tool:test-id:end*/ = t;
+    if b { s } else { s + "!" }
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable is moved more than once"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn variable_mutated() {
+        let input = r#"fn foo() -> i32 {
+    let /*refactor-tool:test-id:start*/mut x/*refactor-tool:test-id:end*/ = 1;
+    x += 1;
+    x
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the variable is mutated"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn side_effects_would_be_reordered() {
+        let input = r#"fn foo() -> i32 {
+    let /*refactor-tool:test-id:start*/a/*refactor-tool:test-id:end*/ = bar();
+    bar();
+    a
+}
+fn bar() -> i32 { 1 }"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the side effects of the initializer would be reordered"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn side_effects_in_loop() {
+        let input = r#"fn foo() -> i32 {
+    let /*refactor-tool:test-id:start*/a/*refactor-tool:test-id:end*/ = bar();
+    let mut sum = 0;
+    for i in 0..2 {
+        sum += i * a;
+    }
+    sum
+}
+fn bar() -> i32 { 1 }"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_INLINE, "the side effects of the initializer would be reordered"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
@@ -1,7 +1,7 @@
 use super::utils::{map_change_from_span, get_source};
 use em_refactor_lib_types::{create_refactor_tool_marker, FileStringReplacement, defs::INTRODUCE_CLOSURE_CALL_EXPR};
 use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
-use crate::refactorings::visitors::hir::{collect_cfs, collect_innermost_contained_block};
+use crate::refactorings::visitors::hir::{collect_cfs, collect_innermost_contained_block, contains_await};
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
 
@@ -32,8 +32,11 @@ fn get_start_comment(add_comment: bool) -> String {
 /// Output
 /// - A new expression containing the block as an anonymous closure
 /// 
-/// Preconditions
-/// - Break, continue, return, `?` are not currently handled, so they must be preventet
+/// Break, continue and return are mapped to variants of an enum, which is matched on after the call. Labeled breaks and continues have a variant for each label.
+/// If the block contains `?`, the closure returns the Result / Option of the enclosing function and `?` is applied to the call.
+/// If the block contains `.await`, it becomes an async block which is awaited, since a closure cannot await.
+/// Break, continue and return are mapped to the enum as for a closure, and with `?`, the values of the async block are wrapped in `Ok::<_, E>` / `Some`
+/// and `?` is applied after `.await`.
 /// 
 pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryResult<AstDiff> {
     if let Some(result) = collect_innermost_contained_block(tcx, span) {
@@ -44,22 +47,59 @@ pub fn do_refactoring(tcx: &TyContext, span: Span, add_comment: bool) -> QueryRe
 
         let cf_expr = collect_cfs(tcx.0, result.0.hir_id);
 
+        if contains_await(result.0) {
+            let block_span = result.0.span;
+            let match_ = if cf_expr.has_cfs() { "match " } else { "" };
+            let mut replacements = vec![
+                tcx.map_change(block_span.shrink_to_lo(), format!("{}{}async ", match_, get_start_comment(add_comment)))?
+            ];
+            if cf_expr.has_cfs() || cf_expr.try_type.is_some() {
+                for (span, replacement) in cf_expr.replace_cfs(true) {
+                    replacements.push(tcx.map_change(span, replacement)?);
+                }
+            }
+            let try_op = if cf_expr.try_type.is_some() { "?" } else { "" };
+            if cf_expr.has_cfs() {
+                replacements.push(tcx.map_change(block_span.shrink_to_hi(), format!(".await{}{} {{{}}}", get_end_comment(add_comment), try_op, cf_expr.get_cf_arms()))?);
+                let parent_mod_span = get_parent_mod_inner(tcx, result.0.hir_id);
+                replacements.push(tcx.map_change(parent_mod_span.shrink_to_hi(), cf_expr.get_enum_decl())?);
+            } else {
+                replacements.push(tcx.map_change(block_span.shrink_to_hi(), format!(".await{}{}", get_end_comment(add_comment), try_op))?);
+            }
+            return Ok(AstDiff(replacements));
+        }
+
         let mut replacements = vec![]; 
+        let (return_type, try_op) = match cf_expr.get_return_type() {
+            Some(ty) => (format!("-> {} ", ty), "?"),
+            None => ("".to_owned(), "")
+        };
         if cf_expr.has_cfs() {
 
             replacements.push(tcx.map_change(span.shrink_to_lo(), 
-                format!("match {}(|| ", get_start_comment(add_comment)))?);
+                format!("match {}(|| {}", get_start_comment(add_comment), return_type))?);
 
-            for (span, replacement) in cf_expr.replace_cfs() {
+            for (span, replacement) in cf_expr.replace_cfs(false) {
                 replacements.push(tcx.map_change(span, replacement)?);
             }
 
             replacements.push(tcx.map_change(span.shrink_to_hi(), 
-                format!(")(){} {{{}}}", get_end_comment(add_comment), cf_expr.get_cf_arms()))?);
+                format!(")(){}{} {{{}}}", get_end_comment(add_comment), try_op, cf_expr.get_cf_arms()))?);
 
             let parent_mod_span = get_parent_mod_inner(tcx, result.0.hir_id);
             replacements.push(tcx.map_change(parent_mod_span.shrink_to_hi(), cf_expr.get_enum_decl())?);
 
+        } else if cf_expr.try_type.is_some() {
+            let block_span = result.0.span;
+            replacements.push(tcx.map_change(block_span.shrink_to_lo(), 
+                format!("{}(|| {}", get_start_comment(add_comment), return_type))?);
+
+            for (span, replacement) in cf_expr.replace_cfs(false) {
+                replacements.push(tcx.map_change(span, replacement)?);
+            }
+
+            replacements.push(tcx.map_change(block_span.shrink_to_hi(), 
+                format!(")(){}{}", get_end_comment(add_comment), try_op))?);
         } else {
             replacements.push(get_call(tcx.0, result.0.span, add_comment)?);
         }
@@ -143,5 +183,91 @@ Expr(i32)
             .with_add_comment());
         assert_eq!(actual, expected);
     }
+    #[test]
+    fn introduce_closure_try_result() {
+        let input = r#"fn foo() -> Result<i32, std::num::ParseIntError> {
+    let i = /*refactor-tool:test-id:start*/{
+        "1".parse::<i32>()? + 1
+    }/*refactor-tool:test-id:end*/;
+    Ok(i)
+}"#;
+        let expected = Ok(r#"fn foo() -> Result<i32, std::num::ParseIntError> {
+    let i = /*refactor-tool:test-id:start*/(|| -> std::result::Result<i32, std::num::ParseIntError> {
+        Ok("1".parse::<i32>()? + 1)
+    })()?/*refactor-tool:test-id:end*/;
+    Ok(i)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn introduce_closure_try_result_alias() {
+        let input = r#"type Result<T> = std::result::Result<T, std::num::ParseIntError>;
+fn foo() -> Result<i32> {
+    let i = /*refactor-tool:test-id:start*/{
+        "1".parse::<i32>()? + 1
+    }/*refactor-tool:test-id:end*/;
+    Ok(i)
+}"#;
+        let expected = Ok(r#"type Result<T> = std::result::Result<T, std::num::ParseIntError>;
+fn foo() -> Result<i32> {
+    let i = /*refactor-tool:test-id:start*/(|| -> std::result::Result<i32, std::num::ParseIntError> {
+        Ok("1".parse::<i32>()? + 1)
+    })()?/*refactor-tool:test-id:end*/;
+    Ok(i)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn introduce_closure_try_option_no_tail() {
+        let input = r#"fn foo(v: Option<i32>) -> Option<i32> {
+    /*refactor-tool:test-id:start*/{
+        v?;
+    }/*refactor-tool:test-id:end*/
+    None
+}"#;
+        let expected = Ok(r#"fn foo(v: Option<i32>) -> Option<i32> {
+    /*refactor-tool:test-id:start*/(|| -> std::option::Option<()> {
+        v?;
+    Some(())})()?/*refactor-tool:test-id:end*/
+    None
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn introduce_closure_try_and_break() {
+        let input = r#"fn foo() -> Result<(), std::num::ParseIntError> {
+    loop {
+        let i = /*refactor-tool:test-id:start*/{
+            if "1".parse::<i32>()? == 1 { break; }
+            2
+        }/*refactor-tool:test-id:end*/;
+    }
+    Ok(())
+}"#;
+        let expected = Ok(r#"fn foo() -> Result<(), std::num::ParseIntError> {
+    loop {
+        let i = /*refactor-tool:test-id:start*/match (|| -> std::result::Result<ReturnFoo, std::num::ParseIntError> {
+            if "1".parse::<i32>()? == 1 { return Ok(ReturnFoo::Break()); }
+            Ok(ReturnFoo::Expr(2))
+        })()? {
+ReturnFoo::Break() => break,
+ReturnFoo::Expr(e) => e}/*refactor-tool:test-id:end*/;
+    }
+    Ok(())
+}
+enum ReturnFoo {
+Break(),
+Expr(i32)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
     // TODO: test add_comment for call expression and match-expression
 }
\ No newline at end of file
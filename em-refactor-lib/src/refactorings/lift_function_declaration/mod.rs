@@ -1,11 +1,19 @@
-use rustc_span::Span;
+use rustc_hir::{BindingAnnotation, BorrowKind, Expr, ExprKind, Item, ItemKind, Local, Mutability, Node, PatKind};
+use rustc_middle::ty::TyKind;
+use rustc_span::{BytePos, DesugaringKind, Span};
 use crate::refactoring_invocation::{AstDiff, QueryResult, TyContext};
-use crate::refactorings::visitors::hir::{collect_function_definition, FnDefinition};
-use qpath_res_collector::collect_qpaths;
-
-mod qpath_res_collector;
+use crate::refactorings::convert_to_method::is_postfix;
+use crate::refactorings::utils::{get_source_with_replacements, is_declared_in_body};
+use crate::refactorings::visitors::collect_local_variable_use_exprs;
+use crate::refactorings::visitors::hir::{collect_calls, collect_function_definition, collect_qpaths, FnDefinition};
+use em_refactor_lib_types::{create_refactor_tool_marker, defs::CLOSE_OVER_VARIABLES_SELF};
 
 /// Lift function declaration
+///
+/// A function declared in a method of an inherent impl is moved into the impl. If it is called with `self` marked by close over variables
+/// (which chooses the type of the parameter from how `self` is used), and the parameter has the type `Self`, `&Self` or `&mut Self`,
+/// it becomes a method with the receiver `self`, `&self` or `&mut self`, and its calls `f(a, self)` become `self.f(a)`.
+/// A function declared in a nested function is moved after it, otherwise it is moved to the parent module.
 pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
 
     // Find function declaration
@@ -30,26 +38,142 @@ pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryR
 }
 
 fn move_to_parent_mod(tcx: &TyContext, fn_def: FnDefinition) -> QueryResult<AstDiff> {
+    let hir = tcx.0.hir();
+    // A function declared in a nested function is moved next to it, where the items declared in the same body are visible
+    let (target, indent) = match hir.as_local_hir_id(fn_def.hir_id).map(|hir_id| hir.get_parent_item(hir_id)) {
+        Some(parent) if is_declared_in_body(tcx.0, parent) => (hir.span(parent), tcx.source().get_indent(hir.span(parent))),
+        _ => (fn_def.get_parent_mod_inner(), "".to_owned())
+    };
 
     let changes = vec![
         tcx.map_change(fn_def.span, "".to_owned())?,
-        tcx.map_change(fn_def.get_parent_mod_inner().shrink_to_hi(), format!("\n{}", tcx.get_source(fn_def.span)))?
+        tcx.map_change(target.shrink_to_hi(), format!("\n{}{}", indent, tcx.get_source(fn_def.span)))?
     ];
 
     Ok(AstDiff(changes))
 }
 fn move_to_impl(tcx: &TyContext, fn_def: FnDefinition) -> QueryResult<AstDiff> {
+    let self_param = get_self_param(tcx, &fn_def);
+    let source = match &self_param {
+        Some((_, replacements)) => get_source_with_replacements(tcx.0, fn_def.span, replacements),
+        None => tcx.get_source(fn_def.span)
+    };
 
+    let impl_item = fn_def.impl_.unwrap().0;
     let mut changes = vec![
         tcx.map_change(fn_def.span, "".to_owned())?,
-        tcx.map_change(fn_def.impl_.unwrap().0.shrink_to_hi(), format!("\n{}", tcx.get_source(fn_def.span)))?
+        tcx.map_change(impl_item.shrink_to_hi(), format!("\n{}{}", tcx.source().get_indent(impl_item), source))?
     ];
 
+    let mut method_calls = vec![];
+    if let Some((index, _)) = self_param {
+        let name = tcx.0.item_name(fn_def.hir_id);
+        for call in collect_calls(tcx, fn_def.hir_id) {
+            if call.is_method_call() || call.expr.span.from_expansion() {
+                continue;
+            }
+            let receiver = match &call.args[index].kind {
+                ExprKind::AddrOf(BorrowKind::Ref, _, expr) => expr,
+                _ => &call.args[index]
+            };
+            let mut receiver_source = tcx.get_source(receiver.span);
+            if !is_postfix(receiver) {
+                receiver_source = format!("({})", receiver_source);
+            }
+            let args = call.args.iter().enumerate()
+                .filter(|(i, _)| *i != index)
+                .map(|(_, arg)| tcx.get_source(arg.span))
+                .collect::<Vec<_>>();
+            let method_call = format!("{}.{}({})", receiver_source, name, args.join(", "));
+            // The declaration in `({ fn f() {} f })(a)` is removed separately
+            let call_span = match call.expr.kind {
+                ExprKind::Call(Expr { kind: ExprKind::Block(block, _), .. }, _) => {
+                    changes.push(tcx.map_change(call.expr.span.with_hi(block.span.lo() + BytePos(1)), "".to_owned())?);
+                    call.callee.unwrap().to(call.expr.span.shrink_to_hi())
+                },
+                _ => call.expr.span
+            };
+            changes.push(tcx.map_change(call_span, method_call)?);
+            method_calls.push(call_span);
+        }
+    }
+
     for span in collect_qpaths(tcx, fn_def.hir_id) {
-        changes.push(
-            tcx.map_change(span, format!("Self::{}", tcx.get_source(span)))?
-        );
+        if !method_calls.iter().any(|call| call.contains(span)) {
+            changes.push(
+                tcx.map_change(span, format!("Self::{}", tcx.get_source(span)))?
+            );
+        }
     }
 
     Ok(AstDiff(changes))
 }
+
+///
+/// The index of the parameter for `self` of the type `Self`, `&Self` or `&mut Self`, and the replacements in the function which make it the receiver
+///
+fn get_self_param(tcx: &TyContext, fn_def: &FnDefinition) -> Option<(usize, Vec<(Span, String)>)> {
+    let hir = tcx.0.hir();
+    let hir_id = hir.as_local_hir_id(fn_def.hir_id)?;
+    let (decl, body_id) = match hir.get(hir_id) {
+        Node::Item(Item { kind: ItemKind::Fn(sig, _, body_id), .. }) => (sig.decl, *body_id),
+        _ => return None
+    };
+    let body = hir.body(body_id);
+    let index = get_marked_self_arg(tcx, fn_def)?;
+    let param = body.params.get(index)?;
+    if !matches!(param.pat.kind, PatKind::Binding(_, _, _, None)) {
+        return None;
+    }
+
+    // The function is declared in a method of the impl
+    let impl_hir_id = hir.get_parent_item(hir.get_parent_item(hir_id));
+    let self_ty = tcx.0.type_of(hir.local_def_id(impl_hir_id));
+    let ty = tcx.0.fn_sig(fn_def.hir_id).skip_binder().inputs()[index];
+    let is_mut = matches!(param.pat.kind, PatKind::Binding(BindingAnnotation::Mutable, ..));
+    let receiver = match (&ty.kind, is_mut) {
+        (TyKind::Ref(_, inner, Mutability::Not), false) if *inner == self_ty => "&self",
+        (TyKind::Ref(_, inner, Mutability::Mut), false) if *inner == self_ty => "&mut self",
+        (_, false) if ty == self_ty => "self",
+        (_, true) if ty == self_ty => "mut self",
+        _ => return None
+    };
+
+    let mut uses = vec![];
+    let mut bindings = vec![param.pat.hir_id];
+    while let Some(binding) = bindings.pop() {
+        for use_ in collect_local_variable_use_exprs(tcx, binding) {
+            if !use_.span.from_expansion() {
+                uses.push(use_.span);
+                continue;
+            }
+            // The parameters of an async function are moved into its body by `let self_ = self_;`
+            match hir.find(hir.get_parent_node(use_.hir_id)) {
+                Some(Node::Local(Local { pat, .. })) if use_.span.is_desugaring(DesugaringKind::Async) => bindings.push(pat.hir_id),
+                _ => return None
+            }
+        }
+    }
+
+    // The receiver is the first parameter
+    let other_params = body.params.iter().zip(decl.inputs).enumerate()
+        .filter(|(i, _)| *i != index)
+        .map(|(_, (param, ty))| tcx.get_source(param.pat.span.source_callsite().to(ty.span)));
+    let params = std::iter::once(receiver.to_owned()).chain(other_params).collect::<Vec<_>>();
+    let mut replacements = vec![(body.params[0].pat.span.source_callsite().to(decl.inputs.last()?.span), params.join(", "))];
+    replacements.extend(uses.into_iter().map(|use_| (use_, "self".to_owned())));
+    Some((index, replacements))
+}
+
+///
+/// The index of the argument `self` which close over variables has marked in a call of the function
+///
+fn get_marked_self_arg(tcx: &TyContext, fn_def: &FnDefinition) -> Option<usize> {
+    let marker = create_refactor_tool_marker(CLOSE_OVER_VARIABLES_SELF, false);
+    let source_map = tcx.0.sess.source_map();
+    collect_calls(tcx, fn_def.hir_id).iter()
+        .filter(|call| !call.is_method_call())
+        .find_map(|call| call.args.iter().position(|arg| {
+            source_map.span_to_prev_source(arg.span).map_or(false, |prev| prev.ends_with(&marker))
+        }))
+}
@@ -1,13 +1,25 @@
 pub mod box_field;
 mod box_named_field;
 mod box_tuple_field;
+pub mod change_signature;
 pub mod close_over_variables;
 pub mod convert_closure_to_fn;
+pub mod convert_to_associated_function;
+pub mod convert_to_method;
+pub mod encapsulate_field;
 pub mod extract_block;
+pub mod extract_variable;
+pub mod inline_function;
+pub mod inline_local;
 pub mod inline_macro;
 pub mod introduce_closure;
 pub mod lift_function_declaration;
+pub mod move_item;
 pub mod pull_up_item_declaration;
 pub mod remove_refactoring_comments;
+pub mod rename;
+pub mod split_conflicting_match_arms;
+pub mod unbox_field;
 pub mod utils;
 pub mod visitors;
+pub mod wrap_field;
@@ -0,0 +1,598 @@
+use std::path::Path as FilePath;
+use em_refactor_lib_types::FileStringReplacement;
+use rustc_hir::{HirId, ImplItem, Item, ItemKind, Node, Path, StructField, UseKind, Visibility, VisibilityKind};
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{CRATE_DEF_INDEX, DefId};
+use rustc_middle::ty::{DefIdTree, TyCtxt, Visibility as DefVisibility};
+use rustc_span::{BytePos, FileName, Span, symbol::kw};
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::{get_absolute_path, get_item_span, get_relative_path, get_source_with_replacements};
+use reference_collector::{collect_references, Reference};
+
+mod reference_collector;
+
+const CANNOT_MOVE: &str = "The item cannot be moved";
+
+/// The module to move the item to
+enum Target {
+    Existing(DefId),
+    /// A module with this name, which is declared in the module
+    New(DefId, String)
+}
+
+/// Move item
+///
+/// ## Algorithm
+///
+/// Steps
+/// - I <- the selected function, struct, enum, impl or constant, declared in the module M
+/// - T <- the module given by the option `to` (e.g. `crate::a::b`), which is declared as `mod b;` with a new file if it does not exist
+/// - abort if I is in T already, or if T has an item with the name of I
+/// - for each path to I outside of I:
+///   - if it starts with a path to M (e.g. `m::I` or `super::I`), replace that with a path to T
+///   - if I is imported with braces (e.g. `use m::{I, J};`), remove it and add a separate `use T::I;`
+///   - if it is the name of I in M, add `use T::I;` to M
+/// - for each path in I which is relative to M, and starts with `self`, `super`, the name of an item declared in M or the name imported by a `use` in M:
+///   - replace that with a path to it
+/// - make each private item, field or associated item `pub(crate)`, if it is used where it is not visible after the move. These are
+///   - I and its fields and associated items, when used outside of T
+///   - the items used by I, which are not visible in T
+/// - move I (with its attributes) to the end of T
+///
+/// When the selection is in a source file of another (local) crate, the paths of this crate to the item defined at the selection are replaced.
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, to: &str) -> QueryResult<AstDiff> {
+    let to_segments = to.split("::").map(str::trim).collect::<Vec<_>>();
+    if to_segments.iter().any(|segment| segment.is_empty()) {
+        return Err(RefactoringErrorInternal::arg_def(&format!("Invalid value for option to: {}", to)));
+    }
+    let (references, paths) = collect_references(tcx.0);
+
+    if tcx.0.sess.source_map().lookup_char_pos(span.lo()).file.is_imported() {
+        let def_id = match references.iter().map(|r| r.def_id).find(|def_id| !def_id.is_local() && tcx.0.def_span(*def_id) == span) {
+            Some(def_id) => def_id,
+            None => return Ok(AstDiff(vec![]))
+        };
+        let module = tcx.0.parent(def_id).unwrap();
+        let crate_name = format!("{}", tcx.0.crate_name(def_id.krate));
+        let target_path = std::iter::once(crate_name.as_str())
+            .chain(to_segments.iter().skip_while(|segment| **segment == "crate").cloned())
+            .collect::<Vec<_>>()
+            .join("::");
+        let mut replacements = vec![];
+        for (path, hir_id) in &paths {
+            replacements.extend(rewrite_path_to_item(tcx, path, *hir_id, def_id, module, &target_path)?);
+        }
+        return get_changes(tcx, replacements, vec![]);
+    }
+
+    let hir = tcx.0.hir();
+    let item = hir.krate().items.values()
+        .filter(|item| span.contains(item.span) && get_item_span(tcx.0, item.attrs, item.span).contains(span))
+        .min_by_key(|item| item.span.hi() - item.span.lo())
+        .ok_or_else(|| tcx.source().span_err(span, false))?;
+    let def_id = hir.local_def_id(item.hir_id);
+    let module = tcx.0.parent_module(item.hir_id).to_def_id();
+    match item.kind {
+        ItemKind::Fn(..) | ItemKind::Const(..) | ItemKind::Struct(..) | ItemKind::Enum(..) | ItemKind::Impl { .. } => {},
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, "only functions, structs, enums, impls and constants can be moved"))
+    }
+    if item.span.from_expansion() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, "the item is declared by a macro"));
+    }
+    if tcx.0.parent(def_id) != Some(module) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, "the item is not declared in a module"));
+    }
+    let is_impl = matches!(item.kind, ItemKind::Impl { .. });
+
+    let target = get_target(tcx.0, &to_segments)?;
+    let target_path = match &target {
+        Target::Existing(target) if *target == module => {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, "the item is already in the module"));
+        },
+        Target::Existing(target) => get_absolute_path(tcx.0, *target),
+        Target::New(parent, name) => format!("{}::{}", get_absolute_path(tcx.0, *parent), name)
+    };
+    if let Target::Existing(target) = target {
+        if !is_impl && get_items(tcx.0, target).iter().any(|other| other.ident == item.ident && !matches!(other.kind, ItemKind::Impl { .. })) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, &format!("the module already has an item named {}", item.ident)));
+        }
+    }
+    let is_in_target = |module: DefId| match target {
+        Target::Existing(target) => tcx.0.is_descendant_of(module, target),
+        Target::New(..) => false
+    };
+    let item_span = get_item_span(tcx.0, item.attrs, item.span);
+    let is_in_item = |span: Span| item_span.contains(span.source_callsite());
+    let is_outside = |reference: &Reference| !is_in_item(reference.span) && !is_in_target(tcx.0.parent_module(reference.hir_id).to_def_id());
+
+    let mut replacements = vec![];
+    let mut needs_use = false;
+    if !is_impl {
+        for (path, hir_id) in &paths {
+            if is_in_item(path.span) {
+                continue;
+            }
+            replacements.extend(rewrite_path_to_item(tcx, path, *hir_id, def_id, module, &target_path)?);
+            // The name of the item in M, e.g. `I` or `I::new`
+            needs_use |= is_item_res(tcx.0, get_segment_res(path, 0), def_id)
+                && tcx.0.parent_module(*hir_id).to_def_id() == module && !path.span.from_expansion();
+        }
+    }
+    for (path, hir_id) in paths.iter().filter(|(path, _)| is_in_item(path.span) && !path.span.from_expansion()) {
+        let is_import = matches!(hir.find(*hir_id), Some(Node::Item(Item { kind: ItemKind::Use(..), .. })));
+        replacements.extend(rewrite_path_in_item(tcx, path, is_import, def_id, module, &target_path));
+    }
+
+    // The visibility of the item, and of its fields and associated items
+    let mut moved = vec![];
+    if !is_impl {
+        moved.push((def_id, &item.vis));
+    }
+    match &item.kind {
+        ItemKind::Struct(data, _) => moved.extend(data.fields().iter().map(|field| (hir.local_def_id(field.hir_id), &field.vis))),
+        ItemKind::Impl { of_trait: None, items, .. } => moved.extend(items.iter().map(|item| (hir.local_def_id(item.id.hir_id), &item.vis))),
+        _ => {}
+    }
+    let is_used_outside = |def_id: DefId| references.iter().any(|r| r.def_id == def_id && is_outside(r));
+    for (def_id, vis) in &moved {
+        if !matches!(vis.node, VisibilityKind::Public | VisibilityKind::Crate(_)) && is_used_outside(*def_id) {
+            replacements.push(make_visible(vis));
+        }
+    }
+    // The visibility of the items used in the moved item
+    let visibility_module = match target {
+        Target::Existing(target) => target,
+        Target::New(parent, _) => parent
+    };
+    let mut used = vec![];
+    for reference in references.iter().filter(|r| is_in_item(r.span) && r.def_id.is_local() && !tcx.0.is_descendant_of(r.def_id, def_id)) {
+        if used.contains(&reference.def_id) {
+            continue;
+        }
+        used.push(reference.def_id);
+        if let Some((vis, hir_id)) = get_visibility(tcx.0, reference.def_id) {
+            if let DefVisibility::Restricted(visible_in) = DefVisibility::from_hir(vis, hir_id, tcx.0) {
+                if !tcx.0.is_descendant_of(visibility_module, visible_in) {
+                    replacements.push(make_visible(vis));
+                }
+            }
+        }
+    }
+
+    if needs_use {
+        let use_ = format!("use {}::{};", get_relative_path(&target_path, &get_absolute_path(tcx.0, module)), item.ident);
+        replacements.push(insert_use(tcx, module, def_id, &use_));
+    }
+
+    // Move the item
+    let indent = tcx.source().get_indent(item_span);
+    let source = get_source_with_replacements(tcx.0, item_span, &replacements);
+    let reindent = |new_indent: &str| source.lines().enumerate()
+        .map(|(i, line)| match if line.starts_with(&indent as &str) { &line[indent.len()..] } else { line.trim_start() } {
+            "" => "".to_owned(),
+            line if i == 0 => line.to_string(),
+            line => format!("{}{}", new_indent, line)
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    let removed = get_removed_span(tcx, item_span);
+    let mut new_files = vec![];
+    match &target {
+        Target::Existing(target) => replacements.push(insert_item(tcx, *target, def_id, &reindent)),
+        Target::New(parent, name) => {
+            let is_used_outside_parent = moved.iter().any(|(def_id, _)| references.iter()
+                .any(|r| r.def_id == *def_id && !is_in_item(r.span) && !tcx.0.is_descendant_of(tcx.0.parent_module(r.hir_id).to_def_id(), *parent)));
+            let vis = match item.vis.node {
+                VisibilityKind::Public => "pub ",
+                _ if is_used_outside_parent => "pub(crate) ",
+                _ => ""
+            };
+            let file_name = get_new_file_name(tcx, *parent, name)?;
+            new_files.push(FileStringReplacement {
+                file_name,
+                line_start: 0,
+                char_start: 0,
+                line_end: 0,
+                char_end: 0,
+                byte_start: 0,
+                byte_end: 0,
+                replacement: format!("{}\n", reindent(""))
+            });
+            let declaration = format!("{}mod {};", vis, name);
+            match insert_module_declaration(tcx, *parent, def_id, &declaration) {
+                Some(replacement) => replacements.push(replacement),
+                // The module only contains the item
+                None => {
+                    replacements.push((removed, format!("{}\n", declaration)));
+                    return get_changes(tcx, replacements, new_files);
+                }
+            }
+        }
+    }
+    replacements.push((removed, "".to_owned()));
+
+    get_changes(tcx, replacements, new_files)
+}
+
+fn get_changes(tcx: &TyContext, replacements: Vec<(Span, String)>, new_files: Vec<FileStringReplacement>) -> QueryResult<AstDiff> {
+    let mut changes = vec![];
+    for (i, (span, replacement)) in replacements.iter().enumerate() {
+        // An insertion after a replaced span (e.g. after the removed item) is not contained in it
+        let is_contained = replacements.iter()
+            .any(|(other, _)| other != span && other.contains(*span) && other.lo() != other.hi() && !(span.lo() == span.hi() && span.lo() == other.hi()));
+        if !is_contained && !replacements[..i].contains(&(*span, replacement.to_string())) {
+            changes.push(tcx.map_change(*span, replacement.to_string())?);
+        }
+    }
+    changes.extend(new_files);
+    Ok(AstDiff(changes))
+}
+
+fn get_target(tcx: TyCtxt, segments: &[&str]) -> QueryResult<Target> {
+    let segments = if segments.first() == Some(&"crate") { &segments[1..] } else { segments };
+    let mut module = DefId::local(CRATE_DEF_INDEX);
+    for (i, segment) in segments.iter().enumerate() {
+        let child = get_items(tcx, module).iter()
+            .find(|item| matches!(item.kind, ItemKind::Mod(..)) && format!("{}", item.ident) == *segment)
+            .map(|item| tcx.hir().local_def_id(item.hir_id));
+        match child {
+            Some(child) => module = child,
+            None if i == segments.len() - 1 => return Ok(Target::New(module, segment.to_string())),
+            None => return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, &format!("the module {} does not exist", segments[..=i].join("::"))))
+        }
+    }
+    Ok(Target::Existing(module))
+}
+
+///
+/// The items declared in the module, without those added by the compiler
+///
+fn get_items<'v>(tcx: TyCtxt<'v>, module: DefId) -> Vec<&'v Item<'v>> {
+    let (module, ..) = tcx.hir().get_module(module);
+    module.item_ids.iter()
+        .map(|item_id| tcx.hir().item(item_id.id))
+        .filter(|item| !item.span.from_expansion() && !item.span.is_dummy())
+        .collect()
+}
+
+///
+/// The lines of the item, and an empty line after it if it is between empty lines
+///
+fn get_removed_span(tcx: &TyContext, item_span: Span) -> Span {
+    let span = tcx.source().get_lines(item_span);
+    let source_map = tcx.0.sess.source_map();
+    let prev = source_map.span_to_prev_source(span).unwrap_or_default();
+    let next = source_map.span_to_snippet(span.shrink_to_hi().with_hi(span.hi() + BytePos(1))).unwrap_or_default();
+    if next == "\n" && (prev.is_empty() || prev.ends_with("\n\n")) {
+        span.with_hi(span.hi() + BytePos(1))
+    } else {
+        span
+    }
+}
+
+///
+/// The resolution of the segment, where the last segment is resolved by the path
+///
+fn get_segment_res(path: &Path, i: usize) -> Option<Res> {
+    if i + 1 == path.segments.len() {
+        Some(path.res)
+    } else {
+        path.segments[i].res
+    }
+}
+
+///
+/// Replaces the path to the module in a path to the item, e.g. `m::I` with `T::I`
+///
+fn rewrite_path_to_item(tcx: &TyContext, path: &Path, hir_id: HirId, def_id: DefId, module: DefId, target_path: &str) -> QueryResult<Vec<(Span, String)>> {
+    if path.span.from_expansion() {
+        return Ok(vec![]);
+    }
+    let index = match (0..path.segments.len()).find(|i| is_item_res(tcx.0, get_segment_res(path, *i), def_id)) {
+        Some(index) if index > 0 => index,
+        _ => return Ok(vec![])
+    };
+    let use_item = match tcx.0.hir().find(hir_id) {
+        Some(Node::Item(item @ Item { kind: ItemKind::Use(_, UseKind::Single), .. })) => Some(item),
+        _ => None
+    };
+    let prev = &path.segments[index - 1];
+    let is_in_module = match get_segment_res(path, index - 1) {
+        Some(Res::Def(DefKind::Mod, prev)) => prev == module,
+        // The prefix of an import in braces, e.g. `m` in `use m::{I, J};`, is not resolved
+        Some(Res::Err) if use_item.is_some() => prev.ident.is_path_segment_keyword() || prev.ident.name == tcx.0.item_name(module),
+        _ => false
+    };
+    if !is_in_module {
+        return Ok(vec![]);
+    }
+    let module_path = get_absolute_path(tcx.0, tcx.0.parent_module(hir_id).to_def_id());
+    let new_path = get_relative_path(target_path, &module_path);
+    let first = &path.segments[0];
+    let lo = if first.ident.name == kw::PathRoot { path.span.lo() } else { first.ident.span.lo() };
+    let prefix = prev.ident.span.with_lo(lo);
+    let segment = path.segments[index].ident.span;
+
+    let use_item = match use_item {
+        Some(use_item) if tcx.get_source(prefix.with_hi(segment.lo())).contains('{') => use_item,
+        _ => return Ok(vec![(prefix, new_path)])
+    };
+    // `use m::{I, J};` becomes `use m::J;` and `use T::I;`
+    let statement = get_items(tcx.0, tcx.0.parent_module(hir_id).to_def_id()).into_iter()
+        .filter(|item| matches!(item.kind, ItemKind::Use(_, UseKind::ListStem)) && item.span.contains(segment))
+        .map(|item| item.span)
+        .max_by_key(|span| span.hi() - span.lo())
+        .unwrap_or(use_item.span);
+    let name = if use_item.ident.name != path.segments[index].ident.name { segment.to(use_item.ident.span) } else { segment };
+    let after = tcx.get_source(name.between(statement.shrink_to_hi()));
+    let before = tcx.get_source(statement.shrink_to_lo().to(name.shrink_to_lo()));
+    let removed = if after.trim_start().starts_with(',') {
+        let comma = after.find(',').unwrap() + 1;
+        let whitespace = after[comma..].len() - after[comma..].trim_start().len();
+        name.with_hi(name.hi() + BytePos((comma + whitespace) as u32))
+    } else if before.trim_end().ends_with(',') {
+        name.with_lo(statement.lo() + BytePos(before.trim_end().len() as u32 - 1))
+    } else {
+        name
+    };
+    let vis = tcx.get_source(use_item.vis.span);
+    let use_ = format!("\n{}{}{}use {}::{};", tcx.source().get_indent(statement), vis, if vis.is_empty() { "" } else { " " }, new_path, tcx.get_source(name));
+    Ok(vec![(removed, "".to_owned()), (statement.shrink_to_hi(), use_)])
+}
+
+fn is_item_res(tcx: TyCtxt, res: Option<Res>, def_id: DefId) -> bool {
+    match res {
+        Some(Res::Def(DefKind::Ctor(..), ctor)) => tcx.parent(ctor) == Some(def_id),
+        Some(Res::Def(_, id)) => id == def_id,
+        _ => false
+    }
+}
+
+///
+/// Replaces the start of a path in the moved item, which is relative to the module, with a path which is valid in the target
+///
+fn rewrite_path_in_item(tcx: &TyContext, path: &Path, is_import: bool, def_id: DefId, module: DefId, target_path: &str) -> Option<(Span, String)> {
+    let first = path.segments.first()?;
+    match first.ident.name {
+        kw::Crate | kw::PathRoot | kw::DollarCrate | kw::SelfUpper => return None,
+        kw::SelfLower | kw::Super => {},
+        _ => {
+            let referenced = match get_segment_res(path, 0)? {
+                Res::Def(DefKind::TyParam, _) | Res::Def(DefKind::ConstParam, _) => return None,
+                Res::Def(_, referenced) => referenced,
+                _ => return None
+            };
+            if (referenced.is_local() && tcx.0.is_descendant_of(referenced, def_id)) || referenced.index == CRATE_DEF_INDEX {
+                return None;
+            }
+            let is_declared_in_module = referenced.is_local() && tcx.0.parent(referenced) == Some(module);
+            let is_imported_in_module = get_items(tcx.0, module).iter().any(|item| match &item.kind {
+                ItemKind::Use(use_path, UseKind::Single) => item.ident.name == first.ident.name && use_path.res.opt_def_id() == Some(referenced),
+                _ => false
+            });
+            if !is_declared_in_module && !is_imported_in_module {
+                return None;
+            }
+        }
+    }
+
+    // The modules at the start of the path, e.g. `super::a` in `super::a::f`, where `self` and `super` are not resolved
+    let mut modules = None;
+    for i in 0..path.segments.len() - 1 {
+        let next = match path.segments[i].ident.name {
+            kw::SelfLower if i == 0 => Some(module),
+            kw::Super => tcx.0.parent(modules.map_or(module, |(_, module)| module)),
+            _ => match get_segment_res(path, i) {
+                Some(Res::Def(DefKind::Mod, module)) => Some(module),
+                _ => None
+            }
+        };
+        match next {
+            Some(next) => modules = Some((i, next)),
+            None => break
+        }
+    }
+    match modules {
+        Some((last, referenced)) => {
+            let prefix = path.segments[last].ident.span.with_lo(first.ident.span.lo());
+            match get_relative_path(&get_absolute_path(tcx.0, referenced), target_path) {
+                // `T::f` becomes `f`
+                relative if relative == "self" && !is_import => Some((prefix.until(path.segments[last + 1].ident.span), "".to_owned())),
+                relative => Some((prefix, relative))
+            }
+        },
+        None if first.ident.is_path_segment_keyword() => None,
+        None => {
+            let referenced = get_segment_res(path, 0)?.opt_def_id()?;
+            Some((first.ident.span, get_relative_path(&get_absolute_path(tcx.0, referenced), target_path)))
+        }
+    }
+}
+
+///
+/// The visibility of an item, a field of a struct or an item of an inherent impl, which are the definitions with a visibility
+///
+fn get_visibility<'v>(tcx: TyCtxt<'v>, def_id: DefId) -> Option<(&'v Visibility<'v>, HirId)> {
+    let hir = tcx.hir();
+    let hir_id = hir.as_local_hir_id(def_id)?;
+    let vis = match hir.get(hir_id) {
+        Node::Item(item) => &item.vis,
+        Node::Field(StructField { vis, .. }) => match hir.get(hir.get_parent_item(hir_id)) {
+            Node::Item(Item { kind: ItemKind::Struct(..), .. }) => vis,
+            _ => return None
+        },
+        Node::ImplItem(ImplItem { vis, .. }) => match hir.get(hir.get_parent_item(hir_id)) {
+            Node::Item(Item { kind: ItemKind::Impl { of_trait: None, .. }, .. }) => vis,
+            _ => return None
+        },
+        _ => return None
+    };
+    Some((vis, hir_id))
+}
+
+fn make_visible(vis: &Visibility) -> (Span, String) {
+    match vis.node {
+        VisibilityKind::Inherited => (vis.span.shrink_to_lo(), "pub(crate) ".to_owned()),
+        _ => (vis.span, "pub(crate)".to_owned())
+    }
+}
+
+///
+/// Adds `use_` after the last `use` in the module, or before its first item
+///
+fn insert_use(tcx: &TyContext, module: DefId, def_id: DefId, use_: &str) -> (Span, String) {
+    let items = get_items(tcx.0, module).into_iter()
+        .filter(|item| tcx.0.hir().local_def_id(item.hir_id) != def_id)
+        .collect::<Vec<_>>();
+    match items.iter().filter(|item| matches!(item.kind, ItemKind::Use(..))).max_by_key(|item| item.span.hi()) {
+        Some(last_use) => (last_use.span.shrink_to_hi(), format!("\n{}{}", tcx.source().get_indent(last_use.span), use_)),
+        None => {
+            let first = items.iter().map(|item| get_item_span(tcx.0, item.attrs, item.span)).min_by_key(|span| span.lo()).unwrap();
+            (first.shrink_to_lo(), format!("{}\n\n{}", use_, tcx.source().get_indent(first)))
+        }
+    }
+}
+
+///
+/// Adds the declaration of a new module after the last module declared in `module`, or after its `use` declarations, or before its first item.
+/// None if `module` only contains the moved item
+///
+fn insert_module_declaration(tcx: &TyContext, module: DefId, def_id: DefId, declaration: &str) -> Option<(Span, String)> {
+    let items = get_items(tcx.0, module).into_iter()
+        .filter(|item| tcx.0.hir().local_def_id(item.hir_id) != def_id)
+        .collect::<Vec<_>>();
+    let last_of = |is_kind: fn(&ItemKind) -> bool| items.iter().filter(|item| is_kind(&item.kind)).max_by_key(|item| item.span.hi()).cloned();
+    if let Some(last) = last_of(|kind| matches!(kind, ItemKind::Mod(..))) {
+        return Some((last.span.shrink_to_hi(), format!("\n{}{}", tcx.source().get_indent(last.span), declaration)));
+    }
+    if let Some(last) = last_of(|kind| matches!(kind, ItemKind::Use(..))) {
+        return Some((last.span.shrink_to_hi(), format!("\n\n{}{}", tcx.source().get_indent(last.span), declaration)));
+    }
+    let first = items.iter().map(|item| get_item_span(tcx.0, item.attrs, item.span)).min_by_key(|span| span.lo())?;
+    Some((first.shrink_to_lo(), format!("{}\n\n{}", declaration, tcx.source().get_indent(first))))
+}
+
+///
+/// Adds the moved item after the last item of the target module, where `reindent` returns the source of the item with the given indentation
+///
+fn insert_item(tcx: &TyContext, target: DefId, def_id: DefId, reindent: &dyn Fn(&str) -> String) -> (Span, String) {
+    let hir = tcx.0.hir();
+    let items = get_items(tcx.0, target).into_iter()
+        .filter(|item| hir.local_def_id(item.hir_id) != def_id)
+        .collect::<Vec<_>>();
+    if let Some(last) = items.iter().max_by_key(|item| item.span.hi()) {
+        let indent = tcx.source().get_indent(last.span);
+        return (last.span.shrink_to_hi(), format!("\n\n{}{}", indent, reindent(&indent)));
+    }
+    let (module, _, hir_id) = hir.get_module(target);
+    match hir.find(hir_id) {
+        // An inline module, e.g. `mod m {}`
+        Some(Node::Item(item)) if item.span.contains(module.inner) => {
+            let indent = tcx.source().get_indent(item.span);
+            let open = tcx.get_source(item.span).find('{').unwrap_or_default();
+            let inner = item.span.with_lo(item.span.lo() + BytePos(open as u32 + 1)).with_hi(item.span.hi() - BytePos(1));
+            (inner, format!("\n{0}    {1}\n{0}", indent, reindent(&format!("{}    ", indent))))
+        },
+        _ if module.inner.lo() == module.inner.hi() => (module.inner, format!("{}\n", reindent(""))),
+        _ => (module.inner.shrink_to_hi(), format!("\n{}\n", reindent("")))
+    }
+}
+
+///
+/// The file of a new module declared in `parent`, e.g. `src/a/b.rs` for `b` in `src/a.rs` or `src/a/mod.rs`
+///
+fn get_new_file_name(tcx: &TyContext, parent: DefId, name: &str) -> QueryResult<String> {
+    let hir = tcx.0.hir();
+    let (module, _, hir_id) = hir.get_module(parent);
+    if let Some(Node::Item(item)) = hir.find(hir_id) {
+        if item.span.contains(module.inner) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, "a module cannot be created in an inline module"));
+        }
+    }
+    let file = match tcx.0.sess.source_map().span_to_filename(module.inner) {
+        FileName::Real(file) => file,
+        file => return Err(RefactoringErrorInternal::int(&format!("unexpected file type: {:?}", file)))
+    };
+    let dir = file.parent().unwrap_or_else(|| FilePath::new(""));
+    let is_mod_rs = parent.index == CRATE_DEF_INDEX || file.file_stem().map_or(false, |stem| stem == "mod");
+    let new_file = if is_mod_rs {
+        dir.join(format!("{}.rs", name))
+    } else {
+        dir.join(file.file_stem().unwrap()).join(format!("{}.rs", name))
+    };
+    if new_file.exists() || new_file.with_extension("").join("mod.rs").exists() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_MOVE, &format!("the file {} already exists", new_file.display())));
+    }
+    Ok(new_file.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test_utils::{run_refactoring, TestInit};
+    const NAME: &str = "move-item";
+
+    #[test]
+    fn relative_paths() {
+        let input = r#"mod a {
+    fn helper() -> i32 { 1 }
+    pub mod b {
+        /*refactor-tool:test-id:start*/pub fn f() -> i32 { super::helper() + self::g() }/*refactor-tool:test-id:end*/
+        pub fn g() -> i32 { 2 }
+    }
+}
+mod c {
+}
+fn main() {
+    a::b::f();
+}"#;
+        let expected = Ok(r#"mod a {
+    pub(crate) fn helper() -> i32 { 1 }
+    pub mod b {
+        /*refactor-tool:test-id:start*//*refactor-tool:test-id:end*/
+        pub fn g() -> i32 { 2 }
+    }
+}
+mod c {
+    pub fn f() -> i32 { crate::a::helper() + crate::a::b::g() }
+}
+fn main() {
+    c::f();
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("to", "c"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn import_in_braces() {
+        let input = r#"mod a {
+    /*refactor-tool:test-id:start*/pub struct S;/*refactor-tool:test-id:end*/
+    pub struct T;
+}
+mod b {
+    pub fn f() {}
+}
+use a::{S as U, T};
+fn main() {
+    let _ = (U, T);
+    b::f();
+}"#;
+        let expected = Ok(r#"mod a {
+    /*refactor-tool:test-id:start*//*refactor-tool:test-id:end*/
+    pub struct T;
+}
+mod b {
+    pub fn f() {}
+
+    pub struct S;
+}
+use a::{T};
+use b::S as U;
+fn main() {
+    let _ = (U, T);
+    b::f();
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("to", "crate::b"));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,127 @@
+use rustc_hir::{Body, BodyId, Expr, ExprKind, HirId, Pat, PatKind, Path, QPath};
+use rustc_hir::def::{CtorOf, DefKind, Res};
+use rustc_hir::def_id::DefId;
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_body, walk_crate, walk_expr, walk_pat, walk_path, walk_qpath};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::{DefIdTree, TyCtxt, TypeckTables};
+use rustc_span::Span;
+
+/// A use of an item, a field or an associated item
+pub struct Reference {
+    pub def_id: DefId,
+    pub hir_id: HirId,
+    pub span: Span
+}
+
+///
+/// Collects the references to items, fields and associated items in the crate, and the paths (with the HirId of their owner node)
+///
+pub fn collect_references(tcx: TyCtxt<'_>) -> (Vec<Reference>, Vec<(&Path<'_>, HirId)>) {
+    let mut v = ReferenceCollector {
+        tcx,
+        bodies: vec![],
+        references: vec![],
+        paths: vec![]
+    };
+
+    walk_crate(&mut v, tcx.hir().krate());
+
+    (v.references, v.paths)
+}
+
+struct ReferenceCollector<'v> {
+    tcx: TyCtxt<'v>,
+    bodies: Vec<BodyId>,
+    references: Vec<Reference>,
+    paths: Vec<(&'v Path<'v>, HirId)>
+}
+
+impl<'v> ReferenceCollector<'v> {
+    fn tables(&self) -> Option<&'v TypeckTables<'v>> {
+        self.bodies.last().map(|body_id| self.tcx.body_tables(*body_id))
+    }
+    fn push(&mut self, res: Res, hir_id: HirId, span: Span) {
+        if let Res::Def(kind, def_id) = res {
+            self.references.push(Reference { def_id, hir_id, span });
+            // The constructor of a tuple struct is only visible where all its fields are
+            if let DefKind::Ctor(CtorOf::Struct, _) = kind {
+                let struct_def_id = self.tcx.parent(def_id).unwrap();
+                for field in &self.tcx.adt_def(struct_def_id).non_enum_variant().fields {
+                    self.references.push(Reference { def_id: field.did, hir_id, span });
+                }
+            }
+        }
+    }
+    fn push_field(&mut self, tables: &TypeckTables<'v>, res: Res, adt_hir_id: HirId, field_hir_id: HirId, span: Span) {
+        if let Some(adt_def) = tables.node_type(adt_hir_id).ty_adt_def() {
+            let field_indices = tables.field_indices();
+            if let Some(field) = field_indices.get(field_hir_id).and_then(|index| adt_def.variant_of_res(res).fields.get(*index)) {
+                self.references.push(Reference { def_id: field.did, hir_id: field_hir_id, span });
+            }
+        }
+    }
+}
+
+impl<'v> Visitor<'v> for ReferenceCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_body(&mut self, body: &'v Body<'v>) {
+        self.bodies.push(body.id());
+        walk_body(self, body);
+        self.bodies.pop();
+    }
+    fn visit_path(&mut self, path: &'v Path<'v>, hir_id: HirId) {
+        self.paths.push((path, hir_id));
+        self.push(path.res, hir_id, path.span);
+        for segment in path.segments.iter().rev().skip(1) {
+            if let Some(res) = segment.res {
+                self.push(res, hir_id, segment.ident.span);
+            }
+        }
+        walk_path(self, path);
+    }
+    fn visit_qpath(&mut self, qpath: &'v QPath<'v>, hir_id: HirId, span: Span) {
+        if let (QPath::TypeRelative(..), Some(tables)) = (qpath, self.tables()) {
+            self.push(tables.qpath_res(qpath, hir_id), hir_id, span);
+        }
+        walk_qpath(self, qpath, hir_id, span);
+    }
+    fn visit_expr(&mut self, expr: &'v Expr<'v>) {
+        if let Some(tables) = self.tables() {
+            match &expr.kind {
+                ExprKind::MethodCall(segment, ..) => {
+                    if let Some(def_id) = tables.type_dependent_def_id(expr.hir_id) {
+                        self.references.push(Reference { def_id, hir_id: expr.hir_id, span: segment.ident.span });
+                    }
+                },
+                ExprKind::Field(base, ident) => {
+                    if let Some(adt_def) = tables.expr_ty_adjusted(base).ty_adt_def().filter(|adt_def| !adt_def.is_enum()) {
+                        let field_indices = tables.field_indices();
+                        if let Some(field) = field_indices.get(expr.hir_id).and_then(|index| adt_def.non_enum_variant().fields.get(*index)) {
+                            self.references.push(Reference { def_id: field.did, hir_id: expr.hir_id, span: ident.span });
+                        }
+                    }
+                },
+                ExprKind::Struct(qpath, fields, _) => {
+                    let res = tables.qpath_res(qpath, expr.hir_id);
+                    for field in fields.iter() {
+                        self.push_field(tables, res, expr.hir_id, field.hir_id, field.ident.span);
+                    }
+                },
+                _ => {}
+            }
+        }
+        walk_expr(self, expr);
+    }
+    fn visit_pat(&mut self, pat: &'v Pat<'v>) {
+        if let (PatKind::Struct(qpath, fields, _), Some(tables)) = (&pat.kind, self.tables()) {
+            let res = tables.qpath_res(qpath, pat.hir_id);
+            for field in fields.iter() {
+                self.push_field(tables, res, pat.hir_id, field.hir_id, field.ident.span);
+            }
+        }
+        walk_pat(self, pat);
+    }
+}
@@ -0,0 +1,286 @@
+use rustc_hir::{HirId, Item, ItemKind, Node, VariantData};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::AssocItemContainer;
+use rustc_span::{FileName, Span, symbol::Ident};
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::utils::is_identifier;
+use occurrence_collector::{collect_occurrences, Occurrence, ShorthandField, Target};
+
+mod occurrence_collector;
+
+const CANNOT_RENAME: &str = "The symbol cannot be renamed";
+
+/// Rename refactoring
+///
+/// ## Algorithm
+///
+/// Steps
+/// - Os <- All identifiers in the crate, and what they resolve to (a local variable or an item)
+/// - T <- what the selected identifier resolves to
+/// - N <- the new name
+/// - if renaming T to N could change what any identifier resolves to then abort:
+///   - T is a local variable and N is already used in the same function, or N is a constant, static, unit struct or import in the same module
+///   - T is an item and an item named N is already defined in the same scope (module, impl, trait, enum, struct or generics)
+///   - T is referred to by a path with a single segment in a function where N is defined
+///   - N is referred to by a path with a single segment in the module where T is defined
+/// - for O in Os where O resolves to T: replace O with N
+/// - for shorthand fields `x` which are initialized with or bound to T: add `x: ` before N
+/// - for shorthand fields where the field is T: add `N: ` before the field
+///
+/// When the selection is in a source file of another (local) crate, the identifiers which refer to the item defined at the selection are renamed.
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, new_name: &str) -> QueryResult<AstDiff> {
+    check_name(new_name)?;
+    let (occurrences, shorthands) = collect_occurrences(tcx.0);
+
+    let target = if tcx.0.sess.source_map().lookup_char_pos(span.lo()).file.is_imported() {
+        match get_extern_target(tcx, &occurrences, span) {
+            Some(target) => target,
+            None => return Ok(AstDiff(vec![]))
+        }
+    } else {
+        let occurrence = occurrences.iter()
+            .find(|o| o.span.contains(span))
+            .ok_or_else(|| tcx.source().span_err(span, false))?;
+        if let Target::Def(def_id) = occurrence.target {
+            if !def_id.is_local() {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, "the item is defined in another crate, select its definition instead"));
+            }
+        }
+        occurrence.target
+    };
+
+    let old_name = match target {
+        Target::Local(hir_id) => tcx.0.hir().name(hir_id).to_string(),
+        Target::Def(def_id) => tcx.0.item_name(def_id).to_string()
+    };
+    if Ident::from_str(&old_name).is_reserved() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("{} cannot be renamed", old_name)));
+    }
+    if old_name == new_name {
+        return Ok(AstDiff(vec![]));
+    }
+    let renamed = occurrences.iter()
+        .filter(|o| o.target == target && tcx.get_source(o.span) == old_name)
+        .collect::<Vec<_>>();
+    check_no_collisions(tcx, target, &occurrences, &renamed, new_name)?;
+
+    let mut changes = vec![];
+    for occurrence in renamed {
+        let replacement = match (target, shorthands.iter().find(|s| s.ident_span == occurrence.span)) {
+            (Target::Local(_), Some(ShorthandField { start, .. })) if *start == occurrence.span.shrink_to_lo() => format!("{}: {}", old_name, new_name),
+            (Target::Local(_), Some(ShorthandField { start, .. })) => {
+                changes.push(tcx.map_change(*start, format!("{}: ", old_name))?);
+                new_name.to_owned()
+            },
+            _ => new_name.to_owned()
+        };
+        changes.push(tcx.map_change(occurrence.span, replacement)?);
+    }
+    if let Target::Def(def_id) = target {
+        for shorthand in shorthands.iter().filter(|s| s.field == def_id) {
+            changes.push(tcx.map_change(shorthand.start, format!("{}: ", new_name))?);
+        }
+    }
+
+    Ok(AstDiff(changes))
+}
+
+fn check_name(name: &str) -> QueryResult<()> {
+    if !is_identifier(name) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("{} is not a valid identifier", name)));
+    }
+    Ok(())
+}
+
+///
+/// The item from another crate which is defined at `span`, and which is referred to in this crate
+///
+fn get_extern_target(tcx: &TyContext, occurrences: &[Occurrence], span: Span) -> Option<Target> {
+    let name = get_imported_ident(tcx, span)?;
+    occurrences.iter()
+        .filter_map(|o| match o.target {
+            Target::Def(def_id) if !def_id.is_local() => Some((def_id, tcx.0.def_span(def_id))),
+            _ => None
+        })
+        .filter(|(def_id, def_span)| def_span.contains(span)
+            && tcx.0.def_key(*def_id).disambiguated_data.data.get_opt_name().map_or(false, |n| n.as_str() == name))
+        .min_by_key(|(_, def_span)| def_span.hi() - def_span.lo())
+        .map(|(def_id, _)| Target::Def(def_id))
+}
+
+///
+/// The identifier at `span` in a source file of another crate, which is read from disk as the source is not loaded
+///
+fn get_imported_ident(tcx: &TyContext, span: Span) -> Option<String> {
+    let file = tcx.0.sess.source_map().lookup_char_pos(span.lo()).file;
+    let src = match &file.name {
+        FileName::Real(path) => std::fs::read_to_string(path).ok()?,
+        _ => return None
+    };
+    let (lo, hi) = ((span.lo() - file.start_pos).0 as usize, (span.hi() - file.start_pos).0 as usize);
+    let is_ident_char = |c: char| c.is_alphanumeric() || c == '_';
+    let start = src.get(..lo)?.rfind(|c| !is_ident_char(c)).map_or(0, |i| i + 1);
+    let end = src.get(hi..)?.find(|c| !is_ident_char(c)).map_or(src.len(), |i| hi + i);
+    Some(src[start..end].to_string())
+}
+
+fn check_no_collisions(tcx: &TyContext, target: Target, occurrences: &[Occurrence], renamed: &[&Occurrence], new_name: &str) -> QueryResult<()> {
+    let hir = tcx.0.hir();
+    let with_new_name = occurrences.iter()
+        .filter(|o| tcx.get_source(o.span) == new_name)
+        .collect::<Vec<_>>();
+
+    match target {
+        Target::Local(hir_id) => {
+            let owner = hir.get_parent_item(hir_id);
+            if with_new_name.iter().any(|o| hir.get_parent_item(o.hir_id) == owner) {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("the name {} is already used in the function", new_name)));
+            }
+            let module = tcx.0.parent_module(hir_id);
+            if hir.krate().items.values().any(|item| item.ident.as_str() == new_name && tcx.0.parent_module(item.hir_id) == module && can_be_matched(item)) {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("an item named {} is defined in the same module", new_name)));
+            }
+        },
+        Target::Def(def_id) => {
+            if let Some(hir_id) = hir.as_local_hir_id(def_id) {
+                if let Node::Item(Item { kind: ItemKind::Mod(module), span, .. }) = hir.get(hir_id) {
+                    if !span.contains(module.inner) {
+                        return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, "the module is defined in a separate file"));
+                    }
+                }
+                if get_names_in_scope(tcx, def_id, hir_id).iter().any(|name| name == new_name) {
+                    return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("an item named {} is already defined in the same scope", new_name)));
+                }
+                let module = tcx.0.parent_module(hir_id);
+                if with_new_name.iter().any(|o| o.is_unqualified && matches!(o.target, Target::Def(_)) && tcx.0.parent_module(o.hir_id) == module) {
+                    return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("the name {} is already used in the module", new_name)));
+                }
+            }
+            let owners = with_new_name.iter()
+                .filter(|o| o.is_definition)
+                .map(|o| hir.get_parent_item(o.hir_id))
+                .collect::<Vec<_>>();
+            if renamed.iter().any(|o| o.is_unqualified && owners.contains(&hir.get_parent_item(o.hir_id))) {
+                return Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, &format!("the item would be shadowed by another item or variable named {}", new_name)));
+            }
+        }
+    }
+    Ok(())
+}
+
+///
+/// Whether an identifier pattern with the same name as the item would refer to the item, instead of binding a new variable
+///
+fn can_be_matched(item: &Item) -> bool {
+    matches!(item.kind, ItemKind::Const(..) | ItemKind::Static(..) | ItemKind::Use(..) | ItemKind::Struct(VariantData::Unit(..), _))
+}
+
+///
+/// The names of the items which are defined in the same scope as the item `def_id`
+///
+fn get_names_in_scope(tcx: &TyContext, def_id: DefId, hir_id: HirId) -> Vec<String> {
+    let hir = tcx.0.hir();
+    if let Some(item) = tcx.0.opt_associated_item(def_id) {
+        let containers = match item.container {
+            AssocItemContainer::TraitContainer(trait_def_id) => vec![trait_def_id],
+            AssocItemContainer::ImplContainer(impl_def_id) => match tcx.0.type_of(impl_def_id).ty_adt_def() {
+                Some(adt_def) if tcx.0.impl_trait_ref(impl_def_id).is_none() => tcx.0.inherent_impls(adt_def.did).to_vec(),
+                _ => vec![impl_def_id]
+            }
+        };
+        return containers.iter()
+            .flat_map(|container| tcx.0.associated_item_def_ids(*container).iter())
+            .map(|id| tcx.0.item_name(*id).to_string())
+            .collect();
+    }
+    match hir.get(hir_id) {
+        Node::Field(..) => {
+            let fields = match hir.get(hir.get_parent_node(hir_id)) {
+                Node::Item(Item { kind: ItemKind::Struct(data, _), .. }) => data.fields(),
+                Node::Item(Item { kind: ItemKind::Union(data, _), .. }) => data.fields(),
+                Node::Variant(variant) => variant.data.fields(),
+                _ => &[]
+            };
+            fields.iter().map(|f| f.ident.to_string()).collect()
+        },
+        Node::Variant(..) => match hir.get(hir.get_parent_node(hir_id)) {
+            Node::Item(Item { kind: ItemKind::Enum(def, _), .. }) => def.variants.iter().map(|v| v.ident.to_string()).collect(),
+            _ => vec![]
+        },
+        Node::GenericParam(..) => {
+            let mut generics = Some(tcx.0.generics_of(hir.local_def_id(hir.get_parent_item(hir_id))));
+            let mut names = vec![];
+            while let Some(g) = generics {
+                names.extend(g.params.iter().map(|p| p.name.to_string()));
+                generics = g.parent.map(|parent| tcx.0.generics_of(parent));
+            }
+            names
+        },
+        _ => {
+            let parent = hir.get_parent_item(hir_id);
+            hir.krate().items.values()
+                .filter(|item| item.hir_id != hir_id && hir.get_parent_item(item.hir_id) == parent)
+                .map(|item| item.ident.to_string())
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_RENAME;
+    const NAME: &str = "rename";
+
+    #[test]
+    fn local_with_shorthand_field() {
+        let input = r#"struct S { x: i32 }
+fn foo() -> i32 {
+    let /*refactor-tool:test-id:start*/x/*refactor-tool:test-id:end*/ = 1;
+    let s = S { x };
+    let S { x } = s;
+    x
+}"#;
+        let expected = Ok(r#"struct S { x: i32 }
+fn foo() -> i32 {
+    let /*refactor-tool:test-id:start*/y/*refactor-tool:test-id:end*/ = 1;
+    let s = S { x: y };
+    let S { x } = s;
+    x
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("name", "y"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn trait_method() {
+        let input = r#"trait T { fn /*refactor-tool:test-id:start*/foo/*refactor-tool:test-id:end*/(&self) -> i32; }
+struct S;
+impl T for S { fn foo(&self) -> i32 { 1 } }
+fn bar<U: T>(u: U) -> i32 {
+    u.foo() + S.foo() + T::foo(&S)
+}"#;
+        let expected = Ok(r#"trait T { fn /*refactor-tool:test-id:start*/baz/*refactor-tool:test-id:end*/(&self) -> i32; }
+struct S;
+impl T for S { fn baz(&self) -> i32 { 1 } }
+fn bar<U: T>(u: U) -> i32 {
+    u.baz() + S.baz() + T::baz(&S)
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("name", "baz"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn shadowed_by_local() {
+        let input = r#"fn /*refactor-tool:test-id:start*/foo/*refactor-tool:test-id:end*/() -> i32 { 1 }
+fn bar() -> i32 {
+    let baz = 2;
+    foo() + baz
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_RENAME, "the item would be shadowed by another item or variable named baz"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("name", "baz"));
+        assert_eq!(actual, expected);
+    }
+}
@@ -0,0 +1,230 @@
+use rustc_hir::{Body, BodyId, Expr, ExprKind, ForeignItem, GenericParam, GenericParamKind, Generics, HirId, ImplItem, Item, ItemKind, ParamName, Pat, PatKind, Path, QPath, StructField, TraitItem, Variant};
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::DefId;
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_body, walk_crate, walk_expr, walk_foreign_item, walk_generic_param, walk_impl_item, walk_item, walk_pat, walk_path, walk_qpath, walk_struct_field, walk_trait_item, walk_variant};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::{AssocItemContainer, DefIdTree, TyCtxt, TypeckTables};
+use rustc_span::{Span, symbol::Ident};
+
+///
+/// What an identifier resolves to
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Target {
+    Local(HirId),
+    Def(DefId)
+}
+
+///
+/// An identifier which resolves to `target`, either where it is defined (e.g. the name of a function) or where it is referred to
+///
+#[derive(Debug)]
+pub struct Occurrence {
+    pub span: Span,
+    pub target: Target,
+    pub hir_id: HirId,
+    pub is_definition: bool,
+    /// A path with a single segment, which is resolved by looking up the name in the scope
+    pub is_unqualified: bool
+}
+
+///
+/// A shorthand field in a struct expression or pattern, e.g. `S { x }`, where `start` is the position before the field (`ref`/`mut` included)
+///
+#[derive(Debug)]
+pub struct ShorthandField {
+    pub ident_span: Span,
+    pub start: Span,
+    pub field: DefId
+}
+
+///
+/// Collects all identifiers in the crate which resolve to a local variable or an item (including fields, variants, associated items and generic parameters).
+///
+/// Implementations of trait items resolve to the trait item, and constructors of tuple and unit structs (and variants) resolve to the struct (or the variant).
+/// Identifiers from macro expansions (and from the sources of other crates) are skipped.
+///
+pub fn collect_occurrences(tcx: TyCtxt) -> (Vec<Occurrence>, Vec<ShorthandField>) {
+    let mut v = OccurrenceCollector {
+        tcx,
+        bodies: vec![],
+        occurrences: vec![],
+        shorthands: vec![]
+    };
+
+    walk_crate(&mut v, tcx.hir().krate());
+
+    (v.occurrences, v.shorthands)
+}
+
+struct OccurrenceCollector<'v> {
+    tcx: TyCtxt<'v>,
+    bodies: Vec<BodyId>,
+    occurrences: Vec<Occurrence>,
+    shorthands: Vec<ShorthandField>
+}
+
+impl<'v> OccurrenceCollector<'v> {
+    fn tables(&self) -> Option<&'v TypeckTables<'v>> {
+        let body_id = self.bodies.last()?;
+        Some(self.tcx.typeck_tables_of(self.tcx.hir().body_owner_def_id(*body_id)))
+    }
+    fn push(&mut self, ident: Ident, target: Target, hir_id: HirId, is_definition: bool, is_unqualified: bool) {
+        if ident.span.from_expansion() || self.tcx.sess.source_map().is_imported(ident.span) || self.occurrences.iter().any(|o| o.span == ident.span) {
+            return;
+        }
+        self.occurrences.push(Occurrence { span: ident.span, target, hir_id, is_definition, is_unqualified });
+    }
+    fn push_definition(&mut self, ident: Ident, hir_id: HirId) {
+        let def_id = canonical_def_id(self.tcx, self.tcx.hir().local_def_id(hir_id));
+        self.push(ident, Target::Def(def_id), hir_id, true, false);
+    }
+    fn get_target(&self, res: Res) -> Option<Target> {
+        match res {
+            Res::Local(hir_id) => Some(Target::Local(hir_id)),
+            Res::Def(DefKind::Ctor(..), def_id) => self.tcx.parent(def_id).map(Target::Def),
+            Res::Def(_, def_id) => Some(Target::Def(canonical_def_id(self.tcx, def_id))),
+            _ => None
+        }
+    }
+    fn get_field(&self, tables: &TypeckTables, hir_id: HirId, qpath: &QPath, ident: Ident) -> Option<DefId> {
+        let adt_def = tables.node_type(hir_id).ty_adt_def()?;
+        let variant = adt_def.variant_of_res(tables.qpath_res(qpath, hir_id));
+        variant.fields.iter().find(|f| f.ident.name == ident.name).map(|f| f.did)
+    }
+}
+
+///
+/// The trait item, if `def_id` is an item in an implementation of a trait
+///
+pub fn canonical_def_id(tcx: TyCtxt, def_id: DefId) -> DefId {
+    if let Some(item) = tcx.opt_associated_item(def_id) {
+        if let AssocItemContainer::ImplContainer(impl_def_id) = item.container {
+            if let Some(trait_ref) = tcx.impl_trait_ref(impl_def_id) {
+                if let Some(trait_item) = tcx.associated_item_def_ids(trait_ref.def_id).iter()
+                    .find(|id| tcx.associated_item(**id).ident.name == item.ident.name) {
+                    return *trait_item;
+                }
+            }
+        }
+    }
+    def_id
+}
+
+impl<'v> Visitor<'v> for OccurrenceCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_body(&mut self, b: &'v Body<'v>) {
+        self.bodies.push(b.id());
+        walk_body(self, b);
+        self.bodies.pop();
+    }
+    fn visit_item(&mut self, item: &'v Item<'v>) {
+        match item.kind {
+            ItemKind::Use(..) | ItemKind::Impl { .. } | ItemKind::ForeignMod(..) | ItemKind::GlobalAsm(..) => {},
+            _ => self.push_definition(item.ident, item.hir_id)
+        }
+        walk_item(self, item);
+    }
+    fn visit_impl_item(&mut self, ii: &'v ImplItem<'v>) {
+        self.push_definition(ii.ident, ii.hir_id);
+        walk_impl_item(self, ii);
+    }
+    fn visit_trait_item(&mut self, ti: &'v TraitItem<'v>) {
+        self.push_definition(ti.ident, ti.hir_id);
+        walk_trait_item(self, ti);
+    }
+    fn visit_foreign_item(&mut self, fi: &'v ForeignItem<'v>) {
+        self.push_definition(fi.ident, fi.hir_id);
+        walk_foreign_item(self, fi);
+    }
+    fn visit_struct_field(&mut self, s: &'v StructField<'v>) {
+        if !s.is_positional() {
+            self.push_definition(s.ident, s.hir_id);
+        }
+        walk_struct_field(self, s);
+    }
+    fn visit_variant(&mut self, v: &'v Variant<'v>, g: &'v Generics<'v>, item_id: HirId) {
+        self.push_definition(v.ident, v.id);
+        walk_variant(self, v, g, item_id);
+    }
+    fn visit_generic_param(&mut self, p: &'v GenericParam<'v>) {
+        if let (ParamName::Plain(ident), GenericParamKind::Type { .. }) | (ParamName::Plain(ident), GenericParamKind::Const { .. }) = (p.name, &p.kind) {
+            self.push_definition(ident, p.hir_id);
+        }
+        walk_generic_param(self, p);
+    }
+    fn visit_pat(&mut self, p: &'v Pat<'v>) {
+        match &p.kind {
+            PatKind::Binding(_, hir_id, ident, _) => self.push(*ident, Target::Local(*hir_id), *hir_id, true, false),
+            PatKind::Struct(qpath, fields, _) => {
+                if let Some(tables) = self.tables() {
+                    for field in fields.iter() {
+                        if let Some(field_def_id) = self.get_field(tables, p.hir_id, qpath, field.ident) {
+                            if field.is_shorthand {
+                                self.shorthands.push(ShorthandField { ident_span: field.ident.span, start: field.pat.span.shrink_to_lo(), field: field_def_id });
+                            } else {
+                                self.push(field.ident, Target::Def(field_def_id), field.hir_id, false, false);
+                            }
+                        }
+                    }
+                }
+            },
+            _ => {}
+        }
+        walk_pat(self, p);
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if let Some(tables) = self.tables() {
+            match &ex.kind {
+                ExprKind::Struct(qpath, fields, _) => {
+                    for field in fields.iter() {
+                        if let Some(field_def_id) = self.get_field(tables, ex.hir_id, qpath, field.ident) {
+                            if field.is_shorthand {
+                                self.shorthands.push(ShorthandField { ident_span: field.ident.span, start: field.expr.span.shrink_to_lo(), field: field_def_id });
+                            } else {
+                                self.push(field.ident, Target::Def(field_def_id), field.hir_id, false, false);
+                            }
+                        }
+                    }
+                },
+                ExprKind::Field(base, ident) => {
+                    if let (Some(adt_def), Some(index)) = (tables.expr_ty_adjusted(base).ty_adt_def(), tables.field_indices().get(ex.hir_id)) {
+                        if adt_def.is_struct() || adt_def.is_union() {
+                            let field = &adt_def.non_enum_variant().fields[*index];
+                            self.push(*ident, Target::Def(field.did), ex.hir_id, false, false);
+                        }
+                    }
+                },
+                ExprKind::MethodCall(segment, ..) => {
+                    if let Some(def_id) = tables.type_dependent_def_id(ex.hir_id) {
+                        self.push(segment.ident, Target::Def(canonical_def_id(self.tcx, def_id)), ex.hir_id, false, false);
+                    }
+                },
+                _ => {}
+            }
+        }
+        walk_expr(self, ex);
+    }
+    fn visit_qpath(&mut self, qpath: &'v QPath<'v>, id: HirId, span: Span) {
+        if let QPath::TypeRelative(_, segment) = qpath {
+            if let Some(tables) = self.tables() {
+                if let Some(target) = self.get_target(tables.qpath_res(qpath, id)) {
+                    self.push(segment.ident, target, id, false, false);
+                }
+            }
+        }
+        walk_qpath(self, qpath, id, span);
+    }
+    fn visit_path(&mut self, path: &'v Path<'v>, id: HirId) {
+        for (i, segment) in path.segments.iter().enumerate() {
+            let res = if i + 1 == path.segments.len() { Some(path.res) } else { segment.res };
+            if let Some(target) = res.and_then(|res| self.get_target(res)) {
+                self.push(segment.ident, target, id, false, path.segments.len() == 1);
+            }
+        }
+        walk_path(self, path);
+    }
+}
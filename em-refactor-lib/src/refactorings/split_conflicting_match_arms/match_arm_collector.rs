@@ -0,0 +1,115 @@
+use rustc_hir::{Arm, Expr, ExprKind, MatchSource, PatKind};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr, walk_crate};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use crate::refactoring_invocation::{QueryResult, TyContext};
+
+pub enum OrPatternArm<'v> {
+    Match(&'v Arm<'v>),
+    IfLet {
+        expr: &'v Expr<'v>,
+        scrutinee: &'v Expr<'v>,
+        arm: &'v Arm<'v>,
+        else_: Option<&'v Expr<'v>>
+    }
+}
+
+impl<'v> OrPatternArm<'v> {
+    pub fn arm(&self) -> &'v Arm<'v> {
+        match self {
+            OrPatternArm::Match(arm) => arm,
+            OrPatternArm::IfLet { arm, .. } => arm
+        }
+    }
+    /// The span which is replaced when the arm is split
+    pub fn span(&self) -> Span {
+        match self {
+            OrPatternArm::Match(arm) => get_arm_span(arm),
+            OrPatternArm::IfLet { expr, .. } => expr.span
+        }
+    }
+}
+
+/// The span of the arm from the pattern to the end of the body, as `arm.span` may include the trailing comma
+pub fn get_arm_span(arm: &Arm) -> Span {
+    arm.pat.span.with_hi(arm.body.span.hi())
+}
+
+///
+/// Finds the innermost match arm or `if let` containing `pos`, where the pattern has multiple alternatives (`A | B`)
+///
+pub fn collect_or_pattern_arm<'v>(tcx: &'v TyContext, pos: Span) -> QueryResult<OrPatternArm<'v>> {
+    let mut v = OrPatternArmCollector {
+        tcx: tcx.0,
+        pos: Some(pos),
+        result: vec![]
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.result.pop().ok_or_else(|| tcx.source().span_err(pos, false))
+}
+
+///
+/// Finds all match arms and `if let`s in the crate, where the pattern has multiple alternatives (`A | B`)
+///
+pub fn collect_or_pattern_arms<'v>(tcx: &'v TyContext) -> Vec<OrPatternArm<'v>> {
+    let mut v = OrPatternArmCollector {
+        tcx: tcx.0,
+        pos: None,
+        result: vec![]
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.result
+}
+
+struct OrPatternArmCollector<'v> {
+    tcx: TyCtxt<'v>,
+    pos: Option<Span>,
+    result: Vec<OrPatternArm<'v>>
+}
+
+impl OrPatternArmCollector<'_> {
+    fn contains_pos(&self, span: Span) -> bool {
+        self.pos.map(|pos| span.contains(pos)).unwrap_or(true)
+    }
+}
+
+fn is_or_pattern(arm: &Arm) -> bool {
+    if let PatKind::Or(..) = arm.pat.kind { true } else { false }
+}
+
+impl<'v> Visitor<'v> for OrPatternArmCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if self.contains_pos(ex.span) && !ex.span.from_expansion() {
+            match ex.kind {
+                ExprKind::Match(_, arms, MatchSource::Normal) => {
+                    for arm in arms.iter() {
+                        if self.contains_pos(get_arm_span(arm)) && is_or_pattern(arm) {
+                            self.result.push(OrPatternArm::Match(arm));
+                        }
+                    }
+                },
+                ExprKind::Match(scrutinee, arms, MatchSource::IfLetDesugar { contains_else_clause }) => {
+                    if is_or_pattern(&arms[0]) {
+                        self.result.push(OrPatternArm::IfLet {
+                            expr: ex,
+                            scrutinee,
+                            arm: &arms[0],
+                            else_: if contains_else_clause { Some(&arms[1].body) } else { None }
+                        });
+                    }
+                },
+                _ => {}
+            }
+        }
+        walk_expr(self, ex);
+    }
+}
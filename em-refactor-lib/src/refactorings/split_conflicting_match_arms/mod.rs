@@ -0,0 +1,305 @@
+use std::collections::{BTreeMap, BTreeSet};
+use em_refactor_lib_types::FileStringReplacement;
+use rustc_hir::{Pat, PatKind, QPath};
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use crate::refactorings::visitors::collect_field;
+use match_arm_collector::{collect_or_pattern_arm, collect_or_pattern_arms, get_arm_span, OrPatternArm};
+
+mod match_arm_collector;
+
+/// Split conflicting match arms
+///
+/// ## Algorithm
+/// 1. Find the match arms to split, which is either
+///    a. the innermost match arm or `if let` containing the selection, where the pattern has alternatives (`A | B`)
+///    b. if a struct field is selected, all such arms where a binding binds to the field in some alternative
+/// 2. For each alternative, collect the place each binding binds to (e.g. the field `S.f`)
+/// 3. Replace the arm with one arm per alternative, each with a copy of the guard and body.
+///    An `if let` is converted to the equivalent match, so the scrutinee is still evaluated once
+///
+/// Preconditions
+/// - A binding binds to different places in the alternatives
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+    let or_arms = if let Some((field, _)) = collect_field(tcx.0, span) {
+        let field_segment = format!("({}).{}", tcx.0.def_path_str(field.hir_id.owner.to_def_id()), field.ident);
+        collect_or_pattern_arms(tcx).into_iter()
+            .filter(|or_arm| binds_field_with_conflict(tcx.0, or_arm, &field_segment))
+            .collect::<Vec<_>>()
+    } else {
+        let or_arm = collect_or_pattern_arm(tcx, span)?;
+        if !has_conflict(tcx.0, &or_arm) {
+            return Err(RefactoringErrorInternal::bindings_do_not_conflict());
+        }
+        vec![or_arm]
+    };
+    if or_arms.is_empty() {
+        return Err(RefactoringErrorInternal::bindings_do_not_conflict());
+    }
+
+    let mut changes = vec![];
+    for or_arm in &or_arms {
+        // Arms nested in an arm which is split are copied, so they are left as is
+        if !or_arms.iter().any(|other| other.span() != or_arm.span() && other.span().contains(or_arm.span())) {
+            changes.push(split_arm(tcx, or_arm)?);
+        }
+    }
+
+    Ok(AstDiff(changes))
+}
+
+fn split_arm(tcx: &TyContext, or_arm: &OrPatternArm) -> QueryResult<FileStringReplacement> {
+    let arm = or_arm.arm();
+    let alternatives = get_alternatives(arm.pat);
+
+    match *or_arm {
+        OrPatternArm::Match(arm) => {
+            // The guard and the body
+            let arm_span = get_arm_span(arm);
+            let rest = tcx.get_source(arm_span.with_lo(arm.pat.span.hi()));
+            let arms = alternatives.iter()
+                .map(|pat| format!("{}{}", tcx.get_source(pat.span), rest))
+                .collect::<Vec<_>>();
+
+            tcx.map_change(arm_span, arms.join(&format!(",\n{}", tcx.source().get_indent(arm_span))))
+        },
+        OrPatternArm::IfLet { expr, scrutinee, arm, else_ } => {
+            let indent = tcx.source().get_indent(expr.span);
+            let body = tcx.get_source(arm.body.span);
+            let mut arms = alternatives.iter()
+                .map(|pat| format!("{}    {} => {},\n", indent, tcx.get_source(pat.span), body))
+                .collect::<Vec<_>>();
+            arms.push(format!("{}    _ => {}\n", indent, else_.map(|e| tcx.get_source(e.span)).unwrap_or_else(|| "{}".to_owned())));
+
+            tcx.map_change(expr.span, format!("match {} {{\n{}{}}}", tcx.get_source(scrutinee.span), arms.join(""), indent))
+        }
+    }
+}
+
+fn get_alternatives<'v>(pat: &'v Pat<'v>) -> Vec<&'v Pat<'v>> {
+    match pat.kind {
+        PatKind::Or(pats) => pats.to_vec(),
+        _ => vec![pat]
+    }
+}
+
+fn has_conflict(tcx: TyCtxt, or_arm: &OrPatternArm) -> bool {
+    let places = get_alternatives(or_arm.arm().pat).into_iter().map(|pat| get_binding_places(tcx, pat)).collect::<Vec<_>>();
+    places.iter().any(|p| p != &places[0])
+}
+
+/// Checks if a binding binds to different places in the alternatives, where one of the places is (a part of) the field
+fn binds_field_with_conflict(tcx: TyCtxt, or_arm: &OrPatternArm, field_segment: &str) -> bool {
+    let places = get_alternatives(or_arm.arm().pat).into_iter().map(|pat| get_binding_places(tcx, pat)).collect::<Vec<_>>();
+    let bindings = places.iter().flat_map(|p| p.keys()).collect::<BTreeSet<_>>();
+
+    let ret = bindings.into_iter().any(|binding| {
+        let binding_places = places.iter().map(|p| p.get(binding)).collect::<Vec<_>>();
+        binding_places.iter().any(|p| p != &binding_places[0])
+            && binding_places.iter().flatten().flat_map(|p| p.iter()).any(|place| place.iter().any(|s| s == field_segment))
+    });
+    ret
+}
+
+///
+/// Maps each binding in the pattern to the places it binds to, where a place is a path of segments from the scrutinee
+/// e.g. `[(S).g]` for `f` in `S {f: 1, g: f}`
+///
+fn get_binding_places(tcx: TyCtxt, pat: &Pat) -> BTreeMap<String, BTreeSet<Vec<String>>> {
+    let mut places = BTreeMap::new();
+    collect_binding_places(tcx, pat, vec![], &mut places);
+    places
+}
+
+fn collect_binding_places(tcx: TyCtxt, pat: &Pat, place: Vec<String>, places: &mut BTreeMap<String, BTreeSet<Vec<String>>>) {
+    let sub_place = |segment: String| {
+        let mut place = place.to_vec();
+        place.push(segment);
+        place
+    };
+    match pat.kind {
+        PatKind::Binding(_, _, ident, sub) => {
+            places.entry(format!("{}", ident)).or_insert_with(BTreeSet::new).insert(place.to_vec());
+            if let Some(sub) = sub {
+                collect_binding_places(tcx, sub, place, places);
+            }
+        },
+        PatKind::Struct(ref qpath, fields, _) => {
+            let path = get_path(tcx, qpath, pat);
+            for field in fields {
+                collect_binding_places(tcx, &field.pat, sub_place(format!("({}).{}", path, field.ident)), places);
+            }
+        },
+        PatKind::TupleStruct(ref qpath, pats, ddpos) => {
+            let path = get_path(tcx, qpath, pat);
+            for (i, p) in pats.iter().enumerate() {
+                collect_binding_places(tcx, p, sub_place(format!("({}).{}", path, get_index(i, pats.len(), ddpos))), places);
+            }
+        },
+        PatKind::Tuple(pats, ddpos) => {
+            for (i, p) in pats.iter().enumerate() {
+                collect_binding_places(tcx, p, sub_place(format!(".{}", get_index(i, pats.len(), ddpos))), places);
+            }
+        },
+        PatKind::Box(p) | PatKind::Ref(p, _) => {
+            collect_binding_places(tcx, p, sub_place("*".to_owned()), places);
+        },
+        PatKind::Slice(before, slice, after) => {
+            for (i, p) in before.iter().enumerate() {
+                collect_binding_places(tcx, p, sub_place(format!("[{}]", i)), places);
+            }
+            if let Some(p) = slice {
+                collect_binding_places(tcx, p, sub_place(format!("[{}..-{}]", before.len(), after.len())), places);
+            }
+            for (i, p) in after.iter().enumerate() {
+                collect_binding_places(tcx, p, sub_place(format!("[-{}]", after.len() - i)), places);
+            }
+        },
+        PatKind::Or(pats) => {
+            for p in pats.iter() {
+                collect_binding_places(tcx, p, place.to_vec(), places);
+            }
+        },
+        PatKind::Wild | PatKind::Path(..) | PatKind::Lit(..) | PatKind::Range(..) => {}
+    }
+}
+
+/// Elements after `..` are indexed from the end, e.g. `-1` for `b` in `(a, .., b)`
+fn get_index(i: usize, len: usize, ddpos: Option<usize>) -> String {
+    match ddpos {
+        Some(pos) if i >= pos => format!("-{}", len - i),
+        _ => format!("{}", i)
+    }
+}
+
+fn get_path(tcx: TyCtxt, qpath: &QPath, pat: &Pat) -> String {
+    let typecheck_table = tcx.typeck_tables_of(pat.hir_id.owner.to_def_id());
+    match typecheck_table.qpath_res(qpath, pat.hir_id).opt_def_id() {
+        Some(def_id) => tcx.def_path_str(def_id),
+        None => "".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    const NAME: &str = "split-conflicting-match-arms";
+
+    #[test]
+    fn split_match_arm_with_guard() {
+        let input = r#"fn foo(s: S) -> i32 {
+    match s {
+        /*refactor-tool:test-id:start*/S {f, g: 1} | S {f: 1, g: f} if f > 0 => f/*refactor-tool:test-id:end*/,
+        _ => 0
+    }
+}
+struct S {f: i32, g: i32}"#;
+        let expected = Ok(r#"fn foo(s: S) -> i32 {
+    match s {
+        /*refactor-tool:test-id:start*/S {f, g: 1} if f > 0 => f,
+        S {f: 1, g: f} if f > 0 => f/*refactor-tool:test-id:end*/,
+        _ => 0
+    }
+}
+struct S {f: i32, g: i32}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn split_enum_variants() {
+        let input = r#"fn foo(e: E) -> i32 {
+    match e {
+        /*refactor-tool:test-id:start*/E::A(i) | E::B(i) => i/*refactor-tool:test-id:end*/
+    }
+}
+enum E {A(i32), B(i32)}"#;
+        let expected = Ok(r#"fn foo(e: E) -> i32 {
+    match e {
+        /*refactor-tool:test-id:start*/E::A(i) => i,
+        E::B(i) => i/*refactor-tool:test-id:end*/
+    }
+}
+enum E {A(i32), B(i32)}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn split_if_let_with_else() {
+        let input = r#"fn foo(t: (i32, i32)) -> i32 {
+    if let (0, i) | (i, 0) = t {
+        /*refactor-tool:test-id:start*/i/*refactor-tool:test-id:end*/
+    } else {
+        1
+    }
+}"#;
+        let expected = Ok(r#"fn foo(t: (i32, i32)) -> i32 {
+    match t {
+        (0, i) => {
+        /*refactor-tool:test-id:start*/i/*refactor-tool:test-id:end*/
+    },
+        (i, 0) => {
+        /*refactor-tool:test-id:start*/i/*refactor-tool:test-id:end*/
+    },
+        _ => {
+        1
+    }
+    }
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn split_arms_binding_selected_field() {
+        let input = r#"fn foo(s: S) -> i32 {
+    match s {
+        S {f, g: 1, ..} | S {f: 1, g: f, ..} => f,
+        S {f: 2, g: h, ..} | S {f: 3, g: h, ..} => h,
+        _ => 0
+    }
+}
+fn bar(s: S) -> i32 {
+    match s {
+        S {g: 1, h: i, ..} | S {g: i, h: 1, ..} => i,
+        _ => 0
+    }
+}
+struct S {/*refactor-tool:test-id:start*/f/*refactor-tool:test-id:end*/: i32, g: i32, h: i32}"#;
+        let expected = Ok(r#"fn foo(s: S) -> i32 {
+    match s {
+        S {f, g: 1, ..} => f,
+        S {f: 1, g: f, ..} => f,
+        S {f: 2, g: h, ..} | S {f: 3, g: h, ..} => h,
+        _ => 0
+    }
+}
+fn bar(s: S) -> i32 {
+    match s {
+        S {g: 1, h: i, ..} | S {g: i, h: 1, ..} => i,
+        _ => 0
+    }
+}
+struct S {/*refactor-tool:test-id:start*/f/*refactor-tool:test-id:end*/: i32, g: i32, h: i32}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn same_places_are_not_split() {
+        let input = r#"fn foo(o: Option<i32>) -> i32 {
+    match o {
+        Some(0) | None => /*refactor-tool:test-id:start*/0/*refactor-tool:test-id:end*/,
+        Some(i @ 1) | Some(i @ 2) => i,
+        _ => 3
+    }
+}"#;
+        let expected = Err(RefactoringErrorInternal::bindings_do_not_conflict());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
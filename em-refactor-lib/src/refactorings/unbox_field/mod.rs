@@ -0,0 +1,290 @@
+use rustc_hir::{Expr, ExprKind, GenericArg, Node, QPath, StructField, TyKind, UnOp};
+use rustc_hir::def::{DefKind, Res};
+use rustc_middle::ty::{self, Ty};
+use rustc_middle::ty::adjustment::Adjust;
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use super::box_named_field::struct_expression_collector::collect_struct_expressions;
+use super::box_named_field::struct_named_pattern_collector::collect_struct_named_patterns;
+use super::box_tuple_field::struct_constructor_call_collector::collect_struct_constructor_calls;
+use super::box_tuple_field::struct_tuple_pattern_collector::collect_struct_tuple_patterns;
+use super::visitors::{collect_field, collect_local_variable_use_exprs, collect_struct_field_access_expressions};
+use super::visitors::hir::collect_expr;
+use super::utils::format_ty;
+
+const CANNOT_UNBOX: &str = "The field cannot be unboxed";
+
+/// Unbox field refactoring, the inverse of box field
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the field which should be unboxed, with the type `Box<U>`
+/// - T <- the type of the struct
+/// - Ps <- All StructPatterns where PathInExpression has type T and F is bound
+/// - Vs <- All StructExpressions (or constructor calls) where PathInExpression has type T and F is initialized
+/// - Fs <- All FieldExpressions where Expression has type T and F is IDENTIFIER (or the index)
+/// - if F is bound to anything else than a variable in Ps then abort
+/// - Change F's type in T to U
+/// - for V in Vs:
+///   - Replace `Box::new(V')` with V', or replace V with `*Box::<U>::from(V)`
+/// - for F' in Fs and the uses of the variables bound in Ps
+///   - Remove the * around F', or add Box::new around F' if it is used as a Box
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool) -> QueryResult<AstDiff> {
+    let (field, index) = collect_field(tcx.0, span).ok_or_else(|| tcx.source().span_err(span, false))?;
+    let struct_hir_id = tcx.get_struct_hir_id(&field);
+    let boxed_ty_span = get_boxed_ty_span(&field)
+        .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the type of the field is not Box<_>"))?;
+    check_boxed_ty(tcx, &field)?;
+
+    let field_ident = if field.is_positional() { index.to_string() } else { field.ident.to_string() };
+    let struct_patterns = if field.is_positional() {
+        collect_struct_tuple_patterns(tcx, struct_hir_id, index)
+    } else {
+        collect_struct_named_patterns(tcx, struct_hir_id, &field_ident)
+    };
+    if !struct_patterns.other.is_empty() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the field is used in a pattern"));
+    }
+
+    let mut uses = vec![];
+    for field_access_expression in collect_struct_field_access_expressions(tcx, struct_hir_id, &field_ident) {
+        uses.push(get_expr(tcx, field_access_expression)?);
+    }
+    for new_binding in struct_patterns.new_bindings {
+        uses.extend(collect_local_variable_use_exprs(tcx, new_binding));
+    }
+
+    let mut changes = vec![tcx.map_change(field.ty.span, tcx.get_source(boxed_ty_span))?];
+
+    let (struct_expressions, struct_expression_shorthands) = if field.is_positional() {
+        (collect_struct_constructor_calls(tcx, struct_hir_id, index), vec![])
+    } else {
+        collect_struct_expressions(tcx, struct_hir_id, &field_ident)?
+    };
+    // The field is moved to the same field of another struct expression, e.g. `S { field: s.field }`
+    let (moved_uses, uses): (Vec<_>, Vec<_>) = uses.into_iter().partition(|use_| {
+        struct_expressions.contains(&use_.span) || struct_expression_shorthands.iter().any(|(span, _)| *span == use_.span)
+    });
+    for struct_expression in struct_expressions {
+        if moved_uses.iter().any(|use_| use_.span == struct_expression) {
+            continue;
+        }
+        let expr = get_expr(tcx, struct_expression)?;
+        changes.push(tcx.map_change(struct_expression, unbox_value(tcx, expr)?)?);
+    }
+    for (struct_expression, ident) in struct_expression_shorthands {
+        if moved_uses.iter().any(|use_| use_.span == struct_expression) {
+            continue;
+        }
+        let expr = get_expr(tcx, struct_expression)?;
+        changes.push(tcx.map_change(struct_expression, format!("{}: {}", ident, unbox_value(tcx, expr)?))?);
+    }
+    for use_ in uses {
+        if let Some((span, replacement)) = unbox_use(tcx, use_)? {
+            changes.push(tcx.map_change(span, replacement)?);
+        }
+    }
+
+    Ok(AstDiff(changes))
+}
+
+///
+/// The span of `T` if the type of the field is written as `Box<T>`
+///
+fn get_boxed_ty_span(field: &StructField) -> Option<Span> {
+    if let TyKind::Path(QPath::Resolved(None, path)) = &field.ty.kind {
+        let segment = path.segments.last()?;
+        if format!("{}", segment.ident) == "Box" {
+            if let [GenericArg::Type(ty)] = segment.generic_args().args {
+                return Some(ty.span);
+            }
+        }
+    }
+    None
+}
+
+///
+/// The boxed type must be sized and must not contain the struct itself
+///
+fn check_boxed_ty(tcx: &TyContext, field: &StructField) -> QueryResult<()> {
+    let hir = tcx.0.hir();
+    let field_def_id = hir.local_def_id(field.hir_id);
+    let struct_def_id = hir.local_def_id(hir.get_parent_item(field.hir_id));
+    let boxed_ty = match tcx.0.type_of(field_def_id).kind {
+        ty::Adt(adt_def, substs) if adt_def.is_box() => substs.type_at(0),
+        _ => return Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the type of the field is not Box<_>"))
+    };
+    if !boxed_ty.is_sized(tcx.0.at(field.span), tcx.0.param_env(struct_def_id)) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the boxed type is not sized"));
+    }
+    if boxed_ty.walk().any(|arg| matches!(arg.unpack(), ty::subst::GenericArgKind::Type(t) if t.ty_adt_def().map(|adt| adt.did) == Some(struct_def_id))) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the struct would contain itself"));
+    }
+    Ok(())
+}
+
+fn get_expr<'v>(tcx: &TyContext<'v>, span: Span) -> QueryResult<&'v Expr<'v>> {
+    collect_expr(tcx.0, span)
+        .map(|(expr, _)| expr)
+        .ok_or_else(|| tcx.source().span_err(span, true))
+}
+
+///
+/// `Box::new(e)` is replaced with `e`, anything else with `*Box::<U>::from(e)`
+///
+/// The boxed type is spelled out, since `*e` does not compile when the type of `e` is inferred from the field, e.g. `*vec![1].into()`
+///
+fn unbox_value(tcx: &TyContext, expr: &Expr) -> QueryResult<String> {
+    if let ExprKind::Call(callee, [arg]) = &expr.kind {
+        if is_box_new(tcx, callee) {
+            return Ok(tcx.get_source(arg.span));
+        }
+    }
+    if is_inferred_from_context(tcx, expr) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the type of the boxed value is inferred from the field"));
+    }
+    let tables = tcx.0.typeck_tables_of(expr.hir_id.owner.to_def_id());
+    let boxed_ty = tables.expr_ty_adjusted(expr).boxed_ty();
+    Ok(format!("*Box::<{}>::from({})", format_ty(tcx.0, boxed_ty), tcx.get_source(expr.span)))
+}
+
+///
+/// Whether `expr` calls a function returning a type parameter which does not occur in its parameters, e.g. `x.into()` or `Default::default()`
+///
+/// `Box::<U>::from(expr)` is ambiguous for such calls.
+///
+fn is_inferred_from_context(tcx: &TyContext, expr: &Expr) -> bool {
+    let tables = tcx.0.typeck_tables_of(expr.hir_id.owner.to_def_id());
+    let def_id = match &expr.kind {
+        ExprKind::Call(Expr { kind: ExprKind::Path(qpath), hir_id, .. }, _) => match tables.qpath_res(qpath, *hir_id) {
+            Res::Def(DefKind::Fn, def_id) | Res::Def(DefKind::AssocFn, def_id) => def_id,
+            _ => return false
+        },
+        ExprKind::MethodCall(..) => match tables.type_dependent_def_id(expr.hir_id) {
+            Some(def_id) => def_id,
+            None => return false
+        },
+        _ => return false
+    };
+    let sig = tcx.0.fn_sig(def_id);
+    let sig = sig.skip_binder();
+    let output = sig.output();
+    matches!(output.kind, ty::Param(..))
+        && !sig.inputs().iter().any(|input| input.walk().any(|arg| arg == output.into()))
+}
+
+fn is_box_new(tcx: &TyContext, callee: &Expr) -> bool {
+    if let ExprKind::Path(qpath) = &callee.kind {
+        let tables = tcx.0.typeck_tables_of(callee.hir_id.owner.to_def_id());
+        if let Some(def_id) = tables.qpath_res(qpath, callee.hir_id).opt_def_id() {
+            return format!("{}", tcx.0.item_name(def_id)) == "new"
+                && tcx.0.impl_of_method(def_id).map_or(false, |impl_def_id| tcx.0.type_of(impl_def_id).is_box());
+        }
+    }
+    false
+}
+
+///
+/// The change needed where the (boxed) field is used.
+///
+/// `use_` is a field access or the use of a variable bound to the field, possibly by reference
+///
+fn unbox_use<'v>(tcx: &TyContext<'v>, use_: &'v Expr<'v>) -> QueryResult<Option<(Span, String)>> {
+    let hir = tcx.0.hir();
+    let tables = tcx.0.typeck_tables_of(use_.hir_id.owner.to_def_id());
+    let expr_ty = |expr: &Expr| -> Ty { tables.expr_ty(expr) };
+    let get_parent = |expr: &Expr| match hir.get(hir.get_parent_node(expr.hir_id)) {
+        Node::Expr(parent) => Some(parent),
+        _ => None
+    };
+
+    // `**a` where `a` is bound by reference
+    let mut expr = use_;
+    while !expr_ty(expr).is_box() {
+        match get_parent(expr) {
+            Some(parent @ Expr { kind: ExprKind::Unary(UnOp::UnDeref, _), .. }) => expr = parent,
+            _ => break
+        }
+    }
+    let is_autoderefed = matches!(tables.expr_adjustments(expr).first(), Some(adjustment) if matches!(adjustment.kind, Adjust::Deref(None)));
+
+    match get_parent(expr) {
+        Some(parent @ Expr { kind: ExprKind::Unary(UnOp::UnDeref, _), .. }) if expr_ty(expr).is_box() => {
+            Ok(Some((parent.span, tcx.get_source(expr.span))))
+        },
+        Some(Expr { kind: ExprKind::Assign(lhs, rhs, _), .. }) if lhs.hir_id == expr.hir_id => {
+            Ok(Some((rhs.span, unbox_value(tcx, rhs)?)))
+        },
+        _ if is_autoderefed || !expr_ty(expr).is_box() => Ok(None),
+        Some(Expr { kind: ExprKind::Field(..), .. }) | Some(Expr { kind: ExprKind::MethodCall(..), .. }) => Ok(None),
+        Some(parent @ Expr { kind: ExprKind::AddrOf(..), .. }) if !tables.expr_adjustments(parent).is_empty() => Ok(None),
+        Some(Expr { kind: ExprKind::AddrOf(..), .. }) => {
+            Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the field is borrowed as a Box"))
+        },
+        _ => Ok(Some((expr.span, format!("Box::new({})", tcx.get_source(expr.span)))))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_UNBOX;
+    const NAME: &str = "unbox-field";
+
+    #[test]
+    fn binding_by_reference() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Box<i32> }
+fn foo(s: &S) -> i32 {
+    match s {
+        S { field } => **field + 1
+    }
+}"#;
+        let expected = Ok(r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: i32 }
+fn foo(s: &S) -> i32 {
+    match s {
+        S { field } => *field + 1
+    }
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn method_call_on_field() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Box<Vec<i32>> }
+fn foo(v: &Box<Vec<i32>>) -> usize {
+    let s = S { field: v.clone() };
+    s.field.len()
+}"#;
+        let expected = Ok(r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+fn foo(v: &Box<Vec<i32>>) -> usize {
+    let s = S { field: *Box::<std::vec::Vec<i32>>::from(v.clone()) };
+    s.field.len()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn value_inferred_from_field() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Box<Vec<i32>> }
+fn foo() -> usize {
+    let s = S { field: vec![1].into() };
+    s.field.len()
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the type of the boxed value is inferred from the field"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn unsized_boxed_type() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Box<dyn Fn()> }"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_UNBOX, "the boxed type is not sized"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME));
+        assert_eq!(actual, expected);
+    }
+}
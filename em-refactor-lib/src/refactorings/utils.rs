@@ -1,7 +1,12 @@
 use em_refactor_lib_types::FileStringReplacement;
 use crate::refactoring_invocation::{QueryResult, RefactoringErrorInternal};
-use rustc_middle::ty::TyCtxt;
-use rustc_span::{FileName, Span};
+use rustc_ast::ast::Attribute;
+use rustc_hir::{CRATE_HIR_ID, Expr, ExprKind, HirId, Node};
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{Ty, TyCtxt, TyKind, TyS, TypeckTables, print::with_crate_prefix};
+use rustc_middle::ty::subst::GenericArgKind;
+use rustc_middle::ty::adjustment::{Adjust, AutoBorrow, AutoBorrowMutability};
+use rustc_span::{BytePos, FileName, Span, symbol::Ident};
 use rustc_span::source_map::SourceMap;
 use std::path::PathBuf;
 
@@ -44,18 +49,211 @@ pub(crate) fn map_change_from_span(source_map: &SourceMap, span: Span, replaceme
         file_name: filename,
         byte_start: span.lo().0 - file_offset,
         byte_end: span.hi().0 - file_offset,
-        char_end: line_end.end_col.0,
-        char_start: line_start.start_col.0,
+        char_end: utf16_column(source_map, span.hi()).unwrap_or(line_end.end_col.0),
+        char_start: utf16_column(source_map, span.lo()).unwrap_or(line_start.start_col.0),
         line_end: line_end.line_index,
         line_start: line_start.line_index,
         replacement,
     })
 }
 
+///
+/// The column of `pos` counted in UTF-16 code units (which editors use), instead of chars
+///
+fn utf16_column(source_map: &SourceMap, pos: BytePos) -> Option<usize> {
+    let loc = source_map.lookup_char_pos(pos);
+    let line = loc.file.get_line(loc.line - 1)?;
+    Some(line.chars().take(loc.col.0).map(char::len_utf16).sum())
+}
+
 pub(crate) fn get_source(tcx: TyCtxt, span: Span) -> String {
     tcx.sess.source_map().span_to_snippet(span).unwrap()
 }
 
+///
+/// The source of `span`, where the replacements inside it are applied
+///
+pub(crate) fn get_source_with_replacements(tcx: TyCtxt, span: Span, replacements: &[(Span, String)]) -> String {
+    let mut replacements = replacements.iter().filter(|(s, _)| span.contains(*s)).collect::<Vec<_>>();
+    replacements.sort_by_key(|(s, _)| std::cmp::Reverse(s.lo()));
+    let mut ret = get_source(tcx, span);
+    for (s, replacement) in replacements {
+        let start = (s.lo() - span.lo()).0 as usize;
+        let end = (s.hi() - span.lo()).0 as usize;
+        ret.replace_range(start..end, replacement);
+    }
+    ret
+}
+
+///
+/// The receiver of a method call, with the autoref and autoderef made explicit
+///
+pub(crate) fn get_receiver(tcx: TyCtxt, tables: &TypeckTables, receiver: &Expr, replacements: &[(Span, String)]) -> String {
+    let adjustments = tables.expr_adjustments(receiver);
+    let mut ret = get_source_with_replacements(tcx, receiver.span, replacements);
+    let is_postfix = match receiver.kind {
+        ExprKind::Path(..) | ExprKind::Field(..) | ExprKind::Call(..) | ExprKind::MethodCall(..) | ExprKind::Index(..)
+        | ExprKind::Lit(..) | ExprKind::Tup(..) | ExprKind::Unary(..) | ExprKind::AddrOf(..) => true,
+        _ => false
+    };
+    if !adjustments.is_empty() && !is_postfix {
+        ret = format!("({})", ret);
+    }
+    for adjustment in adjustments {
+        match adjustment.kind {
+            Adjust::Deref(_) => ret = format!("*{}", ret),
+            Adjust::Borrow(AutoBorrow::Ref(_, AutoBorrowMutability::Mut { .. })) => ret = format!("&mut {}", ret),
+            Adjust::Borrow(AutoBorrow::Ref(_, AutoBorrowMutability::Not)) => ret = format!("&{}", ret),
+            _ => {}
+        }
+    }
+    ret
+}
+
+///
+/// Checks if a block or an operator expression replacing `expr` needs parentheses,
+/// i.e. unless it is used where any expression is allowed as is
+///
+pub(crate) fn replacement_needs_parens(tcx: TyCtxt, expr: &Expr) -> bool {
+    let hir = tcx.hir();
+    match hir.get(hir.get_parent_node(expr.hir_id)) {
+        Node::Stmt(_) | Node::Local(_) | Node::Block(_) | Node::Arm(_) => false,
+        // Macro arguments and desugared expressions are whole expressions in the source
+        Node::Expr(parent) if (parent.span.from_expansion() || parent.span == expr.span) && !expr.span.from_expansion() => false,
+        Node::Expr(parent) => match parent.kind {
+            ExprKind::Call(callee, _) => callee.hir_id == expr.hir_id,
+            ExprKind::MethodCall(_, _, args) => args[0].hir_id == expr.hir_id,
+            ExprKind::Assign(_, rhs, ..) => rhs.hir_id != expr.hir_id,
+            ExprKind::Tup(..) | ExprKind::Array(..) | ExprKind::Ret(..) | ExprKind::Break(..) | ExprKind::Struct(..) => false,
+            _ => true
+        },
+        _ => true
+    }
+}
+
+///
+/// `name`, or `name_1`, `name_2`, ... if it is already used
+///
+pub(crate) fn fresh_name(name: &str, used_names: &[String]) -> String {
+    if !used_names.iter().any(|used| used == name) {
+        return name.to_string();
+    }
+    (1..).map(|i| format!("{}_{}", name, i)).find(|n| !used_names.contains(n)).unwrap()
+}
+
+pub(crate) fn to_snake_case(s: &str) -> String {
+    let mut ret = String::new();
+    for (i, c) in s.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            ret.push('_');
+        }
+        ret.extend(c.to_lowercase());
+    }
+    ret
+}
+
+pub(crate) fn to_camel_case(s: &str) -> String {
+    s.split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars.next().map(|c| c.to_uppercase().chain(chars).collect::<String>()).unwrap_or_default()
+        })
+        .collect()
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().map_or(false, |c| c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+        && name != "_"
+        && !Ident::from_str(name).is_reserved()
+}
+
+///
+/// The path to `def_id`, e.g. `crate::a::S`
+///
+pub(crate) fn get_absolute_path(tcx: TyCtxt, def_id: DefId) -> String {
+    let path = with_crate_prefix(|| tcx.def_path_str(def_id));
+    // The prefix is only added in the 2018 edition
+    if !def_id.is_local() || path == "crate" || path.starts_with("crate::") {
+        path
+    } else if path.is_empty() {
+        "crate".to_owned()
+    } else {
+        format!("crate::{}", path)
+    }
+}
+
+///
+/// Whether the item `hir_id` is declared in the body of a function, a constant or a static, where it is not visible outside of it
+///
+pub(crate) fn is_declared_in_body(tcx: TyCtxt, hir_id: HirId) -> bool {
+    let hir = tcx.hir();
+    let parent = hir.get_parent_item(hir_id);
+    parent != CRATE_HIR_ID && hir.maybe_body_owned_by(parent).is_some()
+}
+
+///
+/// `ty` with absolute paths, except for the types declared in a body, which can only be referred to by their name, e.g. `S` instead of `crate::main::S`
+///
+pub(crate) fn format_ty(tcx: TyCtxt, ty: Ty) -> String {
+    let mut formatted = with_crate_prefix(|| format!("{}", ty));
+    for arg in ty.walk() {
+        if let GenericArgKind::Type(TyS { kind: TyKind::Adt(adt_def, _), .. }) = arg.unpack() {
+            match tcx.hir().as_local_hir_id(adt_def.did) {
+                Some(hir_id) if is_declared_in_body(tcx, hir_id) => {
+                    let path = with_crate_prefix(|| tcx.def_path_str(adt_def.did));
+                    formatted = replace_path(&formatted, &path, &tcx.item_name(adt_def.did).to_string());
+                },
+                _ => {}
+            }
+        }
+    }
+    formatted
+}
+
+/// Replaces the occurences of `path` in `s` which are not followed by more characters of an identifier
+fn replace_path(s: &str, path: &str, replacement: &str) -> String {
+    let mut ret = String::new();
+    let mut rest = s;
+    while let Some(i) = rest.find(path) {
+        let end = i + path.len();
+        let is_whole = !rest[end..].starts_with(|c: char| c.is_alphanumeric() || c == '_');
+        ret.push_str(&rest[..i]);
+        ret.push_str(if is_whole { replacement } else { path });
+        rest = &rest[end..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+///
+/// `path`, relative to the module `module_path` if it is declared in it or in a submodule of it,
+/// e.g. `b::S` for `crate::a::b::S` in `crate::a`
+///
+pub(crate) fn get_relative_path(path: &str, module_path: &str) -> String {
+    if path == module_path {
+        "self".to_owned()
+    } else if path.starts_with(&format!("{}::", module_path)) {
+        path[module_path.len() + 2..].to_string()
+    } else {
+        path.to_string()
+    }
+}
+
+///
+/// The name of the field if `expr` is a shorthand field, e.g. `x` in `S {x}`
+///
+pub(crate) fn get_shorthand_field(tcx: TyCtxt, expr: &Expr) -> Option<String> {
+    let hir = tcx.hir();
+    if let Node::Expr(Expr { kind: ExprKind::Struct(_, fields, _), .. }) = hir.get(hir.get_parent_node(expr.hir_id)) {
+        return fields.iter()
+            .find(|field| field.is_shorthand && field.expr.hir_id == expr.hir_id)
+            .map(|field| format!("{}", field.ident));
+    }
+    None
+}
 
 pub(crate) fn map_span_to_index(source_map: &SourceMap, span: Span) -> QueryResult<(String, Range)> {
     let filename = get_filename(source_map, span)?;
@@ -66,12 +264,12 @@ pub(crate) fn map_span_to_index(source_map: &SourceMap, span: Span) -> QueryResu
     Ok((filename, Range {
         from: Position {
             byte: span.lo().0 - file_offset,
-            character: line_start.start_col.0,
+            character: utf16_column(source_map, span.lo()).unwrap_or(line_start.start_col.0),
             line: line_start.line_index
         },
         to: Position {
             byte: span.hi().0 - file_offset,
-            character: line_end.end_col.0,
+            character: utf16_column(source_map, span.hi()).unwrap_or(line_end.end_col.0),
             line: line_end.line_index
         }
     }))
@@ -85,4 +283,26 @@ pub(crate) struct Position {
 pub(crate) struct Range {
     pub from: Position,
     pub to: Position
-}
\ No newline at end of file
+}
+
+///
+/// The span of an item, including the attributes and doc comments before it
+///
+pub(crate) fn get_item_span(tcx: TyCtxt, attrs: &[Attribute], item_span: Span) -> Span {
+    let mut span = attrs.iter()
+        .filter(|attr| !attr.span.from_expansion() && !attr.span.is_dummy())
+        .fold(item_span, |span, attr| if attr.span.lo() < span.lo() { span.with_lo(attr.span.lo()) } else { span });
+    // Attributes which are removed by the expansion, e.g. `#[derive(..)]`
+    let prev = tcx.sess.source_map().span_to_prev_source(span).unwrap_or_default();
+    let mut lines = prev.rsplit('\n');
+    let mut lo = span.lo() - BytePos(lines.next().map_or(0, |line| line.len()) as u32);
+    for line in lines {
+        let trimmed = line.trim();
+        if !trimmed.starts_with("#[") && !trimmed.starts_with("///") {
+            break;
+        }
+        lo = lo - BytePos(line.len() as u32 + 1);
+        span = span.with_lo(lo + BytePos((line.len() - line.trim_start().len()) as u32));
+    }
+    span
+}
@@ -1,5 +1,5 @@
 use rustc_span::{BytePos, Span};
-use rustc_hir::{BodyId, Expr, ExprKind, FnDecl};
+use rustc_hir::{BodyId, Expr, ExprKind, FnDecl, FnRetTy};
 use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr, walk_crate};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
@@ -34,7 +34,7 @@ impl<'v> Visitor<'v> for ClosureCollector<'v> {
                                 call_expr: ex,
                                 call_fn_expr: call_fn_expr,
                                 has_params: args.len() > 0,
-                                params_span: closure_params,
+                                params_span: self.get_params_span(closure_params, _fn_decl),
                                 fn_decl: _fn_decl,
                                 args_1: args
                             });
@@ -85,6 +85,20 @@ struct ClosureCollector<'v> {
     result: Option<Closure<'v>>
 }
 
+impl ClosureCollector<'_> {
+    /// The span of the closure declaration includes the return type, e.g. `|| -> i32`
+    fn get_params_span(&self, decl_span: Span, fn_decl: &FnDecl) -> Span {
+        if let FnRetTy::Return(ty) = fn_decl.output {
+            if let Ok(s) = self.tcx.sess.source_map().span_to_snippet(decl_span.with_hi(ty.span.lo())) {
+                if let Some(i) = s.rfind('|') {
+                    return decl_span.with_hi(BytePos(decl_span.lo().0 + i as u32 + 1));
+                }
+            }
+        }
+        decl_span
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -144,6 +158,21 @@ r#"fn main () {
         });
     }
     #[test]
+    fn closure_with_return_type() {
+        assert_success3(
+r#"fn main () {
+    /*START*/( |i: i32| -> Option<i32> { Some(i) } ) (0)/*END*/; 
+}"#,
+        map,
+        Closure {
+            args: "0".to_owned(),
+            params: "|i: i32|".to_owned(),
+            has_params: true,
+            fn_inputs: "i32".to_owned(),
+            body_params: "i".to_owned(),
+        });
+    }
+    #[test]
     fn closure_with_multiple_param() {
         assert_success3(
 r#"fn main () {
@@ -0,0 +1,68 @@
+use rustc_hir::{Block, BodyId, Expr, ExprKind, YieldSource};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_crate, walk_expr};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use crate::refactoring_invocation::TyContext;
+use super::awaited_async_block;
+
+///
+/// Checks if `block` contains `.await`, which is not in a closure or an async block inside it
+///
+pub fn contains_await(block: &Block) -> bool {
+    let mut v = AwaitCollector {
+        has_await: false
+    };
+    v.visit_block(block);
+    v.has_await
+}
+
+///
+/// Finds the async block `async { .. }.await` at `pos`, and returns the awaited expression and the body of the block
+///
+pub fn collect_awaited_async_block<'v>(tcx: &'v TyContext, pos: Span) -> Option<(&'v Expr<'v>, BodyId)> {
+    let mut v = AsyncBlockCollector {
+        tcx: tcx.0,
+        pos,
+        result: None
+    };
+    walk_crate(&mut v, tcx.0.hir().krate());
+    v.result
+}
+
+struct AsyncBlockCollector<'v> {
+    tcx: TyCtxt<'v>,
+    pos: Span,
+    result: Option<(&'v Expr<'v>, BodyId)>
+}
+
+impl<'v> Visitor<'v> for AsyncBlockCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        // The expressions of the desugaring of `.await` have the same span
+        if ex.span == self.pos && self.result.is_none() {
+            self.result = awaited_async_block(ex).map(|body_id| (ex, body_id));
+        }
+        walk_expr(self, ex);
+    }
+}
+
+struct AwaitCollector {
+    has_await: bool
+}
+
+impl<'v> Visitor<'v> for AwaitCollector {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::None
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if let ExprKind::Yield(_, YieldSource::Await { .. }) = ex.kind {
+            self.has_await = true;
+        }
+        walk_expr(self, ex);
+    }
+}
@@ -0,0 +1,70 @@
+use rustc_hir::{Body, Expr, ExprKind, HirId, Pat, PatKind, QPath};
+use rustc_hir::def::Res;
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr, walk_pat};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+///
+/// Collects the local variables read by the expression
+///
+pub fn collect_locals(expr: &Expr) -> Vec<HirId> {
+    let mut v = LocalPathCollector {
+        locals: vec![]
+    };
+    v.visit_expr(expr);
+    v.locals
+}
+
+struct LocalPathCollector {
+    locals: Vec<HirId>
+}
+
+impl<'v> Visitor<'v> for LocalPathCollector {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::None
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if let ExprKind::Path(QPath::Resolved(None, path)) = &ex.kind {
+            if let Res::Local(hir_id) = path.res {
+                self.locals.push(hir_id);
+            }
+        }
+        walk_expr(self, ex);
+    }
+}
+
+///
+/// Collects the names of the bindings declared inside `span` (in `body`, including closures)
+///
+pub fn collect_binding_names<'v>(tcx: TyCtxt<'v>, body: &'v Body<'v>, span: Span) -> Vec<String> {
+    let mut v = BindingCollector {
+        tcx,
+        span,
+        bindings: vec![]
+    };
+    v.visit_expr(&body.value);
+    v.bindings
+}
+
+struct BindingCollector<'v> {
+    tcx: TyCtxt<'v>,
+    span: Span,
+    bindings: Vec<String>
+}
+
+impl<'v> Visitor<'v> for BindingCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::OnlyBodies(self.tcx.hir())
+    }
+    fn visit_pat(&mut self, p: &'v Pat<'v>) {
+        if let PatKind::Binding(_, _, ident, _) = p.kind {
+            if self.span.contains(p.span) {
+                self.bindings.push(format!("{}", ident));
+            }
+        }
+        walk_pat(self, p);
+    }
+}
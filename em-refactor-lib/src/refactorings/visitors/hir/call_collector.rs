@@ -0,0 +1,146 @@
+use rustc_hir::{Block, Expr, ExprKind, ImplItem, ImplItemKind, StmtKind, def_id::DefId};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_crate, walk_expr, walk_impl_item};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+use crate::refactoring_invocation::TyContext;
+
+/// A call of a function, either as `foo(a)`, `({ fn foo() {} foo })(a)`, `S::foo(s, a)` or `s.foo(a)`
+pub struct FnCall<'v> {
+    pub expr: &'v Expr<'v>,
+    /// The span of the path to the function, or None for a method call
+    pub callee: Option<Span>,
+    pub args: &'v [Expr<'v>],
+    pub def_id: DefId
+}
+
+impl FnCall<'_> {
+    pub fn is_method_call(&self) -> bool {
+        self.callee.is_none()
+    }
+}
+
+/// Collects all calls of the function `def_id`
+pub fn collect_calls<'v>(tcx: &'v TyContext, def_id: DefId) -> Vec<FnCall<'v>> {
+    let mut v = CallCollector {
+        tcx: tcx.0,
+        def_id: Some(def_id),
+        pos: None,
+        calls: vec![]
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.calls
+}
+
+/// Collects all calls in the crate
+pub fn collect_all_calls<'v>(tcx: &'v TyContext) -> Vec<FnCall<'v>> {
+    let mut v = CallCollector {
+        tcx: tcx.0,
+        def_id: None,
+        pos: None,
+        calls: vec![]
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.calls
+}
+
+/// Collects the call expression at `pos`
+pub fn collect_call<'v>(tcx: &'v TyContext, pos: Span) -> Option<FnCall<'v>> {
+    let mut v = CallCollector {
+        tcx: tcx.0,
+        def_id: None,
+        pos: Some(pos),
+        calls: vec![]
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.calls.pop()
+}
+
+struct CallCollector<'v> {
+    tcx: TyCtxt<'v>,
+    def_id: Option<DefId>,
+    pos: Option<Span>,
+    calls: Vec<FnCall<'v>>
+}
+
+impl<'v> CallCollector<'v> {
+    fn get_call(&self, ex: &'v Expr<'v>) -> Option<FnCall<'v>> {
+        let typecheck_table = self.tcx.typeck_tables_of(ex.hir_id.owner.to_def_id());
+        match ex.kind {
+            ExprKind::Call(callee, args) => {
+                // `({ fn foo() {} foo })(a)`, as introduced by convert closure to function
+                let callee = match callee.kind {
+                    ExprKind::Block(Block { stmts, expr: Some(expr), .. }, None) if stmts.iter().all(|stmt| matches!(stmt.kind, StmtKind::Item(..))) => expr,
+                    _ => callee
+                };
+                if let ExprKind::Path(ref qpath) = callee.kind {
+                    let def_id = typecheck_table.qpath_res(qpath, callee.hir_id).opt_def_id()?;
+                    return Some(FnCall { expr: ex, callee: Some(callee.span), args, def_id });
+                }
+                None
+            },
+            ExprKind::MethodCall(_, _, args) => {
+                let def_id = typecheck_table.type_dependent_def_id(ex.hir_id)?;
+                Some(FnCall { expr: ex, callee: None, args, def_id })
+            },
+            _ => None
+        }
+    }
+}
+
+impl<'v> Visitor<'v> for CallCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if self.pos.map(|pos| pos == ex.span).unwrap_or(true) {
+            if let Some(call) = self.get_call(ex) {
+                if self.def_id.map(|def_id| def_id == call.def_id).unwrap_or(true) {
+                    self.calls.push(call);
+                }
+            }
+        }
+        walk_expr(self, ex);
+    }
+}
+
+/// Collects the method definition at `pos`
+pub fn collect_method_definition(tcx: &TyContext, pos: Span) -> Option<DefId> {
+    let mut v = MethodDefCollector {
+        tcx: tcx.0,
+        pos,
+        def_id: None
+    };
+
+    walk_crate(&mut v, tcx.0.hir().krate());
+
+    v.def_id
+}
+
+struct MethodDefCollector<'v> {
+    tcx: TyCtxt<'v>,
+    pos: Span,
+    def_id: Option<DefId>
+}
+
+impl<'v> Visitor<'v> for MethodDefCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_impl_item(&mut self, ii: &'v ImplItem<'v>) {
+        if let ImplItemKind::Fn(..) = ii.kind {
+            if ii.span == self.pos {
+                self.def_id = Some(self.tcx.hir().local_def_id(ii.hir_id));
+            }
+        }
+        walk_impl_item(self, ii);
+    }
+}
@@ -1,4 +1,6 @@
+use itertools::Itertools;
 use rustc_span::Span;
+use crate::refactorings::utils::to_camel_case;
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum CfType {
@@ -13,11 +15,47 @@ pub struct ControlFlowExpr {
     pub cf_key_span: Span,
     pub cf_expr_span: Span,
     pub sub_expr_span: Option<Span>,
-    pub sub_expr_type: Option<String>
+    pub sub_expr_type: Option<String>,
+    /// The label of the loop which is broken out of or continued, e.g. `'outer`
+    pub label: Option<String>
 }
 
 pub struct ControlFlowExprCollection {
-    pub items: Vec<ControlFlowExpr>
+    pub items: Vec<ControlFlowExpr>,
+    /// Set if the block contains `?`, which returns early from the enclosing function
+    pub try_type: Option<TryType>
+}
+
+/// The return type of the enclosing function, which `?` returns to
+#[derive(Clone, Debug, PartialEq)]
+pub enum TryType {
+    /// The error type of a Result
+    Result(String),
+    Option
+}
+
+impl TryType {
+    pub fn wrap_type(&self, ty: &str) -> String {
+        match self {
+            TryType::Result(err) => format!("std::result::Result<{}, {}>", ty, err),
+            TryType::Option => format!("std::option::Option<{}>", ty)
+        }
+    }
+    ///
+    /// The variant with the error type, e.g. `Ok::<_, E>`, which is needed in an async block where the error type of `?` cannot be inferred
+    ///
+    pub fn annotated_ok_variant(&self) -> String {
+        match self {
+            TryType::Result(err) => format!("Ok::<_, {}>", err),
+            TryType::Option => "Some".to_owned()
+        }
+    }
+    fn ok_variant(&self) -> &str {
+        match self {
+            TryType::Result(_) => "Ok",
+            TryType::Option => "Some"
+        }
+    }
 }
 fn get_enum_name() -> String {
     "ReturnFoo".to_owned() // TODO: add a random number to the name?
@@ -29,14 +67,15 @@ impl ControlFlowExprCollection {
         let enum_name = get_enum_name();
         let mut arms = vec![];
         
-        if let Some(e) = self.get_cf_break() {
+        // Each label has its own variant, e.g. `break 'outer` is matched by `BreakOuter() => break 'outer`
+        for e in self.get_cf_breaks() {
             let (sub1, sub2) = 
                 if e.sub_expr_span.is_some() {("e".to_owned(), " e".to_owned())}
                 else {("".to_owned(), "".to_owned())};
-            arms.push(format!("\n{}::Break({}) => break{}", enum_name, sub1, sub2));
+            arms.push(format!("\n{}::{}({}) => {}{}", enum_name, e.get_variant_name(), sub1, e.get_jump(), sub2));
         }
-        if let Some(_) = self.get_cf_cont() {
-            arms.push(format!("\n{}::Continue() => continue", enum_name));
+        for e in self.get_cf_conts() {
+            arms.push(format!("\n{}::{}() => {}", enum_name, e.get_variant_name(), e.get_jump()));
         }
         if let Some(_) = self.get_cf_expr() {
             arms.push(format!("\n{}::Expr(e) => e", enum_name));
@@ -60,34 +99,62 @@ impl ControlFlowExprCollection {
         false
     }
 
-    pub fn replace_cfs(&self) -> Vec<(Span, String)> {
+    ///
+    /// The return type of the closure, if it must be annotated.
+    /// This is needed when the block contains `?`, as the error type can't be inferred
+    ///
+    pub fn get_return_type(&self) -> Option<String> {
+        let try_type = self.try_type.as_ref()?;
+        if self.has_cfs() {
+            Some(try_type.wrap_type(&get_enum_name()))
+        } else {
+            Some(try_type.wrap_type(&self.get_cf_expr()?.sub_expr_type.clone().unwrap_or_default()))
+        }
+    }
+
+    ///
+    /// The replacements of the control flow expressions and the value of the block.
+    /// `annotate_ok` is set for an async block, where the error type of `?` cannot be inferred
+    ///
+    pub fn replace_cfs(&self, annotate_ok: bool) -> Vec<(Span, String)> {
         let mut replacements = vec![];
         let cfs = self.items.to_vec();
 
         let enum_name = get_enum_name();
+        // With `?`, all values are wrapped in Ok / Some
+        let (try_open, try_close) = match &self.try_type {
+            Some(try_type) if annotate_ok => (format!("{}(", try_type.annotated_ok_variant()), ")"),
+            Some(try_type) => (format!("{}(", try_type.ok_variant()), ")"),
+            None => ("".to_owned(), "")
+        };
         for cf in cfs {
             match cf.cf_type {
                 CfType::Break => {
                     // check macros inv!
 
-                    replacements.push((cf.cf_key_span, format!("return {}::Break(", enum_name)));
-                    replacements.push((cf.cf_expr_span.shrink_to_hi(), ")".to_owned()));
+                    replacements.push((cf.cf_key_span, format!("return {}{}::{}(", try_open, enum_name, cf.get_variant_name())));
+                    replacements.push((cf.cf_expr_span.shrink_to_hi(), format!("){}", try_close)));
                 },
                 CfType::Continue => {
-                    replacements.push((cf.cf_key_span, format!("return {}::Continue()", enum_name)));
+                    replacements.push((cf.cf_key_span, format!("return {}{}::{}(){}", try_open, enum_name, cf.get_variant_name(), try_close)));
                 },
                 CfType::Nothing => {
+                    let (open, close) = if self.has_cfs() {
+                        (format!("{}{}::Expr(", try_open, enum_name), format!("){}", try_close))
+                    } else {
+                        (try_open.to_string(), try_close.to_owned())
+                    };
 
                     if cf.cf_expr_span.lo() == cf.cf_expr_span.hi() {
-                        replacements.push((cf.cf_expr_span, format!("{}::Expr(())", enum_name)));
+                        replacements.push((cf.cf_expr_span, format!("{}(){}", open, close)));
                     } else {
-                        replacements.push((cf.cf_expr_span.shrink_to_lo(), format!("{}::Expr(", enum_name)));
-                        replacements.push((cf.cf_expr_span.shrink_to_hi(), ")".to_owned()));
+                        replacements.push((cf.cf_expr_span.shrink_to_lo(), open));
+                        replacements.push((cf.cf_expr_span.shrink_to_hi(), close));
                     }
                 },
                 CfType::Return => {
-                    replacements.push((cf.cf_key_span, format!("return {}::Return(", enum_name)));
-                    replacements.push((cf.cf_expr_span.shrink_to_hi(), ")".to_owned()));
+                    replacements.push((cf.cf_key_span, format!("return {}{}::Return(", try_open, enum_name)));
+                    replacements.push((cf.cf_expr_span.shrink_to_hi(), format!("){}", try_close)));
                 },
             }
         }
@@ -99,25 +166,27 @@ impl ControlFlowExprCollection {
     pub fn get_cf_ret(&self) -> Option<&ControlFlowExpr> {
         self.items.iter().find(|c| c.cf_type == CfType::Return)
     }
-    pub fn get_cf_cont(&self) -> Option<&ControlFlowExpr> {
-        self.items.iter().find(|c| c.cf_type == CfType::Continue)
+    /// The first `continue` of each label
+    pub fn get_cf_conts(&self) -> Vec<&ControlFlowExpr> {
+        self.items.iter().filter(|c| c.cf_type == CfType::Continue).unique_by(|c| &c.label).collect()
     }
     pub fn get_cf_expr(&self) -> Option<&ControlFlowExpr> {
         self.items.iter().find(|c| c.cf_type == CfType::Nothing)
     }
-    pub fn get_cf_break(&self) -> Option<&ControlFlowExpr> {
-        self.items.iter().find(|c| c.cf_type == CfType::Break)
+    /// The first `break` of each label
+    pub fn get_cf_breaks(&self) -> Vec<&ControlFlowExpr> {
+        self.items.iter().filter(|c| c.cf_type == CfType::Break).unique_by(|c| &c.label).collect()
     }
 
     pub fn get_enum_decl(&self) -> String {
         let enum_name = get_enum_name();
 
         let mut parts = vec![];
-        if let Some(e) = self.get_cf_break() {
-            parts.push(format!("Break({})", e.sub_expr_type.clone().unwrap_or_default()));
+        for e in self.get_cf_breaks() {
+            parts.push(format!("{}({})", e.get_variant_name(), e.sub_expr_type.clone().unwrap_or_default()));
         }
-        if let Some(_) = self.get_cf_cont() {
-            parts.push(format!("Continue()"));
+        for e in self.get_cf_conts() {
+            parts.push(format!("{}()", e.get_variant_name()));
         }
         if let Some(e) = self.get_cf_expr() {
             parts.push(format!("Expr({})", e.sub_expr_type.clone().unwrap_or_default()));
@@ -141,32 +210,64 @@ impl ControlFlowExpr {
             _ => true
         }
     }
+    ///
+    /// The variant of the generated enum, e.g. `Break` for `break` and `BreakOuter` for `break 'outer`
+    ///
+    pub fn get_variant_name(&self) -> String {
+        let name = match self.cf_type {
+            CfType::Break => "Break",
+            CfType::Continue => "Continue",
+            CfType::Return => "Return",
+            CfType::Nothing => "Expr"
+        };
+        match &self.label {
+            Some(label) => format!("{}{}", name, to_camel_case(label.trim_start_matches('\''))),
+            None => name.to_owned()
+        }
+    }
+    ///
+    /// The `break` or `continue` with the label, e.g. `break 'outer`
+    ///
+    fn get_jump(&self) -> String {
+        let keyword = if self.cf_type == CfType::Break { "break" } else { "continue" };
+        match &self.label {
+            Some(label) => format!("{} {}", keyword, label),
+            None => keyword.to_owned()
+        }
+    }
     pub fn new(cf_type: CfType, cf_expr_span: Span, cf_key_span: Span, sub_expr_span: Option<Span>, sub_expr_type: Option<String>) -> Self {
         Self {
             cf_type,
             cf_expr_span,
             cf_key_span,
             sub_expr_span,
-            sub_expr_type
+            sub_expr_type,
+            label: None
         }
     }
-    pub fn cont(cf_expr_span: Span) -> Self {
-        Self::new(
-            CfType::Continue,
-            cf_expr_span,
-            cf_expr_span,
-            None,
-            None
-        )
+    pub fn cont(cf_expr_span: Span, label: Option<String>) -> Self {
+        Self {
+            label,
+            ..Self::new(
+                CfType::Continue,
+                cf_expr_span,
+                cf_expr_span,
+                None,
+                None
+            )
+        }
     }
-    pub fn brk(cf_expr_span: Span, cf_key_span: Span, sub_expr_span: Option<Span>, sub_expr_type: Option<String>) -> Self {
-        Self::new(
-            CfType::Break,
-            cf_expr_span,
-            cf_key_span,
-            sub_expr_span,
-            sub_expr_type
-        )
+    pub fn brk(cf_expr_span: Span, cf_key_span: Span, sub_expr_span: Option<Span>, sub_expr_type: Option<String>, label: Option<String>) -> Self {
+        Self {
+            label,
+            ..Self::new(
+                CfType::Break,
+                cf_expr_span,
+                cf_key_span,
+                sub_expr_span,
+                sub_expr_type
+            )
+        }
     }
     pub fn ret(cf_expr_span: Span, cf_key_span: Span, sub_expr_span: Option<Span>, sub_expr_type: Option<String>) -> Self {
         Self::new(
@@ -1,19 +1,20 @@
 use rustc_hir::{Arm, BodyId, Destination, Expr, ExprKind, FnDecl, HirId, MatchSource, Node };
 use rustc_hir::intravisit::{NestedVisitorMap, FnKind, Visitor, walk_block, walk_expr};
 use rustc_middle::hir::map::Map;
-use rustc_middle::ty::{TyCtxt, print::with_crate_prefix};
+use rustc_middle::ty::{TyCtxt, TyKind, print::with_crate_prefix};
 use rustc_span::{BytePos, Span};
-use super::{ControlFlowExpr, ControlFlowExprCollection};
+use super::{ControlFlowExpr, ControlFlowExprCollection, TryType};
 
 /**
  * Given a block id / span?
- * Searches for break, continue,
+ * Searches for break, continue, return and `?`
  * special break-loop!
  */
 struct CfExprCollector<'v> {
     tcx: TyCtxt<'v>,
     block_span: Span,
-    res: Vec<ControlFlowExpr>
+    res: Vec<ControlFlowExpr>,
+    try_type: Option<TryType>
 }
 
 pub fn collect_cfs(tcx: TyCtxt<'_>, block_hir_id: HirId) -> ControlFlowExprCollection {
@@ -27,7 +28,8 @@ pub fn collect_cfs(tcx: TyCtxt<'_>, block_hir_id: HirId) -> ControlFlowExprColle
     let mut v = CfExprCollector {
         tcx,
         block_span: block.span,
-        res: vec![]
+        res: vec![],
+        try_type: None
     };
 
     walk_block(&mut v, block);
@@ -38,7 +40,7 @@ pub fn collect_cfs(tcx: TyCtxt<'_>, block_hir_id: HirId) -> ControlFlowExprColle
         v.res.push(ControlFlowExpr::expr(block.span.with_hi(BytePos((block.span.hi().0 - 1) as u32)).shrink_to_hi(), "()".to_owned()))
     }
 
-    ControlFlowExprCollection { items: v.res }
+    ControlFlowExprCollection { items: v.res, try_type: v.try_type }
 }
 
 impl CfExprCollector<'_> {
@@ -54,6 +56,28 @@ impl CfExprCollector<'_> {
         }
         false
     }
+
+    ///
+    /// `e?` is desugared to
+    /// `match Try::into_result(e) { Ok(val) => val, Err(err) => return Try::from_error(From::from(err)) }`,
+    /// where the type of the returned expression is the return type of the enclosing function
+    ///
+    fn get_try_type(&self, arms: &[Arm]) -> Option<TryType> {
+        let ret_ex = arms.iter().find_map(|arm| if let ExprKind::Ret(Some(ret_ex)) = arm.body.kind {Some(ret_ex)} else {None})?;
+        let typecheck_table = self.tcx.typeck_tables_of(ret_ex.hir_id.owner.to_def_id());
+        let ty = typecheck_table.expr_ty(ret_ex);
+
+        if let TyKind::Adt(adt_def, substs) = ty.kind {
+            let path = self.tcx.def_path_str(adt_def.did);
+            if path.ends_with("result::Result") {
+                return Some(TryType::Result(with_crate_prefix(|| format!("{}", substs.type_at(1)))));
+            }
+            if path.ends_with("option::Option") {
+                return Some(TryType::Option);
+            }
+        }
+        None
+    }
 }
 
 impl<'v> Visitor<'v> for CfExprCollector<'v> {
@@ -73,6 +97,13 @@ impl<'v> Visitor<'v> for CfExprCollector<'v> {
 
     fn visit_expr(&mut self, ex: &'v Expr<'v>) {
         match ex.kind {
+            ExprKind::Match(scrutinee, ref arms, MatchSource::TryDesugar) => {
+                // The return in the desugared match is not a return in the source
+                if self.try_type.is_none() {
+                    self.try_type = self.get_try_type(arms);
+                }
+                walk_expr(self, scrutinee);
+            },
             ExprKind::Match(_, ref arms, MatchSource::WhileDesugar) => {
                 if let Some(arm) = arms.first() {
                     let Arm { body, .. } = arm;
@@ -94,12 +125,15 @@ impl<'v> Visitor<'v> for CfExprCollector<'v> {
                     (ex.span, None, None)
                 };
                 if self.points_outside(&dest) {
-                    self.res.push(ControlFlowExpr::brk(ex.span, break_span, break_ex_span, expr_type));
+                    self.res.push(ControlFlowExpr::brk(ex.span, break_span, break_ex_span, expr_type, get_label(&dest)));
+                }
+                if let Some(break_ex) = break_ex {
+                    walk_expr(self, break_ex);
                 }
             },
             ExprKind::Continue(dest) => {
                 if self.points_outside(&dest) {
-                    self.res.push(ControlFlowExpr::cont(ex.span));
+                    self.res.push(ControlFlowExpr::cont(ex.span, get_label(&dest)));
                 }
             },
             ExprKind::Ret(ret_ex) => {
@@ -118,6 +152,10 @@ impl<'v> Visitor<'v> for CfExprCollector<'v> {
     }
 }
 
+fn get_label(dest: &Destination) -> Option<String> {
+    dest.label.map(|label| format!("{}", label.ident))
+}
+
 fn get_type_of_expr(tcx: &TyCtxt, expr: &Expr) -> String {
     let typecheck_table = tcx.typeck_tables_of(expr.hir_id.owner.to_def_id());
     let ty = typecheck_table.expr_ty(expr);
@@ -222,4 +260,84 @@ mod test {
 
         assert_eq!(expected, actual);
     }
-}
\ No newline at end of file
+    fn map_variants(file_name: String, from: u32, to: u32) -> Box<dyn Fn(&TyContext) -> QueryResult<Vec<(String, String)>> + Send> {
+        Box::new(move |ty| {
+            let span = ty.source().map_span(&file_name, from, to)?;
+            let block = collect_innermost_contained_block(ty, span).unwrap();
+            let cfs = collect_cfs(ty.0, block.0.hir_id);
+
+            Ok(cfs.items.into_iter()
+                .map(|cf| (ty.get_source(cf.cf_expr_span), cf.get_variant_name()))
+                .collect::<Vec<_>>())
+        })
+    }
+    #[test]
+    fn should_collect_labels() {
+
+        let input = r#"
+        fn foo () -> i32 {
+            'outer: loop {
+                let _ = loop {
+                    let _ = /*START*/{
+                        continue 'outer;
+                        break 'outer 1;
+                        break 2;
+                        3
+                    }/*END*/;
+                };
+            }
+        }"#;
+        let expected = Ok(vec![
+            ("continue 'outer".to_owned(), "ContinueOuter".to_owned()),
+            ("break 'outer 1".to_owned(), "BreakOuter".to_owned()),
+            ("break 2".to_owned(), "Break".to_owned()),
+            ("3".to_owned(), "Expr".to_owned()),
+        ]);
+
+        let actual = run_ty_query(input, map_variants);
+
+        assert_eq!(expected, actual);
+    }
+    fn map_try_type(file_name: String, from: u32, to: u32) -> Box<dyn Fn(&TyContext) -> QueryResult<(usize, Option<TryType>)> + Send> {
+        Box::new(move |ty| {
+            let span = ty.source().map_span(&file_name, from, to)?;
+            let block = collect_innermost_contained_block(ty, span).unwrap();
+            let cfs = collect_cfs(ty.0, block.0.hir_id);
+
+            Ok((cfs.items.len(), cfs.try_type))
+        })
+    }
+    #[test]
+    fn should_collect_try_result() {
+
+        let input = r#"
+        fn foo () -> Result<i32, std::num::ParseIntError> {
+            let i = /*START*/{
+                let s = "1";
+                s.parse::<i32>()?
+            }/*END*/;
+            Ok(i)
+        }"#;
+        let expected = Ok((1, Some(TryType::Result("std::num::ParseIntError".to_owned()))));
+
+        let actual = run_ty_query(input, map_try_type);
+
+        assert_eq!(expected, actual);
+    }
+    #[test]
+    fn should_collect_try_option() {
+
+        let input = r#"
+        fn foo (v: Option<i32>) -> Option<i32> {
+            let i = /*START*/{
+                v? + 1
+            }/*END*/;
+            Some(i)
+        }"#;
+        let expected = Ok((1, Some(TryType::Option)));
+
+        let actual = run_ty_query(input, map_try_type);
+
+        assert_eq!(expected, actual);
+    }
+}
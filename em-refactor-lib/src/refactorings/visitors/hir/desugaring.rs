@@ -1,4 +1,4 @@
-use rustc_hir::{Arm, Block, Expr, ExprKind, LoopSource, MatchSource, Pat, StmtKind};
+use rustc_hir::{Arm, Block, BodyId, Expr, ExprKind, LoopSource, MatchSource, Pat, StmtKind};
 use rustc_hir::intravisit::{Visitor, walk_expr};
 use if_chain::if_chain;
 
@@ -109,4 +109,17 @@ fn if_block<'tcx>(
 //         }
 //     } 
 //     None
-// }
\ No newline at end of file
+// }
+/// Recover the async block of an awaited async block:
+/// `async { body }.await` becomes the body of the generator
+pub fn awaited_async_block(expr: &Expr) -> Option<BodyId> {
+    if_chain! {
+        if let ExprKind::Match(ref future, _, MatchSource::AwaitDesugar) = expr.kind;
+        if let ExprKind::Call(_, [ref generator]) = future.kind;
+        if let ExprKind::Closure(_, _, body_id, _, Some(_)) = generator.kind;
+        then {
+            return Some(body_id);
+        }
+    }
+    None
+}
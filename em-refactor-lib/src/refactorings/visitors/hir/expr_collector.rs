@@ -0,0 +1,47 @@
+use rustc_hir::{BodyId, Expr};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_crate, walk_expr};
+use rustc_middle::hir::map::Map;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::Span;
+
+///
+/// Finds the innermost expression where the span is `pos`, and the body containing it
+///
+pub fn collect_expr<'v>(tcx: TyCtxt<'v>, pos: Span) -> Option<(&'v Expr<'v>, BodyId)> {
+    let mut v = ExprCollector {
+        tcx,
+        pos,
+        body_ids: vec![],
+        result: None
+    };
+    walk_crate(&mut v, tcx.hir().krate());
+    v.result
+}
+
+struct ExprCollector<'v> {
+    tcx: TyCtxt<'v>,
+    pos: Span,
+    body_ids: Vec<BodyId>,
+    result: Option<(&'v Expr<'v>, BodyId)>
+}
+
+impl<'v> Visitor<'v> for ExprCollector<'v> {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::All(self.tcx.hir())
+    }
+    fn visit_nested_body(&mut self, id: BodyId) {
+        self.body_ids.push(id);
+        let body = self.tcx.hir().body(id);
+        self.visit_body(body);
+        self.body_ids.pop();
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if ex.span == self.pos {
+            if let Some(body_id) = self.body_ids.last() {
+                self.result = Some((ex, *body_id));
+            }
+        }
+        walk_expr(self, ex);
+    }
+}
@@ -45,6 +45,11 @@ impl<'a, 'v> Visitor<'v> for FnDefCollector<'a, 'v> {
                         impl_: self.impl_items.last().map(|span| (*span, *self.impl_for.last().unwrap_or(&false)))
                     });
                 }
+                // The functions declared in its body are not in the impl
+                let impl_items = std::mem::take(&mut self.impl_items);
+                walk_item(self, i);
+                self.impl_items = impl_items;
+                return;
             },
             ItemKind::Impl {of_trait, ..} => {
                 self.impl_for.push(of_trait.is_some());
@@ -195,6 +200,28 @@ mod test {
 
         let actual = run_ty_query(input, map);
 
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn fn_decl_nested_fn_in_impl() {
+        let input = r#"
+        struct S;
+        impl S {
+            fn main () {
+                fn bar() {
+                    /*START*/fn foo() {}/*END*/;
+                }
+            }
+        }"#;
+
+        let expected = Ok(FnDefinitionTest{
+            span: "fn foo() {}".to_owned(),
+            parent_mod_span: input.trim().to_owned(),
+            parent_impl_span: None
+        });
+
+        let actual = run_ty_query(input, map);
+
         assert_eq!(actual, expected);
     }
 }
\ No newline at end of file
@@ -0,0 +1,55 @@
+use rustc_hir::{BodyId, HirId};
+use rustc_infer::infer::{TyCtxtInferExt};
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_typeck::expr_use_visitor::{ConsumeMode, Delegate, ExprUseVisitor, Place, PlaceBase};
+use rustc_span::Span;
+use crate::refactorings::visitors::hir::ExpressionUseKind;
+
+struct LocalUseDelegate {
+    usages: Vec<(HirId, Span, ExpressionUseKind)>
+}
+
+impl LocalUseDelegate {
+    fn var_used(&mut self, place: &Place, use_kind: ExpressionUseKind) {
+        if let PlaceBase::Local(local_id) = place.base {
+            self.usages.push((local_id, place.span, use_kind));
+        }
+    }
+}
+
+impl<'tcx> Delegate<'tcx> for LocalUseDelegate {
+    fn consume(&mut self, place: &Place<'tcx>, cm: ConsumeMode) {
+        self.var_used(place, ExpressionUseKind::from_consume_mode(cm));
+    }
+
+    fn borrow(&mut self, place: &Place<'tcx>, bk: ty::BorrowKind) {
+        self.var_used(place, ExpressionUseKind::from_borrow_kind(bk));
+    }
+
+    fn mutate(&mut self, place: &Place<'tcx>) {
+        self.var_used(place, ExpressionUseKind::Mut);
+    }
+}
+
+///
+/// Collects how each local variable is used in the body, as (local, span of the use, use kind).
+/// Bindings are reported as mutations of the bound variable
+///
+pub fn collect_local_uses(tcx: TyCtxt<'_>, body_id: BodyId) -> Vec<(HirId, Span, ExpressionUseKind)> {
+    let def_id = body_id.hir_id.owner.to_def_id();
+    tcx.infer_ctxt().enter(|inf| {
+        let mut v = LocalUseDelegate {
+            usages: vec![]
+        };
+        ExprUseVisitor::new(
+            &mut v,
+            &inf,
+            def_id,
+            tcx.param_env(def_id),
+            tcx.body_tables(body_id),
+        )
+        .consume_body(tcx.hir().body(body_id));
+
+        v.usages
+    })
+}
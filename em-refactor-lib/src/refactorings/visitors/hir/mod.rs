@@ -1,17 +1,31 @@
 mod anonymous_closure_collector;
+mod await_collector;
+mod binding_collector;
+mod call_collector;
 mod cf_collection;
 mod cf_expr_collector;
 mod desugaring;
+mod expr_collector;
 mod expression_use_kind;
 mod function_definition_collector;
 mod innermost_block_collector;
 mod innermost_contained_block_collector;
+mod local_use_collector;
+mod qpath_res_collector;
+mod side_effect_collector;
 
 pub use anonymous_closure_collector::*;
+pub use await_collector::*;
+pub use binding_collector::*;
+pub use call_collector::*;
 pub use cf_collection::*;
 pub use cf_expr_collector::*;
 pub use desugaring::*;
+pub use expr_collector::*;
 pub use expression_use_kind::*;
 pub use function_definition_collector::*;
 pub use innermost_block_collector::*;
-pub use innermost_contained_block_collector::*;
\ No newline at end of file
+pub use innermost_contained_block_collector::*;
+pub use local_use_collector::*;
+pub use qpath_res_collector::*;
+pub use side_effect_collector::*;
\ No newline at end of file
@@ -0,0 +1,55 @@
+use rustc_hir::{Body, Expr, ExprKind};
+use rustc_hir::intravisit::{NestedVisitorMap, Visitor, walk_expr};
+use rustc_middle::hir::map::Map;
+use rustc_span::Span;
+
+///
+/// Checks if evaluating the expression cannot have side effects, i.e. it does not call functions or assign
+///
+pub fn is_pure(expr: &Expr) -> bool {
+    match &expr.kind {
+        ExprKind::Lit(_) | ExprKind::Path(_) => true,
+        ExprKind::Field(e, _) | ExprKind::AddrOf(_, _, e) | ExprKind::Cast(e, _) | ExprKind::Type(e, _)
+        | ExprKind::Unary(_, e) | ExprKind::DropTemps(e) => is_pure(e),
+        ExprKind::Binary(_, l, r) | ExprKind::Index(l, r) => is_pure(l) && is_pure(r),
+        ExprKind::Tup(es) | ExprKind::Array(es) => es.iter().all(is_pure),
+        ExprKind::Struct(_, fields, base) => fields.iter().all(|f| is_pure(&f.expr)) && base.map_or(true, is_pure),
+        _ => false
+    }
+}
+
+///
+/// Checks if any expression inside `span` (in `body`, but not in closures) can have side effects
+///
+pub fn contains_side_effects<'v>(body: &'v Body<'v>, span: Span) -> bool {
+    let mut v = SideEffectCollector {
+        span,
+        has_side_effects: false
+    };
+    v.visit_expr(&body.value);
+    v.has_side_effects
+}
+
+struct SideEffectCollector {
+    span: Span,
+    has_side_effects: bool
+}
+
+impl<'v> Visitor<'v> for SideEffectCollector {
+    type Map = Map<'v>;
+    fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
+        NestedVisitorMap::None
+    }
+    fn visit_expr(&mut self, ex: &'v Expr<'v>) {
+        if self.span.contains(ex.span) {
+            match ex.kind {
+                ExprKind::Call(..) | ExprKind::MethodCall(..) | ExprKind::Assign(..) | ExprKind::AssignOp(..)
+                | ExprKind::Ret(..) | ExprKind::Break(..) | ExprKind::Continue(..) | ExprKind::Yield(..) => {
+                    self.has_side_effects = true;
+                },
+                _ => {}
+            }
+        }
+        walk_expr(self, ex);
+    }
+}
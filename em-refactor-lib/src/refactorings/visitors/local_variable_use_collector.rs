@@ -25,6 +25,13 @@ use crate::refactoring_invocation::TyContext;
 /// then `collect_local_variable_use(x)` will return `(x0, x1)` and `(y0, y1)`
 ///
 pub fn collect_local_variable_use(tcx: &TyContext, hir_id: HirId) -> Vec<Span> {
+    collect_local_variable_use_exprs(tcx, hir_id).iter().map(|expr| expr.span).collect()
+}
+
+///
+/// Same as `collect_local_variable_use`, but returns the expressions
+///
+pub fn collect_local_variable_use_exprs<'v>(tcx: &TyContext<'v>, hir_id: HirId) -> Vec<&'v Expr<'v>> {
     let mut v = LocalVariableUseCollector {
         tcx: tcx.0,
         hir_id,
@@ -39,7 +46,7 @@ pub fn collect_local_variable_use(tcx: &TyContext, hir_id: HirId) -> Vec<Span> {
 struct LocalVariableUseCollector<'v> {
     tcx: TyCtxt<'v>,
     hir_id: HirId,
-    uses: Vec<Span>,
+    uses: Vec<&'v Expr<'v>>,
 }
 
 impl<'v> Visitor<'v> for LocalVariableUseCollector<'v> {
@@ -47,15 +54,15 @@ impl<'v> Visitor<'v> for LocalVariableUseCollector<'v> {
     fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
         NestedVisitorMap::All(self.tcx.hir())
     }
-    fn visit_expr(&mut self, expr: &'v Expr) {
+    fn visit_expr(&mut self, expr: &'v Expr<'v>) {
         if expr.hir_id == self.hir_id {
-            self.uses.push(expr.span);
+            self.uses.push(expr);
         }
         if let ExprKind::Path(qpath) = &expr.kind {
             if let QPath::Resolved(_, path) = qpath {
                 if let Res::Local(hir_id) = path.res {
                     if hir_id == self.hir_id {
-                        self.uses.push(expr.span);
+                        self.uses.push(expr);
                     }
                 }
             }
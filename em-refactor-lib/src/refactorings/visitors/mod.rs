@@ -6,6 +6,6 @@ mod struct_field_access_expression_collector;
 mod struct_def_field_collector;
 
 pub use inline_macro_collector::collect_inline_macro;
-pub use local_variable_use_collector::collect_local_variable_use;
+pub use local_variable_use_collector::{collect_local_variable_use, collect_local_variable_use_exprs};
 pub use struct_field_access_expression_collector::collect_struct_field_access_expressions;
 pub use struct_def_field_collector::collect_field;
\ No newline at end of file
@@ -1,11 +1,11 @@
 use rustc_hir::intravisit::{walk_crate, walk_item, NestedVisitorMap, Visitor};
-use rustc_hir::{Item, ItemKind, StructField};
+use rustc_hir::{Item, ItemKind, StructField, VariantData};
 use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Span;
 
 ///
-/// Returns the corresponding `StructField` in a struct definition if the `span` is equal to the `StructField`'s `span`
+/// Returns the corresponding `StructField` in a struct (or enum variant) definition if the `span` is equal to the `StructField`'s `span`
 ///
 /// # Example
 /// Given the program:
@@ -47,18 +47,30 @@ struct FieldCollector<'v> {
     field: Option<(&'v StructField<'v>, usize)>,
 }
 
+impl<'v> FieldCollector<'v> {
+    fn visit_fields(&mut self, data: &'v VariantData<'v>) {
+        for (i, field) in data.fields().iter().enumerate() {
+            if field.ident.span.contains(self.span) {
+                self.field = Some((field, i));
+            }
+        }
+    }
+}
+
 impl<'v> Visitor<'v> for FieldCollector<'v> {
     type Map = Map<'v>;
     fn nested_visit_map(&mut self) -> NestedVisitorMap<Self::Map> {
         NestedVisitorMap::All(self.tcx.hir())
     }
     fn visit_item(&mut self, item: &'v Item<'v>) {
-        if let ItemKind::Struct(data, _) = &item.kind {
-            for (i, field) in data.fields().iter().enumerate() {
-                if field.ident.span.contains(self.span) {
-                    self.field = Some((field, i));
+        match &item.kind {
+            ItemKind::Struct(data, _) => self.visit_fields(data),
+            ItemKind::Enum(def, _) => {
+                for variant in def.variants {
+                    self.visit_fields(&variant.data);
                 }
-            }
+            },
+            _ => {}
         }
         walk_item(self, item);
     }
@@ -96,6 +108,18 @@ mod test {
         assert_eq!(actual, expected);
     }
     #[test]
+    fn should_collect_enum_variant_definition() {
+        let input = r#"
+            enum E { A { field: i32 }, B (i32, /*START*/u32/*END*/) }"#;
+        
+        let expected = Ok(Some((
+            "1".to_owned(), "u32".to_owned(), 1)));
+
+        let actual = run_ty_query(input, map);
+
+        assert_eq!(actual, expected);
+    }
+    #[test]
     fn should_collect_tuple_definition() {
         let input = r#"
             struct T (i32);
@@ -0,0 +1,390 @@
+use rustc_hir::{BodyId, Expr, ExprKind, Node};
+use rustc_middle::ty;
+use rustc_middle::ty::adjustment::Adjust;
+use rustc_middle::ty::subst::GenericArgKind;
+use rustc_span::Span;
+use crate::refactoring_invocation::{AstDiff, QueryResult, RefactoringErrorInternal, TyContext};
+use super::box_named_field::struct_expression_collector::collect_struct_expressions;
+use super::box_named_field::struct_named_pattern_collector::collect_struct_named_patterns;
+use super::box_tuple_field::struct_constructor_call_collector::collect_struct_constructor_calls;
+use super::box_tuple_field::struct_tuple_pattern_collector::collect_struct_tuple_patterns;
+use super::visitors::{collect_field, collect_local_variable_use_exprs, collect_struct_field_access_expressions};
+use super::visitors::hir::{collect_expr, collect_local_uses, ExpressionUseKind};
+
+const CANNOT_WRAP: &str = "The field cannot be wrapped";
+
+///
+/// The types a field can be wrapped in
+///
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wrapper {
+    Arc,
+    Cell,
+    Mutex,
+    Option,
+    Rc,
+    RefCell
+}
+
+impl Wrapper {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Arc" => Some(Self::Arc),
+            "Cell" => Some(Self::Cell),
+            "Mutex" => Some(Self::Mutex),
+            "Option" => Some(Self::Option),
+            "Rc" => Some(Self::Rc),
+            "RefCell" => Some(Self::RefCell),
+            _ => None
+        }
+    }
+    fn path(self) -> &'static str {
+        match self {
+            Self::Arc => "std::sync::Arc",
+            Self::Cell => "std::cell::Cell",
+            Self::Mutex => "std::sync::Mutex",
+            Self::Option => "Option",
+            Self::Rc => "std::rc::Rc",
+            Self::RefCell => "std::cell::RefCell"
+        }
+    }
+    fn name(self) -> &'static str {
+        self.path().rsplit("::").next().unwrap()
+    }
+    fn wrap(self, value: &str) -> String {
+        match self {
+            Self::Option => format!("Some({})", value),
+            _ => format!("{}::new({})", self.path(), value)
+        }
+    }
+    /// The wrapped value, when it is copied or borrowed from `place`
+    fn read(self, place: &str) -> String {
+        match self {
+            Self::Arc | Self::Rc => format!("(*{})", place),
+            Self::Cell => format!("{}.get()", place),
+            Self::Mutex => format!("(*{}.lock().unwrap())", place),
+            Self::Option => format!("(*{}.as_ref().unwrap())", place),
+            Self::RefCell => format!("(*{}.borrow())", place)
+        }
+    }
+    /// Whether the wrapped value is accessed through a temporary (a copy or a guard), so a reference to it is only valid in the statement
+    fn is_accessed_through_temporary(self) -> bool {
+        match self {
+            Self::Cell | Self::Mutex | Self::RefCell => true,
+            Self::Arc | Self::Option | Self::Rc => false
+        }
+    }
+    /// The wrapped value, when it is mutated through `place`
+    fn write(self, place: &str) -> Option<String> {
+        match self {
+            Self::Arc | Self::Rc | Self::Cell => None,
+            Self::Mutex => Some(format!("(*{}.lock().unwrap())", place)),
+            Self::Option => Some(format!("(*{}.as_mut().unwrap())", place)),
+            Self::RefCell => Some(format!("(*{}.borrow_mut())", place))
+        }
+    }
+    /// The wrapped value, when it is moved out of `place`
+    fn take(self, place: &str) -> Option<String> {
+        match self {
+            Self::Arc | Self::Rc => None,
+            Self::Cell | Self::RefCell => Some(format!("{}.into_inner()", place)),
+            Self::Mutex => Some(format!("{}.into_inner().unwrap()", place)),
+            Self::Option => Some(format!("{}.unwrap()", place))
+        }
+    }
+}
+
+/// Wrap field refactoring, box field generalized to other wrapper types
+///
+/// ## Algorithm
+///
+/// Steps
+/// - F <- the field which should be wrapped, with the type U
+/// - W <- the wrapper type (`Rc`, `Arc`, `Cell`, `RefCell`, `Option` or `Mutex`)
+/// - T <- the struct or the enum variant which contains F
+/// - Ps <- All StructPatterns where PathInExpression has type T and F is bound
+/// - Vs <- All StructExpressions (or constructor calls) where PathInExpression has type T and F is initialized
+/// - Fs <- All FieldExpressions where Expression has type T and F is IDENTIFIER (or the index)
+/// - if F is bound to anything else than a variable (by value) in Ps then abort
+/// - Change F's type in T to W<U>
+/// - for V in Vs:
+///   - Add the constructor of W around V
+/// - for F' in Fs and the uses of the variables bound in Ps
+///   - Replace F' with the access of W, depending on if F' is read, mutated, moved or assigned to
+pub fn do_refactoring(tcx: &TyContext, span: Span, _add_comment: bool, wrapper: Wrapper) -> QueryResult<AstDiff> {
+    let (field, index) = collect_field(tcx.0, span).ok_or_else(|| tcx.source().span_err(span, false))?;
+    let struct_hir_id = tcx.get_struct_hir_id(&field);
+    let hir = tcx.0.hir();
+
+    if wrapper == Wrapper::Cell {
+        let field_ty = tcx.0.type_of(hir.local_def_id(field.hir_id));
+        let param_env = tcx.0.param_env(hir.local_def_id(hir.get_parent_item(field.hir_id)));
+        if !field_ty.is_copy_modulo_regions(tcx.0, param_env, field.span) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, "the type of the field must be Copy to be wrapped in Cell"));
+        }
+    }
+
+    let field_ident = if field.is_positional() { index.to_string() } else { field.ident.to_string() };
+    let struct_patterns = if field.is_positional() {
+        collect_struct_tuple_patterns(tcx, struct_hir_id, index)
+    } else {
+        collect_struct_named_patterns(tcx, struct_hir_id, &field_ident)
+    };
+    if !struct_patterns.other.is_empty() {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, "the field is matched in a pattern"));
+    }
+
+    let mut uses = vec![];
+    for field_access_expression in collect_struct_field_access_expressions(tcx, struct_hir_id, &field_ident) {
+        uses.push(get_expr(tcx, field_access_expression)?);
+    }
+    for new_binding in struct_patterns.new_bindings {
+        let tables = tcx.0.typeck_tables_of(new_binding.owner.to_def_id());
+        if let Some(ty::BindingMode::BindByReference(_)) = tables.pat_binding_modes().get(new_binding) {
+            return Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, "the field is bound by reference in a pattern"));
+        }
+        uses.extend(collect_local_variable_use_exprs(tcx, new_binding));
+    }
+
+    let mut changes = vec![tcx.map_change(field.ty.span, format!("{}<{}>", wrapper.path(), tcx.get_source(field.ty.span)))?];
+
+    let (struct_expressions, struct_expression_shorthands) = if field.is_positional() {
+        (collect_struct_constructor_calls(tcx, struct_hir_id, index), vec![])
+    } else {
+        collect_struct_expressions(tcx, struct_hir_id, &field_ident)?
+    };
+    // The field is moved to the same field of another struct expression, e.g. `S { field: s.field }`
+    let (moved_uses, uses): (Vec<_>, Vec<_>) = uses.into_iter().partition(|use_| {
+        struct_expressions.contains(&use_.span) || struct_expression_shorthands.iter().any(|(span, _)| *span == use_.span)
+    });
+    for struct_expression in struct_expressions {
+        let struct_expression = struct_expression.source_callsite();
+        if !moved_uses.iter().any(|use_| use_.span == struct_expression) {
+            changes.push(tcx.map_change(struct_expression, wrapper.wrap(&tcx.get_source(struct_expression)))?);
+        }
+    }
+    for (struct_expression, ident) in struct_expression_shorthands {
+        if !moved_uses.iter().any(|use_| use_.span == struct_expression) {
+            changes.push(tcx.map_change(struct_expression, format!("{}: {}", ident, wrapper.wrap(&tcx.get_source(struct_expression))))?);
+        }
+    }
+
+    let mut local_uses = vec![];
+    for use_ in uses {
+        let (span, replacement) = wrap_use(tcx, wrapper, use_, &mut local_uses)?;
+        changes.push(tcx.map_change(span, replacement)?);
+    }
+
+    Ok(AstDiff(changes))
+}
+
+fn get_expr<'v>(tcx: &TyContext<'v>, span: Span) -> QueryResult<&'v Expr<'v>> {
+    collect_expr(tcx.0, span)
+        .map(|(expr, _)| expr)
+        .ok_or_else(|| tcx.source().span_err(span, true))
+}
+
+///
+/// The change needed where the (wrapped) field is used.
+///
+/// `use_` is a field access or the use of a variable bound to the field
+///
+fn wrap_use<'v>(tcx: &TyContext<'v>, wrapper: Wrapper, use_: &'v Expr<'v>, local_uses: &mut Vec<(BodyId, Vec<(Span, ExpressionUseKind)>)>) -> QueryResult<(Span, String)> {
+    let hir = tcx.0.hir();
+    let source = tcx.get_source(use_.span);
+    let parent = match hir.get(hir.get_parent_node(use_.hir_id)) {
+        Node::Expr(parent) => Some(parent),
+        _ => None
+    };
+
+    match parent.map(|parent| (parent.span, &parent.kind)) {
+        Some((parent_span, ExprKind::Assign(lhs, rhs, _))) if lhs.hir_id == use_.hir_id => {
+            let rhs_span = rhs.span.source_callsite();
+            return Ok(match wrapper {
+                Wrapper::Cell => (parent_span, format!("{}.set({})", source, tcx.get_source(rhs_span))),
+                _ => (rhs_span, wrapper.wrap(&tcx.get_source(rhs_span)))
+            });
+        },
+        Some((parent_span, ExprKind::AssignOp(op, lhs, rhs))) if lhs.hir_id == use_.hir_id && wrapper == Wrapper::Cell => {
+            let rhs_source = match rhs.kind {
+                ExprKind::Binary(..) | ExprKind::Cast(..) => format!("({})", tcx.get_source(rhs.span.source_callsite())),
+                _ => tcx.get_source(rhs.span.source_callsite())
+            };
+            return Ok((parent_span, format!("{0}.set({0}.get() {1} {2})", source, op.node.as_str(), rhs_source)));
+        },
+        _ => {}
+    }
+
+    let use_kind = get_use_kind(tcx, use_, local_uses);
+    if wrapper.is_accessed_through_temporary() && !use_kind.is_moved() && is_borrow_kept(tcx, use_) {
+        return Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, &format!("a reference to the field is kept after the statement, which {} does not allow", wrapper.name())));
+    }
+    let replacement = if use_kind.is_mutated() {
+        wrapper.write(&source)
+            .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_WRAP, &format!("the field is mutated, which {} does not allow", wrapper.name())))?
+    } else if use_kind.is_moved() {
+        wrapper.take(&source)
+            .ok_or_else(|| RefactoringErrorInternal::not_supported(CANNOT_WRAP, &format!("the field is moved, which {} does not allow", wrapper.name())))?
+    } else {
+        wrapper.read(&source)
+    };
+    Ok((use_.span, replacement))
+}
+
+///
+/// Checks if a reference to the field, e.g. `&s.field` or `s.field.iter()`, is kept after the statement, in a variable or as the return value
+///
+fn is_borrow_kept(tcx: &TyContext, use_: &Expr) -> bool {
+    let hir = tcx.0.hir();
+    let tables = tcx.0.typeck_tables_of(use_.hir_id.owner.to_def_id());
+    let get_parent = |expr: &Expr| hir.find(hir.get_parent_node(expr.hir_id));
+
+    let mut place = use_;
+    loop {
+        match get_parent(place) {
+            Some(Node::Expr(parent)) => match parent.kind {
+                ExprKind::Field(..) => place = parent,
+                ExprKind::Index(base, _) if base.hir_id == place.hir_id => place = parent,
+                _ => break
+            },
+            _ => break
+        }
+    }
+    let is_autorefed = tables.expr_adjustments(place).iter().any(|adjustment| matches!(adjustment.kind, Adjust::Borrow(_)));
+    let mut expr = match get_parent(place) {
+        Some(Node::Expr(parent @ Expr { kind: ExprKind::AddrOf(..), .. })) => parent,
+        Some(Node::Expr(parent)) if is_autorefed => parent,
+        _ => return false
+    };
+
+    // The reference is kept as long as the value contains a lifetime
+    let has_lifetime = |expr: &Expr| tables.expr_ty(expr).walk().any(|arg| matches!(arg.unpack(), GenericArgKind::Lifetime(_)));
+    while has_lifetime(expr) {
+        let mut parent = hir.get_parent_node(expr.hir_id);
+        while let Some(Node::Block(_)) | Some(Node::Arm(_)) = hir.find(parent) {
+            parent = hir.get_parent_node(parent);
+        }
+        match hir.find(parent) {
+            Some(Node::Expr(Expr { kind: ExprKind::Ret(_), .. })) => return true,
+            Some(Node::Expr(parent)) => expr = parent,
+            Some(Node::Stmt(_)) => return false,
+            _ => return true
+        }
+    }
+    false
+}
+
+///
+/// How the field is used, where a use of a field of the field (e.g. `s.field.x = 1`) is a use of the field
+///
+pub(crate) fn get_use_kind(tcx: &TyContext, use_: &Expr, local_uses: &mut Vec<(BodyId, Vec<(Span, ExpressionUseKind)>)>) -> ExpressionUseKind {
+    let hir = tcx.0.hir();
+    let mut place = use_;
+    while let Node::Expr(parent @ Expr { kind: ExprKind::Field(..), .. }) = hir.get(hir.get_parent_node(place.hir_id)) {
+        place = parent;
+    }
+
+    let body_id = hir.body_owned_by(hir.get_parent_item(use_.hir_id));
+    if !local_uses.iter().any(|(id, _)| *id == body_id) {
+        let uses = collect_local_uses(tcx.0, body_id).into_iter().map(|(_, span, use_kind)| (span, use_kind)).collect();
+        local_uses.push((body_id, uses));
+    }
+    let use_kinds = local_uses.iter()
+        .filter(|(id, _)| *id == body_id)
+        .flat_map(|(_, uses)| uses.iter())
+        .filter(|(span, _)| *span == place.span)
+        .map(|(_, use_kind)| *use_kind)
+        .collect::<Vec<_>>();
+
+    if use_kinds.iter().any(|k| k.is_mutated()) {
+        ExpressionUseKind::Mut
+    } else if use_kinds.iter().any(|k| k.is_moved()) {
+        ExpressionUseKind::Move
+    } else {
+        ExpressionUseKind::Copy
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::refactoring_invocation::RefactoringErrorInternal;
+    use crate::test_utils::{run_refactoring, TestInit};
+    use super::CANNOT_WRAP;
+    const NAME: &str = "wrap-field";
+
+    #[test]
+    fn ref_cell_read_and_mutated() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+fn foo() -> usize {
+    let mut s = S { field: vec![] };
+    s.field.push(1);
+    s.field.len()
+}"#;
+        let expected = Ok(r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: std::cell::RefCell<Vec<i32>> }
+fn foo() -> usize {
+    let mut s = S { field: std::cell::RefCell::new(vec![]) };
+    (*s.field.borrow_mut()).push(1);
+    (*s.field.borrow()).len()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("wrapper", "RefCell"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn cell_assigned() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: i32 }
+fn foo(s: &mut S) -> i32 {
+    s.field = 1;
+    s.field += 2;
+    s.field
+}"#;
+        let expected = Ok(r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: std::cell::Cell<i32> }
+fn foo(s: &mut S) -> i32 {
+    s.field.set(1);
+    s.field.set(s.field.get() + 2);
+    s.field.get()
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("wrapper", "Cell"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn rc_mutated() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+fn foo(s: &mut S) {
+    s.field.push(1);
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, "the field is mutated, which Rc does not allow"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("wrapper", "Rc"));
+        assert_eq!(actual, expected);
+    }    #[test]
+    fn ref_cell_reference_returned() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+impl S {
+    fn get(&self) -> &Vec<i32> {
+        &self.field
+    }
+}"#;
+        let expected = Err(RefactoringErrorInternal::not_supported(CANNOT_WRAP, "a reference to the field is kept after the statement, which RefCell does not allow"));
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("wrapper", "RefCell"));
+        assert_eq!(actual, expected);
+    }
+    #[test]
+    fn mutex_reference_used_in_statement() {
+        let input = r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: Vec<i32> }
+fn foo(s: &S) -> usize {
+    let n = s.field.iter().count();
+    n
+}"#;
+        let expected = Ok(r#"struct S { /*refactor-tool:test-id:start*/field/*refactor-tool:test-id:end*/: std::sync::Mutex<Vec<i32>> }
+fn foo(s: &S) -> usize {
+    let n = (*s.field.lock().unwrap()).iter().count();
+    n
+}"#.to_owned());
+
+        let actual = run_refactoring(TestInit::from_refactoring(input, NAME).with_option("wrapper", "Mutex"));
+        assert_eq!(actual, expected);
+    }
+}
@@ -44,7 +44,8 @@ pub(crate) struct TestInit {
     program: String,
     refactoring: String,
     selection_type: SelectionType,
-    with_changes: Vec<Vec<FileStringReplacement>>
+    with_changes: Vec<Vec<FileStringReplacement>>,
+    options: Vec<(String, String)>
 }
 impl TestInit {
     pub fn from_refactoring(program: &str, refactoring: &str) -> Self {
@@ -53,7 +54,8 @@ impl TestInit {
             program: program.to_string(),
             refactoring: refactoring.to_string(),
             selection_type: SelectionType::Comment("test-id".to_string()),
-            with_changes: vec![]
+            with_changes: vec![],
+            options: vec![]
         }
     }
     pub fn with_add_comment(&self) -> Self {
@@ -66,6 +68,11 @@ impl TestInit {
         ret.with_changes = changes;
         ret
     }
+    pub fn with_option(&self, name: &str, value: &str) -> Self {
+        let mut ret = self.clone();
+        ret.options.push((name.to_owned(), value.to_owned()));
+        ret
+    }
 }
 pub(crate) fn run_refactoring(init: TestInit) -> QueryResult<String>  {
     let (rustc_args, d) = init_main_rs_and_get_args(&init.program);
@@ -77,7 +84,8 @@ pub(crate) fn run_refactoring(init: TestInit) -> QueryResult<String>  {
             unsafe_: false,
             deps: vec![],
             add_comment: init.add_comment,
-            with_changes: init.with_changes
+            with_changes: init.with_changes,
+            options: init.options
         }
     )?;
 
@@ -1,4 +1,4 @@
-import { Range, TextDocument } from 'vscode-languageserver';
+import { Position, Range, TextDocument } from 'vscode-languageserver';
 
 export class ByteRange {
     constructor(public start: Number, public end: Number) { }
@@ -24,6 +24,12 @@ export class ByteRange {
             return this.Null();
         if (range.start.character === range.end.character && range.start.line === range.end.line)
             return this.Empty();
-        return new ByteRange(doc.offsetAt(range.start), doc.offsetAt(range.end));
+        return new ByteRange(byteOffsetAt(doc, range.start), byteOffsetAt(doc, range.end));
     }
 }
+
+/**
+ * offsetAt counts UTF-16 code units, but the selection passed to the refactoring tool is in UTF-8 bytes
+ */
+const byteOffsetAt = (doc: TextDocument, position: Position): number =>
+    Buffer.byteLength(doc.getText(Range.create(Position.create(0, 0), position)), 'utf8');
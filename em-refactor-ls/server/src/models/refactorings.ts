@@ -1,15 +1,27 @@
 const microRefactorings = [
+    "change-signature",
     "close-over-variables",
     "convert-closure-to-function",
+    "convert-to-associated-function",
+    "convert-to-method",
     "extract-block",
+    "extract-variable",
+    "inline-function",
+    "inline-local",
     "inline-macro",
     "introduce-closure",
     "lift-function-declaration",
-    "pull-up-item-declaration"
+    "move-item",
+    "pull-up-item-declaration",
+    "rename",
+    "split-conflicting-match-arms"
 ];
 const compositeRefactorings = [
     "box-field",
+    "encapsulate-field",
     "extract-method",
+    "unbox-field",
+    "wrap-field",
 ];
 
 export const listRefactorings = (isMicroRefactoringsShown: boolean) => {
@@ -0,0 +1,198 @@
+use crate::text_document::*;
+use em_refactor_lib_types::{defs::{refactorings, refactorings_with_required_options}, FileStringReplacement, RefactorOutputs2};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+pub(crate) static REFACTOR_COMMAND: &str = "em-refactor.refactor";
+
+///
+/// Stored in the `data` field of a code action (or as the command argument) until the refactoring is run
+///
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct RefactorCommandArgs {
+    pub uri: String,
+    pub refactoring: String,
+    pub selection: String
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub(crate) struct TextEdit {
+    pub range: Range,
+    #[serde(rename = "newText")]
+    pub new_text: String
+}
+
+///
+/// Lists one code action per refactoring for a non-empty selection.
+/// The edit is computed when the code action is resolved, or when the command is executed if the client cannot resolve code actions.
+/// The refactorings which require options are not listed, since a code action has no options.
+///
+pub(crate) fn list_code_actions(uri: &str, text: &str, range: Range, resolve_support: bool) -> Vec<Value> {
+    if range.is_empty() {
+        return vec![];
+    }
+    let selection = format!("{}:{}", position_to_byte_offset(text, range.start), position_to_byte_offset(text, range.end));
+
+    code_action_refactorings().into_iter().map(|refactoring| {
+        let args = RefactorCommandArgs {
+            uri: uri.to_string(),
+            refactoring: refactoring.to_string(),
+            selection: selection.to_string()
+        };
+        let title = format!("Refactor - {}", refactoring);
+        if resolve_support {
+            json!({
+                "title": title,
+                "kind": "refactor",
+                "data": args
+            })
+        } else {
+            json!({
+                "title": title,
+                "kind": "refactor",
+                "command": {
+                    "title": title,
+                    "command": REFACTOR_COMMAND,
+                    "arguments": [args]
+                }
+            })
+        }
+    }).collect()
+}
+
+fn code_action_refactorings() -> Vec<&'static str> {
+    let with_required_options = refactorings_with_required_options();
+    refactorings().into_iter().filter(|refactoring| !with_required_options.contains(refactoring)).collect()
+}
+
+///
+/// Maps the output of a refactoring to a WorkspaceEdit, or the first error if the refactoring failed
+///
+pub(crate) fn to_workspace_edit(workspace_root: &Path, output: &RefactorOutputs2) -> Result<Value, String> {
+    if let Some(error) = output.errors.first() {
+        return Err(error.message.to_string());
+    }
+    let file_names = output.changes.iter().flatten().map(|c| c.file_name.as_str()).collect::<BTreeSet<_>>();
+
+    let mut changes = serde_json::Map::new();
+    for file_name in file_names {
+        let path = workspace_root.join(file_name);
+        let text = std::fs::read_to_string(&path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        let path = path.to_str().ok_or_else(|| format!("invalid path: {}", path.display()))?;
+
+        changes.insert(path_to_uri(path), json!(text_edits(&text, file_name, &output.changes)));
+    }
+    Ok(json!({ "changes": changes }))
+}
+
+///
+/// The changes of a composite refactoring are given for each step, where the offsets of a step
+/// refer to the file after the previous steps were applied. Only the changes of a single step
+/// are mapped to separate edits, otherwise the whole file is replaced.
+///
+fn text_edits(text: &str, file_name: &str, changes: &[Vec<FileStringReplacement>]) -> Vec<TextEdit> {
+    if let [step] = changes {
+        return step.iter()
+            .filter(|c| c.file_name == file_name)
+            .map(|c| TextEdit {
+                range: Range {
+                    start: byte_offset_to_position(text, c.byte_start as usize),
+                    end: byte_offset_to_position(text, c.byte_end as usize)
+                },
+                new_text: c.replacement.to_string()
+            })
+            .collect();
+    }
+    let mut content = text.to_string();
+    for step in changes {
+        // The changes of each step are sorted by descending byte_start
+        for change in step.iter().filter(|c| c.file_name == file_name) {
+            content.replace_range(change.byte_start as usize..change.byte_end as usize, &change.replacement);
+        }
+    }
+    vec![TextEdit {
+        range: full_range(text),
+        new_text: content
+    }]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn replacement(file_name: &str, byte_start: u32, byte_end: u32, replacement: &str) -> FileStringReplacement {
+        FileStringReplacement {
+            file_name: file_name.to_owned(),
+            line_start: 0,
+            char_start: 0,
+            line_end: 0,
+            char_end: 0,
+            byte_start,
+            byte_end,
+            replacement: replacement.to_owned()
+        }
+    }
+    fn pos(line: usize, character: usize) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn no_code_actions_for_empty_selection() {
+        let range = Range { start: pos(0, 1), end: pos(0, 1) };
+
+        assert!(list_code_actions("file:///main.rs", "fn main() {}", range, true).is_empty());
+    }
+    #[test]
+    fn code_action_selection_is_in_bytes() {
+        let range = Range { start: pos(0, 4), end: pos(0, 5) };
+
+        let actions = list_code_actions("file:///main.rs", "let ø = 1;", range, true);
+        let args: RefactorCommandArgs = serde_json::from_value(actions[0]["data"].clone()).unwrap();
+
+        assert_eq!(actions.len(), code_action_refactorings().len());
+        assert_eq!(args.selection, "4:6");
+    }
+    #[test]
+    fn no_code_action_requires_options() {
+        let range = Range { start: pos(0, 0), end: pos(0, 2) };
+
+        let actions = list_code_actions("file:///main.rs", "fn main() {}", range, true);
+
+        for action in actions {
+            let args: RefactorCommandArgs = serde_json::from_value(action["data"].clone()).unwrap();
+            assert!(!refactorings_with_required_options().contains(&args.refactoring.as_str()), "{}", args.refactoring);
+        }
+    }
+    #[test]
+    fn single_step_is_mapped_to_separate_edits() {
+        let text = "struct S { ø: i32, f: i32 }";
+        let changes = vec![vec![
+            replacement("src/lib.rs", 23, 26, "Box<i32>"),
+            replacement("src/main.rs", 0, 0, "")
+        ]];
+
+        let actual = text_edits(text, "src/lib.rs", &changes);
+
+        assert_eq!(actual, vec![TextEdit {
+            range: Range { start: pos(0, 22), end: pos(0, 25) },
+            new_text: "Box<i32>".to_owned()
+        }]);
+    }
+    #[test]
+    fn multiple_steps_replace_the_file() {
+        let text = "fn f() {\n    1\n}";
+        let changes = vec![
+            vec![replacement("src/main.rs", 13, 14, "{1}")],
+            vec![replacement("src/main.rs", 13, 16, "2"), replacement("src/main.rs", 0, 0, "// ")],
+        ];
+
+        let actual = text_edits(text, "src/main.rs", &changes);
+
+        assert_eq!(actual, vec![TextEdit {
+            range: Range { start: pos(0, 0), end: pos(2, 1) },
+            new_text: "// fn f() {\n    2\n}".to_owned()
+        }]);
+    }
+}
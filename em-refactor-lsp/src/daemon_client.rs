@@ -0,0 +1,55 @@
+use em_refactor_lib_types::{DaemonRequest, RefactorOutputs2};
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+static CLI_NAME: &str = "cargo-em-refactor";
+
+///
+/// Runs `cargo-em-refactor daemon` in the workspace root, so that the rustc invocations are only captured once
+///
+pub(crate) struct DaemonClient {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>
+}
+
+impl DaemonClient {
+    pub(crate) fn start(workspace_root: &str) -> io::Result<Self> {
+        let mut path = std::env::current_exe()?.with_file_name(CLI_NAME);
+        if cfg!(windows) {
+            path.set_extension("exe");
+        }
+        let mut child = Command::new(path)
+            .arg(format!("--workspace-root={}", workspace_root))
+            .arg("daemon")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let stdout = BufReader::new(child.stdout.take().unwrap());
+
+        Ok(Self {
+            child,
+            stdin,
+            stdout
+        })
+    }
+
+    pub(crate) fn request(&mut self, request: &DaemonRequest) -> io::Result<RefactorOutputs2> {
+        writeln!(self.stdin, "{}", serde_json::to_string(request)?)?;
+        self.stdin.flush()?;
+
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the refactoring daemon exited"));
+        }
+        Ok(serde_json::from_str(&line)?)
+    }
+
+    pub(crate) fn shutdown(mut self) -> io::Result<()> {
+        writeln!(self.stdin, "{}", serde_json::to_string(&DaemonRequest::Shutdown)?)?;
+        self.stdin.flush()?;
+        self.child.wait()?;
+        Ok(())
+    }
+}
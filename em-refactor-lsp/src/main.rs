@@ -0,0 +1,200 @@
+use code_actions::*;
+use daemon_client::DaemonClient;
+use em_refactor_lib_types::{DaemonRequest, SelectionType};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+use text_document::*;
+use transport::*;
+
+mod code_actions;
+mod daemon_client;
+mod text_document;
+mod transport;
+
+const INVALID_PARAMS: i64 = -32602;
+const METHOD_NOT_FOUND: i64 = -32601;
+const REQUEST_FAILED: i64 = -32803;
+
+///
+/// Language server (LSP over stdio) which offers the refactorings as code actions.
+///
+/// The refactorings are run by `cargo-em-refactor daemon`, which is expected to be in the same directory as this binary.
+/// Refactorings are only offered for documents where the editor content is the same as the file on disk,
+/// as the refactorings operate on the files on disk.
+///
+pub fn main() {
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+
+    let result = Server::new().run(&mut stdin.lock(), &mut stdout.lock());
+
+    if let Err(err) = result {
+        eprint!("{}", err);
+        std::process::exit(-1);
+    }
+}
+
+struct Server {
+    workspace_root: PathBuf,
+    documents: HashMap<String, String>,
+    resolve_support: bool,
+    daemon: Option<DaemonClient>,
+    next_request_id: u64
+}
+
+impl Server {
+    fn new() -> Self {
+        Self {
+            workspace_root: PathBuf::from("."),
+            documents: HashMap::new(),
+            resolve_support: false,
+            daemon: None,
+            next_request_id: 0
+        }
+    }
+
+    fn run(&mut self, input: &mut impl BufRead, output: &mut impl Write) -> io::Result<()> {
+        while let Some(message) = read_message(input)? {
+            let method = match message["method"].as_str() {
+                Some(method) => method,
+                None => continue // response to a request sent by the server
+            };
+            let params = &message["params"];
+
+            if let Some(id) = message.get("id") {
+                let response = match self.handle_request(method, params, output) {
+                    Ok(result) => response(id, result),
+                    Err((code, message)) => error_response(id, code, &message)
+                };
+                write_message(output, &response)?;
+            } else if method == "exit" {
+                break;
+            } else {
+                self.handle_notification(method, params);
+            }
+        }
+        if let Some(daemon) = self.daemon.take() {
+            daemon.shutdown()?;
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, method: &str, params: &Value, output: &mut impl Write) -> Result<Value, (i64, String)> {
+        match method {
+            "initialize" => Ok(self.initialize(params)),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/codeAction" => {
+                let uri = params["textDocument"]["uri"].as_str().ok_or_else(invalid_params)?;
+                let range = serde_json::from_value::<Range>(params["range"].clone()).map_err(|_| invalid_params())?;
+
+                Ok(json!(self.code_actions(uri, range)))
+            },
+            "codeAction/resolve" => {
+                let args = serde_json::from_value::<RefactorCommandArgs>(params["data"].clone()).map_err(|_| invalid_params())?;
+                let mut code_action = params.clone();
+                code_action["edit"] = self.refactor(&args)?;
+
+                Ok(code_action)
+            },
+            "workspace/executeCommand" if params["command"] == REFACTOR_COMMAND => {
+                let args = serde_json::from_value::<RefactorCommandArgs>(params["arguments"][0].clone()).map_err(|_| invalid_params())?;
+                let edit = self.refactor(&args)?;
+
+                self.next_request_id += 1;
+                let apply_edit = request(self.next_request_id, "workspace/applyEdit", json!({
+                    "label": args.refactoring,
+                    "edit": edit
+                }));
+                write_message(output, &apply_edit).map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+
+                Ok(Value::Null)
+            },
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method)))
+        }
+    }
+
+    fn handle_notification(&mut self, method: &str, params: &Value) {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default().to_string();
+                self.documents.insert(uri, text);
+            },
+            "textDocument/didChange" => {
+                // Full document sync, so the last change contains the whole document
+                if let Some(text) = params["contentChanges"].as_array().and_then(|c| c.last()).and_then(|c| c["text"].as_str()) {
+                    self.documents.insert(uri, text.to_string());
+                }
+            },
+            "textDocument/didClose" => {
+                self.documents.remove(&uri);
+            },
+            _ => {}
+        }
+    }
+
+    fn initialize(&mut self, params: &Value) -> Value {
+        if let Some(root) = params["rootUri"].as_str().and_then(uri_to_path).or_else(|| params["rootPath"].as_str().map(|s| s.to_string())) {
+            self.workspace_root = PathBuf::from(root);
+        }
+        self.resolve_support = params["capabilities"]["textDocument"]["codeAction"]["resolveSupport"]["properties"]
+            .as_array()
+            .map(|props| props.iter().any(|p| p == "edit"))
+            .unwrap_or(false);
+
+        json!({
+            "capabilities": {
+                "textDocumentSync": 1,
+                "codeActionProvider": {
+                    "codeActionKinds": ["refactor"],
+                    "resolveProvider": self.resolve_support
+                },
+                "executeCommandProvider": {
+                    "commands": [REFACTOR_COMMAND]
+                }
+            },
+            "serverInfo": {
+                "name": "em-refactor-lsp"
+            }
+        })
+    }
+
+    fn code_actions(&self, uri: &str, range: Range) -> Vec<Value> {
+        let text = match uri_to_path(uri).and_then(|path| std::fs::read_to_string(path).ok()) {
+            Some(text) => text,
+            None => return vec![]
+        };
+        if self.documents.get(uri).map(|doc| doc != &text).unwrap_or(false) {
+            return vec![];
+        }
+        list_code_actions(uri, &text, range, self.resolve_support)
+    }
+
+    fn refactor(&mut self, args: &RefactorCommandArgs) -> Result<Value, (i64, String)> {
+        let path = uri_to_path(&args.uri).ok_or_else(invalid_params)?;
+        let file = PathBuf::from(&path).strip_prefix(&self.workspace_root)
+            .map(|p| p.to_string_lossy().to_string())
+            .map_err(|_| (REQUEST_FAILED, format!("{} is not in the workspace", path)))?;
+
+        if self.daemon.is_none() {
+            let root = self.workspace_root.to_string_lossy().to_string();
+            self.daemon = Some(DaemonClient::start(&root).map_err(|e| (REQUEST_FAILED, e.to_string()))?);
+        }
+        let request = DaemonRequest::Refactor {
+            refactoring: args.refactoring.to_string(),
+            file,
+            selection: SelectionType::Range(args.selection.to_string()),
+            unsafe_: false,
+            options: vec![]
+        };
+        let output = self.daemon.as_mut().unwrap().request(&request).map_err(|e| (REQUEST_FAILED, e.to_string()))?;
+
+        to_workspace_edit(&self.workspace_root, &output).map_err(|e| (REQUEST_FAILED, e))
+    }
+}
+
+fn invalid_params() -> (i64, String) {
+    (INVALID_PARAMS, "Invalid params".to_owned())
+}
@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+///
+/// LSP position, where `character` is counted in UTF-16 code units
+///
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Position {
+    pub line: usize,
+    pub character: usize
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub(crate) struct Range {
+    pub start: Position,
+    pub end: Position
+}
+
+impl Range {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+///
+/// Maps a position to a byte offset in `text`. Positions past the end of a line are clamped to the end of that line
+///
+pub(crate) fn position_to_byte_offset(text: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match text[line_start..].find('\n') {
+            Some(i) => line_start += i + 1,
+            None => return text.len()
+        }
+    }
+    let mut utf16_col = 0;
+    for (i, c) in text[line_start..].char_indices() {
+        if utf16_col >= position.character || c == '\n' {
+            return line_start + i;
+        }
+        utf16_col += c.len_utf16();
+    }
+    text.len()
+}
+
+pub(crate) fn byte_offset_to_position(text: &str, byte_offset: usize) -> Position {
+    let before = &text[..byte_offset];
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+
+    Position {
+        line: before.matches('\n').count(),
+        character: before[line_start..].encode_utf16().count()
+    }
+}
+
+///
+/// The range covering all of `text`
+///
+pub(crate) fn full_range(text: &str) -> Range {
+    Range {
+        start: Position { line: 0, character: 0 },
+        end: byte_offset_to_position(text, text.len())
+    }
+}
+
+///
+/// Converts a `file://` uri to a path, decoding any percent-encoded characters
+///
+pub(crate) fn uri_to_path(uri: &str) -> Option<String> {
+    if !uri.starts_with("file://") {
+        return None;
+    }
+    let encoded = uri["file://".len()..].as_bytes();
+    let mut decoded = vec![];
+    let mut i = 0;
+    while i < encoded.len() {
+        if encoded[i] == b'%' && i + 2 < encoded.len() {
+            let hex = std::str::from_utf8(&encoded[i + 1..i + 3]).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(encoded[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+pub(crate) fn path_to_uri(path: &str) -> String {
+    let mut uri = "file://".to_owned();
+    for b in path.bytes() {
+        match b {
+            b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => uri.push(b as char),
+            _ => uri.push_str(&format!("%{:02X}", b))
+        }
+    }
+    uri
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pos(line: usize, character: usize) -> Position {
+        Position { line, character }
+    }
+
+    #[test]
+    fn position_to_byte_offset_ascii() {
+        let text = "fn main() {\n    foo();\n}";
+
+        assert_eq!(position_to_byte_offset(text, pos(0, 0)), 0);
+        assert_eq!(position_to_byte_offset(text, pos(1, 4)), 16);
+        assert_eq!(position_to_byte_offset(text, pos(1, 100)), 22);
+        assert_eq!(position_to_byte_offset(text, pos(2, 1)), 24);
+        assert_eq!(position_to_byte_offset(text, pos(5, 0)), 24);
+    }
+    #[test]
+    fn position_to_byte_offset_multibyte() {
+        // 'ø' is 2 bytes in UTF-8 and 1 code unit in UTF-16, '𝄞' is 4 bytes and 2 code units
+        let text = "let ø = \"𝄞\";";
+
+        assert_eq!(position_to_byte_offset(text, pos(0, 5)), 6);
+        assert_eq!(position_to_byte_offset(text, pos(0, 11)), 14);
+    }
+    #[test]
+    fn byte_offset_to_position_roundtrip() {
+        let text = "fn ø() {\n    \"𝄞\";\n}";
+        for (i, _) in text.char_indices() {
+            assert_eq!(position_to_byte_offset(text, byte_offset_to_position(text, i)), i);
+        }
+    }
+    #[test]
+    fn full_range_of_text() {
+        assert_eq!(full_range("a\nbø"), Range { start: pos(0, 0), end: pos(1, 2) });
+    }
+    #[test]
+    fn uri_roundtrip() {
+        let path = "/home/user/my crate/src/main.rs";
+        let uri = path_to_uri(path);
+
+        assert_eq!(uri, "file:///home/user/my%20crate/src/main.rs");
+        assert_eq!(uri_to_path(&uri), Some(path.to_owned()));
+    }
+}
@@ -0,0 +1,88 @@
+use serde_json::{json, Value};
+use std::io::{self, BufRead, Write};
+
+static CONTENT_LENGTH: &str = "Content-Length:";
+
+///
+/// Reads a single message on the format `Content-Length: <n>\r\n\r\n<json>`
+/// Returns None when the input is closed
+///
+pub(crate) fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+    loop {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if line.starts_with(CONTENT_LENGTH) {
+            content_length = line[CONTENT_LENGTH.len()..].trim().parse::<usize>().ok();
+        }
+    }
+    let content_length = content_length
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "missing Content-Length header"))?;
+
+    let mut content = vec![0; content_length];
+    input.read_exact(&mut content)?;
+
+    serde_json::from_slice(&content)
+        .map(Some)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+}
+
+pub(crate) fn write_message(output: &mut impl Write, message: &Value) -> io::Result<()> {
+    let content = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", content.len(), content)?;
+    output.flush()
+}
+
+pub(crate) fn response(id: &Value, result: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "result": result
+    })
+}
+
+pub(crate) fn error_response(id: &Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": {
+            "code": code,
+            "message": message
+        }
+    })
+}
+
+pub(crate) fn request(id: u64, method: &str, params: Value) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "method": method,
+        "params": params
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn read_written_message() {
+        let message = request(1, "shutdown", json!(null));
+        let mut buf = vec![];
+        write_message(&mut buf, &message).unwrap();
+
+        let actual = read_message(&mut buf.as_slice()).unwrap();
+
+        assert_eq!(actual, Some(message));
+    }
+    #[test]
+    fn read_closed_input() {
+        assert_eq!(read_message(&mut "".as_bytes()).unwrap(), None);
+    }
+}